@@ -0,0 +1,96 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! The accent color chosen in the operating system's settings, for the chrome to follow.
+
+use std::sync::LazyLock;
+
+use egui::Color32;
+
+/// The operating system's accent color, which is only read once.
+static ACCENT_COLOR: LazyLock<Option<Color32>> = LazyLock::new(os_accent_color);
+
+/// Returns the operating system's accent color, or None if it can't be read.
+pub fn system_accent_color() -> Option<Color32> {
+    *ACCENT_COLOR
+}
+
+#[cfg(target_os = "linux")]
+fn os_accent_color() -> Option<Color32> {
+    use std::process::Command;
+
+    // GNOME 47 and later offer a fixed set of named accent colors.
+    let output = Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.interface", "accent-color"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let color = match String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .trim_matches('\'')
+    {
+        "blue" => Color32::from_rgb(0x35, 0x84, 0xe4),
+        "teal" => Color32::from_rgb(0x21, 0x90, 0xa4),
+        "green" => Color32::from_rgb(0x3a, 0x94, 0x4a),
+        "yellow" => Color32::from_rgb(0xc8, 0x88, 0x00),
+        "orange" => Color32::from_rgb(0xed, 0x5b, 0x00),
+        "red" => Color32::from_rgb(0xe6, 0x2d, 0x42),
+        "pink" => Color32::from_rgb(0xd5, 0x61, 0x99),
+        "purple" => Color32::from_rgb(0x91, 0x41, 0xac),
+        "slate" => Color32::from_rgb(0x6f, 0x83, 0x96),
+        _ => return None,
+    };
+    Some(color)
+}
+
+#[cfg(target_os = "macos")]
+fn os_accent_color() -> Option<Color32> {
+    use std::process::Command;
+
+    let output = Command::new("defaults")
+        .args(["read", "-g", "AppleAccentColor"])
+        .output()
+        .ok()?;
+    // The setting only exists once the user has chosen something other than the default, blue.
+    let accent = if output.status.success() {
+        String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse()
+            .ok()?
+    } else {
+        4
+    };
+    let color = match accent {
+        -1 => Color32::from_rgb(0x8c, 0x8c, 0x8c),
+        0 => Color32::from_rgb(0xff, 0x52, 0x57),
+        1 => Color32::from_rgb(0xf7, 0x82, 0x1b),
+        2 => Color32::from_rgb(0xff, 0xc6, 0x00),
+        3 => Color32::from_rgb(0x62, 0xba, 0x46),
+        4 => Color32::from_rgb(0x00, 0x7a, 0xff),
+        5 => Color32::from_rgb(0xa5, 0x50, 0xa7),
+        6 => Color32::from_rgb(0xf7, 0x4f, 0x9e),
+        _ => return None,
+    };
+    Some(color)
+}
+
+#[cfg(target_os = "windows")]
+fn os_accent_color() -> Option<Color32> {
+    use windows_sys::Win32::Graphics::Dwm::DwmGetColorizationColor;
+
+    let mut color = 0u32;
+    let mut opaque = 0;
+    let result = unsafe { DwmGetColorizationColor(&mut color, &mut opaque) };
+    if result != 0 {
+        return None;
+    }
+    // The color is 0xAARRGGBB, and the alpha is only for window frames, so ignore it.
+    let [_, r, g, b] = color.to_be_bytes();
+    Some(Color32::from_rgb(r, g, b))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn os_accent_color() -> Option<Color32> {
+    None
+}
@@ -6,16 +6,19 @@
 
 use std::cell::{Cell, RefCell, RefMut};
 use std::collections::HashMap;
+use std::path::Path;
 use std::rc::Rc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use std::{env, fs};
 
 use gleam::gl;
 use log::{info, trace, warn};
+use servo::base::id::WebViewId;
 use servo::compositing::windowing::EmbedderEvent;
 use servo::compositing::CompositeTarget;
 use servo::config::{opts, set_pref};
 use servo::servo_config::pref;
+use servo::servo_url::ServoUrl;
 use servo::Servo;
 use surfman::GLApi;
 use webxr::glwindow::GlWindowDiscovery;
@@ -25,8 +28,13 @@ use winit::event::WindowEvent;
 use winit::event_loop::EventLoopWindowTarget;
 use winit::window::WindowId;
 
+use super::automation::Automation;
+use super::db::config_dir;
 use super::events_loop::{EventsLoop, WakerEvent};
 use super::minibrowser::Minibrowser;
+use super::moto_prefs;
+use super::navigation_log::clear_log;
+use super::session::{clear_session, mark_clean_shutdown};
 use super::webview::WebViewManager;
 use super::{headed_window, headless_window};
 use crate::desktop::embedder::{EmbedderCallbacks, XrDiscovery};
@@ -34,6 +42,10 @@ use crate::desktop::tracing::trace_winit_event;
 use crate::desktop::window_trait::WindowPortsMethods;
 use crate::parser::get_default_url;
 
+/// How often to check on an automation command that is waiting for something other than the
+/// event loop, such as a screenshot.
+const AUTOMATION_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
 pub struct App {
     servo: Option<Servo<dyn WindowPortsMethods>>,
     webviews: RefCell<WebViewManager<dyn WindowPortsMethods>>,
@@ -41,6 +53,8 @@ pub struct App {
     suspended: Cell<bool>,
     windows: HashMap<WindowId, Rc<dyn WindowPortsMethods>>,
     minibrowser: Option<RefCell<Minibrowser>>,
+    /// Reads commands from stdin, if running with `--automation`.
+    automation: Option<Automation>,
 }
 
 enum Present {
@@ -64,7 +78,9 @@ impl App {
         no_native_titlebar: bool,
         device_pixel_ratio_override: Option<f32>,
         user_agent: Option<String>,
-        url: Option<String>,
+        urls: Vec<String>,
+        app_mode: bool,
+        automation: bool,
     ) {
         let events_loop = EventsLoop::new(opts::get().headless, opts::get().output_file.is_some())
             .expect("Failed to create events loop");
@@ -87,10 +103,17 @@ impl App {
         };
 
         // Handle browser state.
-        let webviews = WebViewManager::new(window.clone());
-        let initial_url = get_default_url(url.as_deref(), env::current_dir().unwrap(), |path| {
-            fs::metadata(path).is_ok()
-        });
+        let mut webviews = WebViewManager::new(window.clone());
+        webviews.set_app_mode(app_mode);
+        // The first URL replaces the initial page, and the rest open in tabs of their own.
+        let cwd = env::current_dir().unwrap();
+        let mut urls = urls
+            .iter()
+            .map(|url| get_default_url(Some(url), &cwd, |path| fs::metadata(path).is_ok()));
+        let initial_url = urls
+            .next()
+            .unwrap_or_else(|| get_default_url(None, &cwd, |path| fs::metadata(path).is_ok()));
+        let other_urls: Vec<ServoUrl> = urls.collect();
 
         let mut app = App {
             event_queue: RefCell::new(vec![]),
@@ -99,6 +122,8 @@ impl App {
             suspended: Cell::new(false),
             windows: HashMap::new(),
             minibrowser: None,
+            automation: automation
+                .then(|| Automation::start(events_loop.create_event_loop_waker())),
         };
 
         if opts::get().minibrowser && window.winit_window().is_some() {
@@ -116,7 +141,13 @@ impl App {
             debug_assert_eq!(webrender_gl.get_error(), gleam::gl::NO_ERROR);
 
             app.minibrowser = Some(
-                Minibrowser::new(&rendering_context, &events_loop, initial_url.clone()).into(),
+                Minibrowser::new(
+                    &rendering_context,
+                    &events_loop,
+                    initial_url.clone(),
+                    app_mode,
+                )
+                .into(),
             );
         }
 
@@ -186,6 +217,13 @@ impl App {
                 // Implements embedder methods, used by libservo and constellation.
                 let embedder = Box::new(EmbedderCallbacks::new(ev_waker.clone(), xr_discovery));
 
+                // Servo only reads these when it starts, so the devtools server can't be toggled
+                // while running.
+                if moto_prefs::get_bool("devtools.enabled") {
+                    set_pref!(devtools.server.enabled, true);
+                    set_pref!(devtools.server.port, moto_prefs::get_int("devtools.port"));
+                }
+
                 let composite_target = if app.minibrowser.is_some() {
                     CompositeTarget::Fbo
                 } else {
@@ -199,10 +237,16 @@ impl App {
                 );
                 let mut servo = servo_data.servo;
 
-                servo.handle_events(vec![EmbedderEvent::NewWebView(
+                let mut events = vec![EmbedderEvent::NewWebView(
                     initial_url.to_owned(),
                     servo_data.browser_id,
-                )]);
+                )];
+                events.extend(
+                    other_urls
+                        .iter()
+                        .map(|url| EmbedderEvent::NewWebView(url.clone(), WebViewId::new())),
+                );
+                servo.handle_events(events);
                 servo.setup_logging();
 
                 app.windows.insert(window.id(), window.clone());
@@ -263,6 +307,18 @@ impl App {
                         // the minibrowser, and present the new frame.
                         window.winit_window().unwrap().request_redraw();
                     },
+                    winit::event::Event::WindowEvent {
+                        event: WindowEvent::CloseRequested,
+                        ..
+                    } if minibrowser
+                        .should_confirm_quit(app.webviews.borrow().webviews().len()) =>
+                    {
+                        // Ask the user before closing multiple tabs, instead of letting the window
+                        // queue a Quit event.
+                        minibrowser.request_quit_confirmation();
+                        window.winit_window().unwrap().request_redraw();
+                        consumed = true;
+                    },
                     winit::event::Event::WindowEvent {
                         ref event,
                         window_id: _,
@@ -297,9 +353,35 @@ impl App {
 
             let animating = app.is_animating();
 
-            // Block until the window gets an event
+            // Snapshot the open tabs now and then, so they can be restored after a crash.
+            app.webviews.borrow_mut().autosave_session();
+            // Reload any tabs the user asked to reload now and then.
+            app.webviews.borrow_mut().auto_reload();
+            // Tell the user about any tabs that never opened.
+            if app.webviews.borrow_mut().check_opening_webviews() {
+                if let Some(window) = window.winit_window() {
+                    window.request_redraw();
+                }
+            }
+            let automation_busy = app.automation.as_ref().is_some_and(Automation::is_busy);
+            let deadline = {
+                let webviews = app.webviews.borrow();
+                let deadlines = [
+                    webviews.session_save_deadline(),
+                    webviews.auto_reload_deadline(),
+                    webviews.opening_webviews_deadline(),
+                    automation_busy.then(|| Instant::now() + AUTOMATION_POLL_INTERVAL),
+                ];
+                deadlines.into_iter().flatten().min()
+            };
+
+            // Block until the window gets an event, or it's time to save the session, reload a tab
+            // again, give up on a tab opening or check on an automation command
             if !animating || app.suspended.get() {
-                control_flow.set_wait();
+                match deadline {
+                    Some(deadline) => control_flow.set_wait_until(deadline),
+                    None => control_flow.set_wait(),
+                }
             } else {
                 control_flow.set_poll();
             }
@@ -318,6 +400,15 @@ impl App {
                     if let Some(mut minibrowser) = app.minibrowser() {
                         minibrowser.context.destroy();
                     }
+                    // Servo writes its cookies out as it shuts down, so this has to come after.
+                    clear_data_on_exit();
+                    // App windows don't save the session, so they leave the marker to the browser.
+                    let config_dir = config_dir();
+                    if !config_dir.is_empty() && !app.webviews.borrow().app_mode() {
+                        if let Err(e) = mark_clean_shutdown(&config_dir) {
+                            warn!("Failed to record clean shutdown ({})", e);
+                        }
+                    }
                 },
                 PumpResult::Continue { update, present } => {
                     if update {
@@ -375,6 +466,7 @@ impl App {
 
     fn is_animating(&self) -> bool {
         self.windows.iter().any(|(_, window)| window.is_animating())
+            || self.webviews.borrow().is_smooth_scrolling()
     }
 
     fn get_events(&self) -> Vec<EmbedderEvent> {
@@ -436,6 +528,9 @@ impl App {
         for window in self.windows.values() {
             embedder_events.extend(window.get_events());
         }
+        if let Some(automation) = &mut self.automation {
+            embedder_events.extend(automation.poll(&webviews));
+        }
 
         // Catch some keyboard events, and push the rest onto the WebViewManager event queue.
         webviews.handle_window_events(embedder_events);
@@ -494,3 +589,42 @@ impl App {
         self.minibrowser.as_ref().map(|x| x.borrow_mut())
     }
 }
+
+/// Delete whatever the `privacy.clear-on-exit.*` prefs ask not to keep between sessions.
+/// Bookmarks are always kept.
+fn clear_data_on_exit() {
+    if !moto_prefs::get_bool("privacy.clear-on-exit") {
+        return;
+    }
+
+    let mut files = vec![];
+    if let Some(servo_config_dir) = &opts::get().config_dir {
+        if moto_prefs::get_bool("privacy.clear-on-exit.cookies") {
+            files.push(servo_config_dir.join("cookie_jar.json"));
+            files.push(servo_config_dir.join("auth_cache.json"));
+        }
+        // Servo's HTTP cache only lives in memory. What it does keep between sessions is the list
+        // of sites that asked to only be loaded over HTTPS, which says where the user has been.
+        if moto_prefs::get_bool("privacy.clear-on-exit.cache") {
+            files.push(servo_config_dir.join("hsts_list.json"));
+        }
+    }
+    let config_dir = config_dir();
+    if moto_prefs::get_bool("privacy.clear-on-exit.history") && !config_dir.is_empty() {
+        files.push(Path::new(&config_dir).join("history.sqlite"));
+        // The open tabs and the navigation log are history too.
+        if let Err(e) = clear_session(&config_dir) {
+            warn!("Failed to remove the session snapshot ({})", e);
+        }
+        if let Err(e) = clear_log(&config_dir) {
+            warn!("Failed to remove the navigation log ({})", e);
+        }
+    }
+
+    for file in files.iter().filter(|file| file.exists()) {
+        info!("Clearing {}", file.display());
+        if let Err(e) = fs::remove_file(file) {
+            warn!("Failed to remove {} ({})", file.display(), e);
+        }
+    }
+}
@@ -0,0 +1,224 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Drives the browser from a stream of commands on stdin, for testing and automation.
+//!
+//! Enabled with `--automation`. Each line is one of:
+//! - `navigate <url>`
+//! - `reload`
+//! - `wait-for-load`
+//! - `load-status`
+//! - `screenshot <path>`
+//! - `quit`
+//!
+//! Commands run one at a time in the focused webview, and progress is reported on stdout.
+
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use log::warn;
+use servo::compositing::windowing::EmbedderEvent;
+use servo::embedder_traits::EventLoopWaker;
+use servo::ipc_channel::ipc::{self, IpcReceiver};
+use servo::net_traits::image::base::{Image, PixelFormat};
+use servo::script_traits::WebDriverCommandMsg;
+use servo::servo_url::ServoUrl;
+
+use super::webview::{checked_navigation_url, LoadStatus, WebViewManager};
+use super::window_trait::WindowPortsMethods;
+use crate::parser::location_bar_input_to_url;
+
+enum Command {
+    Navigate(ServoUrl),
+    Reload,
+    WaitForLoad,
+    LoadStatus,
+    Screenshot(PathBuf),
+    Quit,
+}
+
+fn parse_command(line: &str) -> Result<Command, String> {
+    let (name, argument) = match line.trim().split_once(char::is_whitespace) {
+        Some((name, argument)) => (name, argument.trim()),
+        None => (line.trim(), ""),
+    };
+    match (name, argument) {
+        ("navigate", url) if !url.is_empty() => location_bar_input_to_url(url)
+            .map(Command::Navigate)
+            .ok_or_else(|| format!("invalid URL {}", url)),
+        ("reload", "") => Ok(Command::Reload),
+        ("wait-for-load", "") => Ok(Command::WaitForLoad),
+        ("load-status", "") => Ok(Command::LoadStatus),
+        ("screenshot", path) if !path.is_empty() => Ok(Command::Screenshot(PathBuf::from(path))),
+        ("quit", "") => Ok(Command::Quit),
+        _ => Err(format!("unknown command {:?}", line)),
+    }
+}
+
+/// What the current command is waiting for before the next one can run.
+enum Waiting {
+    Nothing,
+    /// A load in the focused webview to complete.
+    Load,
+    /// Servo to take a screenshot to save to the given path.
+    Screenshot(PathBuf, IpcReceiver<Option<Image>>),
+}
+
+pub struct Automation {
+    commands: Receiver<String>,
+    waiting: Waiting,
+    /// The number of completed loads when the last navigation started, or None if there hasn't
+    /// been a navigation since the last wait.
+    navigated_at_load: Option<usize>,
+}
+
+impl Automation {
+    /// Start reading commands from stdin, waking the event loop whenever one arrives.
+    pub fn start(waker: Box<dyn EventLoopWaker>) -> Self {
+        let (sender, commands) = mpsc::channel();
+        thread::Builder::new()
+            .name("Automation".to_owned())
+            .spawn(move || {
+                for line in io::stdin().lock().lines() {
+                    let Ok(line) = line else { break };
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                    waker.wake();
+                }
+            })
+            .expect("Failed to spawn automation thread");
+        Self {
+            commands,
+            waiting: Waiting::Nothing,
+            // The initial page counts as a navigation, so the first wait is for it to load.
+            navigated_at_load: Some(0),
+        }
+    }
+
+    /// Returns true iff a command is waiting for something other than the event loop to finish.
+    pub fn is_busy(&self) -> bool {
+        !matches!(self.waiting, Waiting::Nothing)
+    }
+
+    /// Run as many commands as possible, returning the events to send to Servo.
+    pub fn poll(
+        &mut self,
+        webviews: &WebViewManager<dyn WindowPortsMethods>,
+    ) -> Vec<EmbedderEvent> {
+        let mut events = vec![];
+        loop {
+            if !self.finish_waiting(webviews) {
+                return events;
+            }
+            let line = match self.commands.try_recv() {
+                Ok(line) => line,
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => return events,
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            match parse_command(&line) {
+                Ok(command) => events.extend(self.run(command, webviews)),
+                Err(e) => println!("error: {}", e),
+            }
+        }
+    }
+
+    fn run(
+        &mut self,
+        command: Command,
+        webviews: &WebViewManager<dyn WindowPortsMethods>,
+    ) -> Option<EmbedderEvent> {
+        match (command, webviews.focused_webview_id()) {
+            (Command::Quit, _) => Some(EmbedderEvent::Quit),
+            (_, None) => {
+                println!("error: no webview");
+                None
+            },
+            (Command::Navigate(url), Some(webview_id)) => {
+                self.navigated_at_load = Some(webviews.completed_loads());
+                Some(EmbedderEvent::LoadUrl(
+                    webview_id,
+                    checked_navigation_url(url),
+                ))
+            },
+            (Command::Reload, Some(webview_id)) => {
+                self.navigated_at_load = Some(webviews.completed_loads());
+                Some(EmbedderEvent::Reload(webview_id))
+            },
+            (Command::WaitForLoad, Some(_)) => {
+                self.waiting = Waiting::Load;
+                None
+            },
+            (Command::LoadStatus, Some(_)) => {
+                let status = webviews.load_status().description().to_lowercase();
+                println!("load-status {}", status);
+                None
+            },
+            (Command::Screenshot(path), Some(webview_id)) => {
+                let (sender, receiver) = match ipc::channel() {
+                    Ok(channel) => channel,
+                    Err(e) => {
+                        println!("error: failed to take screenshot ({})", e);
+                        return None;
+                    },
+                };
+                self.waiting = Waiting::Screenshot(path, receiver);
+                Some(EmbedderEvent::WebDriverCommand(
+                    WebDriverCommandMsg::TakeScreenshot(webview_id, None, sender),
+                ))
+            },
+        }
+    }
+
+    /// Check whether the current command has finished, returning true iff the next one can run.
+    fn finish_waiting(&mut self, webviews: &WebViewManager<dyn WindowPortsMethods>) -> bool {
+        match &self.waiting {
+            Waiting::Nothing => return true,
+            Waiting::Load => {
+                let navigated = self
+                    .navigated_at_load
+                    .map_or(true, |loads| webviews.completed_loads() > loads);
+                if !navigated || webviews.load_status() != LoadStatus::LoadComplete {
+                    return false;
+                }
+                self.navigated_at_load = None;
+                let url = webviews.current_url_string().unwrap_or_default();
+                println!("loaded {}", url);
+            },
+            Waiting::Screenshot(path, receiver) => match receiver.try_recv() {
+                Err(ipc::TryRecvError::Empty) => return false,
+                Ok(Some(image)) => match save_screenshot(&image, path) {
+                    Ok(()) => println!("screenshot {}", path.display()),
+                    Err(e) => println!("error: failed to save screenshot ({})", e),
+                },
+                Ok(None) => println!("error: failed to take screenshot"),
+                Err(e) => {
+                    warn!("Failed to receive screenshot ({:?})", e);
+                    println!("error: failed to take screenshot");
+                },
+            },
+        }
+        self.waiting = Waiting::Nothing;
+        true
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+fn save_screenshot(image: &Image, path: &Path) -> Result<(), String> {
+    let PixelFormat::RGBA8 = image.format else {
+        return Err(format!("unsupported pixel format {:?}", image.format));
+    };
+    let buffer = image::RgbaImage::from_raw(image.width, image.height, image.bytes.to_vec())
+        .ok_or("image has the wrong size")?;
+    buffer.save(path).map_err(|e| e.to_string())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn save_screenshot(_image: &Image, _path: &Path) -> Result<(), String> {
+    Err("screenshots are not supported on this platform".to_owned())
+}
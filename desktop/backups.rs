@@ -0,0 +1,132 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Rolling backups of the databases in the config directory.
+//!
+//! Each startup copies a database to the `backups` subdirectory before it is migrated or used,
+//! keeping the last few copies, so a failed migration or a corrupted file doesn't lose the user's
+//! data.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How many backups of each database to keep.
+const MAX_BACKUPS: usize = 3;
+
+/// A copy of a database, taken when Moto started.
+#[derive(Clone)]
+pub struct Backup {
+    pub path: PathBuf,
+    pub created: SystemTime,
+}
+
+impl Backup {
+    /// Describes when the backup was taken, like "3 hours ago".
+    pub fn age(&self) -> String {
+        let seconds = self.created.elapsed().unwrap_or(Duration::ZERO).as_secs();
+        let (count, unit) = match seconds {
+            0..60 => return "just now".to_owned(),
+            60..3600 => (seconds / 60, "minute"),
+            3600..86400 => (seconds / 3600, "hour"),
+            _ => (seconds / 86400, "day"),
+        };
+        let plural = if count == 1 { "" } else { "s" };
+        format!("{} {}{} ago", count, unit, plural)
+    }
+}
+
+fn backups_dir(config_dir: &str) -> PathBuf {
+    Path::new(config_dir).join("backups")
+}
+
+/// Copy the given database, such as `bookmarks.sqlite`, to the backups directory, and remove all
+/// but the newest backups of it. Does nothing if the database doesn't exist yet.
+pub fn back_up_db(config_dir: &str, file_name: &str) -> io::Result<()> {
+    let source = Path::new(config_dir).join(file_name);
+    if !source.exists() {
+        return Ok(());
+    }
+    let dir = backups_dir(config_dir);
+    fs::create_dir_all(&dir)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs();
+    let stem = file_name.trim_end_matches(".sqlite");
+    copy_atomically(&source, &dir.join(format!("{}-{}.sqlite", stem, now)))?;
+
+    for backup in list_backups(config_dir, file_name)
+        .into_iter()
+        .skip(MAX_BACKUPS)
+    {
+        fs::remove_file(backup.path)?;
+    }
+    Ok(())
+}
+
+/// Returns the backups of the given database, newest first.
+pub fn list_backups(config_dir: &str, file_name: &str) -> Vec<Backup> {
+    let Ok(entries) = fs::read_dir(backups_dir(config_dir)) else {
+        return vec![];
+    };
+    let prefix = format!("{}-", file_name.trim_end_matches(".sqlite"));
+    let mut backups: Vec<Backup> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let name = path.file_name()?.to_str()?;
+            let seconds = name
+                .strip_prefix(&prefix)?
+                .strip_suffix(".sqlite")?
+                .parse()
+                .ok()?;
+            Some(Backup {
+                created: UNIX_EPOCH + Duration::from_secs(seconds),
+                path,
+            })
+        })
+        .collect();
+    backups.sort_by(|a, b| b.created.cmp(&a.created));
+    backups
+}
+
+/// Replace the given database with a backup of it.
+pub fn restore_backup(config_dir: &str, file_name: &str, backup: &Backup) -> io::Result<()> {
+    copy_atomically(&backup.path, &Path::new(config_dir).join(file_name))
+}
+
+/// Copy a file next to its destination and then move it into place, so the destination is never
+/// left half written.
+fn copy_atomically(from: &Path, to: &Path) -> io::Result<()> {
+    let mut temp = to.as_os_str().to_owned();
+    temp.push(".tmp");
+    fs::copy(from, &temp)?;
+    fs::rename(&temp, to)
+}
+
+#[test]
+fn test_back_up_db_keeps_newest_backups() {
+    let config_dir = std::env::temp_dir().join(format!("moto-backups-{}", std::process::id()));
+    let config_dir = config_dir.to_str().unwrap();
+    let _ = fs::remove_dir_all(config_dir);
+    fs::create_dir_all(backups_dir(config_dir)).unwrap();
+    fs::write(Path::new(config_dir).join("bookmarks.sqlite"), "current").unwrap();
+    for seconds in 1..=4 {
+        let path = backups_dir(config_dir).join(format!("bookmarks-{}.sqlite", seconds));
+        fs::write(path, seconds.to_string()).unwrap();
+    }
+
+    back_up_db(config_dir, "bookmarks.sqlite").unwrap();
+    let backups = list_backups(config_dir, "bookmarks.sqlite");
+    assert_eq!(backups.len(), MAX_BACKUPS);
+    assert_eq!(fs::read_to_string(&backups[0].path).unwrap(), "current");
+    assert_eq!(fs::read_to_string(&backups[1].path).unwrap(), "4");
+    assert_eq!(fs::read_to_string(&backups[2].path).unwrap(), "3");
+
+    restore_backup(config_dir, "bookmarks.sqlite", &backups[2]).unwrap();
+    let restored = fs::read_to_string(Path::new(config_dir).join("bookmarks.sqlite")).unwrap();
+    assert_eq!(restored, "3");
+    fs::remove_dir_all(config_dir).unwrap();
+}
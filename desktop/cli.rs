@@ -26,6 +26,17 @@ pub fn main() {
         "Do not shutdown until all threads have finished (macos only)",
     );
     opts.optflag("b", "no-native-titlebar", "Do not use native titlebar");
+    opts.optflag(
+        "",
+        "automation",
+        "Accept navigate, reload, wait-for-load, screenshot and quit commands on stdin",
+    );
+    opts.optopt(
+        "",
+        "app",
+        "Open the URL in a window of its own, without tabs or the location bar",
+        "https://example.com",
+    );
     opts.optopt("", "device-pixel-ratio", "Device pixels per px", "");
     opts.optopt(
         "u",
@@ -92,18 +103,21 @@ pub fn main() {
     });
 
     let user_agent = opts_matches.opt_str("u");
-
-    let url_opt = if !opts_matches.free.is_empty() {
-        Some(&opts_matches.free[0][..])
-    } else {
-        None
+    let automation = opts_matches.opt_present("automation");
+    let app_url = opts_matches.opt_str("app");
+    let app_mode = app_url.is_some();
+    let urls = match app_url {
+        Some(url) => vec![url],
+        None => opts_matches.free.clone(),
     };
 
     App::run(
         do_not_use_native_titlebar,
         device_pixel_ratio_override,
         user_agent,
-        url_opt.map(|s| s.to_string()),
+        urls,
+        app_mode,
+        automation,
     );
 
     crate::platform::deinit(clean_shutdown)
@@ -2,49 +2,574 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use std::env;
 use std::path::Path;
 
-use rusqlite::{Connection, Error};
+use directories::ProjectDirs;
+use rusqlite::{params, Connection, Error};
 
-use super::minibrowser::Bookmark;
+use super::minibrowser::{Bookmark, BookmarkFolder};
+
+/// The folder new bookmarks are added to. Bookmarks made before there were folders are moved into
+/// it when the DB is migrated.
+pub const UNSORTED_FOLDER_ID: i64 = 1;
+
+/// A visited page.
+pub struct HistoryEntry {
+    pub url: String,
+    pub title: String,
+    /// When the page was last visited, as an SQLite datetime in local time with milliseconds.
+    pub visited: String,
+}
+
+/// A page saved to read later.
+pub struct ReadingListItem {
+    pub url: String,
+    pub title: String,
+    /// When the item was added, as an SQLite datetime in UTC.
+    pub added: String,
+    pub read: bool,
+}
+
+/// Returns the directory where Moto keeps its user data. If the platform doesn't have one (see
+/// [has_persistent_config_dir]) this is a temporary directory, or an empty string if even that
+/// isn't valid UTF-8.
+pub fn config_dir() -> String {
+    if let Some(proj_dirs) = ProjectDirs::from("org", "Moto Browser", "Moto") {
+        String::from(proj_dirs.config_dir().to_str().unwrap_or(""))
+        // Linux:   /home/alice/.config/barapp
+        // Windows: C:\Users\Alice\AppData\Roaming\Foo Corp\Bar App
+        // macOS:   /Users/Alice/Library/Application Support/com.Foo-Corp.Bar-App
+    } else {
+        String::from(env::temp_dir().join("moto").to_str().unwrap_or(""))
+    }
+}
+
+/// Returns false if the platform has no config directory, so user data is only kept in a
+/// temporary one.
+pub fn has_persistent_config_dir() -> bool {
+    ProjectDirs::from("org", "Moto Browser", "Moto").is_some()
+}
 
 pub fn create_bookmarks_db(config_dir: &str) -> Result<(), Error> {
     let path = Path::new(&config_dir).join("bookmarks.sqlite");
     let conn = Connection::open(path)?;
     conn.execute(
-        "CREATE TABLE bookmarks (id INTEGER PRIMARY KEY, url TEXT, title TEXT)",
+        "CREATE TABLE bookmarks (id INTEGER PRIMARY KEY, url TEXT, title TEXT, notes TEXT, \
+         position INTEGER, folder INTEGER)",
         [],
     )?;
+    create_folders_table(&conn)
+}
+
+/// Create the table of bookmark folders, with just the Unsorted folder in it.
+fn create_folders_table(conn: &Connection) -> Result<(), Error> {
+    conn.execute(
+        "CREATE TABLE folders (id INTEGER PRIMARY KEY, name TEXT, parent INTEGER, icon TEXT, \
+         color TEXT)",
+        [],
+    )?;
+    conn.execute(
+        "INSERT INTO folders (id, name, parent) VALUES (?1, 'Unsorted', NULL)",
+        [UNSORTED_FOLDER_ID],
+    )?;
+    Ok(())
+}
+
+/// Bring a bookmarks DB created by an older version of Moto up to date.
+pub fn migrate_bookmarks_db(config_dir: &str) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("bookmarks.sqlite");
+    let conn = Connection::open(path)?;
+    let mut stmt = conn.prepare("SELECT name FROM pragma_table_info('bookmarks')")?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    if !columns.iter().any(|column| column == "notes") {
+        conn.execute("ALTER TABLE bookmarks ADD COLUMN notes TEXT", [])?;
+    }
+    if !columns.iter().any(|column| column == "position") {
+        // Keep existing bookmarks in the order they were added.
+        conn.execute("ALTER TABLE bookmarks ADD COLUMN position INTEGER", [])?;
+        conn.execute("UPDATE bookmarks SET position = id", [])?;
+    }
+    if !columns.iter().any(|column| column == "folder") {
+        conn.execute("ALTER TABLE bookmarks ADD COLUMN folder INTEGER", [])?;
+        conn.execute("UPDATE bookmarks SET folder = ?1", [UNSORTED_FOLDER_ID])?;
+    }
+    let has_folders_table = conn
+        .prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'folders'")?
+        .exists([])?;
+    if !has_folders_table {
+        create_folders_table(&conn)?;
+    }
+    let mut stmt = conn.prepare("SELECT name FROM pragma_table_info('folders')")?;
+    let folder_columns = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    if !folder_columns.iter().any(|column| column == "icon") {
+        conn.execute("ALTER TABLE folders ADD COLUMN icon TEXT", [])?;
+        conn.execute("ALTER TABLE folders ADD COLUMN color TEXT", [])?;
+    }
+    Ok(())
+}
+
+pub fn get_bookmarks(config_dir: &str) -> Result<Vec<Bookmark>, Error> {
+    let path = Path::new(&config_dir).join("bookmarks.sqlite");
+    let conn = Connection::open(path)?;
+    let mut stmt =
+        conn.prepare("SELECT id, url, title, notes, folder FROM bookmarks ORDER BY position, id")?;
+    let rows = stmt.query([])?;
+    rows.mapped(|row| {
+        let notes: Option<String> = row.get(3)?;
+        let folder: Option<i64> = row.get(4)?;
+        Ok(Bookmark {
+            id: row.get(0)?,
+            url: row.get(1)?,
+            title: row.get(2)?,
+            notes: notes.unwrap_or_default(),
+            folder: folder.unwrap_or(UNSORTED_FOLDER_ID),
+        })
+    })
+    .collect()
+}
+
+/// Returns the bookmark folders, sorted by name. Together with the folder of each bookmark, their
+/// parents describe the whole tree of bookmarks.
+pub fn get_bookmark_folders(config_dir: &str) -> Result<Vec<BookmarkFolder>, Error> {
+    let path = Path::new(&config_dir).join("bookmarks.sqlite");
+    let conn = Connection::open(path)?;
+    let mut stmt = conn.prepare(
+        "SELECT id, name, parent, icon, color FROM folders ORDER BY name COLLATE NOCASE, id",
+    )?;
+    let rows = stmt.query([])?;
+    rows.mapped(|row| {
+        Ok(BookmarkFolder {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            parent: row.get(2)?,
+            icon: row.get(3)?,
+            color: row.get(4)?,
+        })
+    })
+    .collect()
+}
+
+/// Create a bookmark folder in the given one, or at the top level, returning its id.
+pub fn add_folder(
+    config_dir: &str,
+    name: &str,
+    parent: Option<i64>,
+    icon: Option<&str>,
+    color: Option<&str>,
+) -> Result<i64, Error> {
+    let path = Path::new(&config_dir).join("bookmarks.sqlite");
+    let conn = Connection::open(path)?;
+    conn.execute(
+        "INSERT INTO folders (name, parent, icon, color) VALUES (?1, ?2, ?3, ?4)",
+        params![name, parent, icon, color],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Change the name, icon and color of the bookmark folder with the given id.
+pub fn update_folder(
+    config_dir: &str,
+    id: i64,
+    name: &str,
+    icon: Option<&str>,
+    color: Option<&str>,
+) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("bookmarks.sqlite");
+    let conn = Connection::open(path)?;
+    conn.execute(
+        "UPDATE folders SET name = ?2, icon = ?3, color = ?4 WHERE id = ?1",
+        params![id, name, icon, color],
+    )?;
+    Ok(())
+}
+
+/// Move the bookmark with the given id into the given folder.
+pub fn move_bookmark(config_dir: &str, id: i64, folder: i64) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("bookmarks.sqlite");
+    let conn = Connection::open(path)?;
+    // It goes at the end of the new folder, since positions are only compared within one.
+    conn.execute(
+        "UPDATE bookmarks SET folder = ?2, \
+         position = (SELECT COALESCE(MAX(position), 0) + 1 FROM bookmarks WHERE folder = ?2) \
+         WHERE id = ?1",
+        params![id, folder],
+    )?;
+    Ok(())
+}
+
+/// Add a bookmark to the Unsorted folder, returning its id.
+pub fn add_bookmark(config_dir: &str, url: &str, title: &str, notes: &str) -> Result<i64, Error> {
+    let path = Path::new(&config_dir).join("bookmarks.sqlite");
+    let conn = Connection::open(path)?;
+    conn.execute(
+        "INSERT INTO bookmarks (url, title, notes, position, folder) \
+         VALUES (?1, ?2, ?3, \
+         (SELECT COALESCE(MAX(position), 0) + 1 FROM bookmarks WHERE folder = ?4), ?4)",
+        params![url, title, notes, UNSORTED_FOLDER_ID],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Store the order of the bookmarks in the given folder, given as a list of their ids.
+pub fn set_bookmark_order(config_dir: &str, folder: i64, ids: &[i64]) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("bookmarks.sqlite");
+    let mut conn = Connection::open(path)?;
+    let transaction = conn.transaction()?;
+    {
+        let mut stmt = transaction
+            .prepare("UPDATE bookmarks SET position = ?1 WHERE id = ?2 AND folder = ?3")?;
+        for (position, id) in ids.iter().enumerate() {
+            stmt.execute(params![position, id, folder])?;
+        }
+    }
+    transaction.commit()
+}
+
+/// Change the URL, title and notes of the bookmark with the given id.
+pub fn update_bookmark(
+    config_dir: &str,
+    id: i64,
+    url: &str,
+    title: &str,
+    notes: &str,
+) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("bookmarks.sqlite");
+    let conn = Connection::open(path)?;
+    conn.execute(
+        "UPDATE bookmarks SET url = ?2, title = ?3, notes = ?4 WHERE id = ?1",
+        params![id, url, title, notes],
+    )?;
+    Ok(())
+}
+
+/// Change the URL of the bookmark with the given id.
+pub fn set_bookmark_url(config_dir: &str, id: i64, url: &str) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("bookmarks.sqlite");
+    let conn = Connection::open(path)?;
+    conn.execute(
+        "UPDATE bookmarks SET url = ?2 WHERE id = ?1",
+        params![id, url],
+    )?;
     Ok(())
 }
 
-pub fn get_bookmarks(config_dir: &str) -> Vec<Bookmark> {
+pub fn remove_bookmark(config_dir: &str, id: i64) -> Result<(), Error> {
     let path = Path::new(&config_dir).join("bookmarks.sqlite");
+    let conn = Connection::open(path)?;
+    conn.execute("DELETE FROM bookmarks WHERE id = ?1", [id])?;
+    Ok(())
+}
+
+pub fn create_history_db(config_dir: &str) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("history.sqlite");
+    let conn = Connection::open(path)?;
+    conn.execute(
+        "CREATE TABLE history \
+         (url TEXT PRIMARY KEY, title TEXT, last_visit TEXT, visit_count INTEGER NOT NULL)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Bring a history DB created by an older version of Moto up to date.
+pub fn migrate_history_db(config_dir: &str) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("history.sqlite");
+    let conn = Connection::open(path)?;
+    let mut stmt = conn.prepare("SELECT name FROM pragma_table_info('history')")?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    if columns.iter().any(|column| column == "visited") {
+        conn.execute(
+            "ALTER TABLE history RENAME COLUMN visited TO last_visit",
+            [],
+        )?;
+    }
+    if !columns.iter().any(|column| column == "visit_count") {
+        // Only the last visit to each page was kept, so count that.
+        conn.execute(
+            "ALTER TABLE history ADD COLUMN visit_count INTEGER NOT NULL DEFAULT 1",
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+/// Returns up to `limit` visited pages, most recently visited first.
+pub fn get_history(config_dir: &str, limit: usize) -> Result<Vec<HistoryEntry>, Error> {
+    let path = Path::new(&config_dir).join("history.sqlite");
+    let conn = Connection::open(path)?;
+    let mut stmt = conn.prepare(
+        "SELECT url, title, strftime('%Y-%m-%d %H:%M:%f', last_visit, 'localtime') FROM history \
+         ORDER BY last_visit DESC LIMIT ?1",
+    )?;
+    let rows = stmt.query(params![limit as i64])?;
+    rows.mapped(|row| {
+        Ok(HistoryEntry {
+            url: row.get(0)?,
+            title: row.get(1)?,
+            visited: row.get(2)?,
+        })
+    })
+    .collect()
+}
+
+/// Returns up to `limit` visited pages whose URL or title contains the given text, ignoring ASCII
+/// case, most frecent first: pages visited more often come first, but less so the longer ago they
+/// were last visited.
+pub fn get_frecent(config_dir: &str, text: &str, limit: usize) -> Result<Vec<HistoryEntry>, Error> {
+    let path = Path::new(&config_dir).join("history.sqlite");
+    let conn = Connection::open(path)?;
+    let pattern = format!(
+        "%{}%",
+        text.replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    );
+    let mut stmt = conn.prepare(
+        "SELECT url, title, strftime('%Y-%m-%d %H:%M:%f', last_visit, 'localtime') FROM history \
+         WHERE url LIKE ?1 ESCAPE '\\' OR title LIKE ?1 ESCAPE '\\' \
+         ORDER BY visit_count * CASE \
+             WHEN last_visit > datetime('now', '-4 days') THEN 100 \
+             WHEN last_visit > datetime('now', '-14 days') THEN 70 \
+             WHEN last_visit > datetime('now', '-31 days') THEN 50 \
+             WHEN last_visit > datetime('now', '-90 days') THEN 30 \
+             ELSE 10 \
+         END DESC, last_visit DESC LIMIT ?2",
+    )?;
+    let rows = stmt.query(params![pattern, limit as i64])?;
+    rows.mapped(|row| {
+        Ok(HistoryEntry {
+            url: row.get(0)?,
+            title: row.get(1)?,
+            visited: row.get(2)?,
+        })
+    })
+    .collect()
+}
+
+/// Record a visit to the given page. A page that is already in the history is moved to the top and
+/// has its visits counted, keeping its title if the given one is empty.
+pub fn add_history_entry(config_dir: &str, url: &str, title: &str) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("history.sqlite");
+    let conn = Connection::open(path)?;
+    conn.execute(
+        "INSERT INTO history (url, title, last_visit, visit_count) \
+         VALUES (?1, ?2, strftime('%Y-%m-%d %H:%M:%f', 'now'), 1) \
+         ON CONFLICT (url) DO UPDATE SET last_visit = excluded.last_visit, \
+         visit_count = visit_count + 1, \
+         title = COALESCE(NULLIF(excluded.title, ''), title)",
+        [&url, &title],
+    )?;
+    Ok(())
+}
+
+/// Set the title of a page in the history, without counting it as a visit. An empty title leaves
+/// the one it has alone.
+pub fn set_history_title(config_dir: &str, url: &str, title: &str) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("history.sqlite");
+    let conn = Connection::open(path)?;
+    conn.execute(
+        "UPDATE history SET title = COALESCE(NULLIF(?2, ''), title) WHERE url = ?1",
+        [&url, &title],
+    )?;
+    Ok(())
+}
+
+/// Forget every visited page.
+pub fn clear_history(config_dir: &str) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("history.sqlite");
+    let conn = Connection::open(path)?;
+    conn.execute("DELETE FROM history", [])?;
+    Ok(())
+}
+
+/// Forget all but the `max_entries` most recently visited pages, and the pages last visited more
+/// than `retention_days` days ago. Either limit is off when it is 0.
+pub fn prune_history(config_dir: &str, max_entries: i64, retention_days: i64) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("history.sqlite");
+    let conn = Connection::open(path)?;
+    if max_entries > 0 {
+        conn.execute(
+            "DELETE FROM history WHERE url NOT IN \
+             (SELECT url FROM history ORDER BY last_visit DESC LIMIT ?1)",
+            [max_entries],
+        )?;
+    }
+    if retention_days > 0 {
+        conn.execute(
+            "DELETE FROM history WHERE last_visit < strftime('%Y-%m-%d %H:%M:%f', 'now', ?1)",
+            [format!("-{} days", retention_days)],
+        )?;
+    }
+    Ok(())
+}
+
+pub fn create_prefs_db(config_dir: &str) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("prefs.sqlite");
+    let conn = Connection::open(path)?;
+    conn.execute("CREATE TABLE prefs (key TEXT PRIMARY KEY, value TEXT)", [])?;
+    Ok(())
+}
+
+pub fn get_prefs(config_dir: &str) -> Vec<(String, String)> {
+    let path = Path::new(&config_dir).join("prefs.sqlite");
     let conn = Connection::open(path).expect("Failed to open connection to DB!");
-    let mut stmt = conn.prepare("SELECT * FROM bookmarks").unwrap();
+    let mut stmt = conn.prepare("SELECT key, value FROM prefs").unwrap();
     let rows = stmt.query([]).unwrap();
     rows.mapped(|row| {
-        let url: String = row.get(1).unwrap();
-        let title: String = row.get(2).unwrap();
-        Ok(Bookmark { url, title })
+        let key: String = row.get(0).unwrap();
+        let value: String = row.get(1).unwrap();
+        Ok((key, value))
     })
-    .map(|bookmark| bookmark.unwrap())
+    .map(|pref| pref.unwrap())
     .collect()
 }
 
-pub fn add_bookmark(config_dir: &str, url: &str, title: &str) -> Result<(), Error> {
-    let path = Path::new(&config_dir).join("bookmarks.sqlite");
+pub fn set_pref(config_dir: &str, key: &str, value: &str) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("prefs.sqlite");
     let conn = Connection::open(path).expect("Failed to open connection to DB!");
     conn.execute(
-        "INSERT INTO bookmarks (url, title) VALUES (?1, ?2)",
+        "INSERT OR REPLACE INTO prefs (key, value) VALUES (?1, ?2)",
+        [&key, &value],
+    )?;
+    Ok(())
+}
+
+pub fn create_site_settings_db(config_dir: &str) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("site_settings.sqlite");
+    let conn = Connection::open(path)?;
+    conn.execute(
+        "CREATE TABLE site_settings (origin TEXT, setting TEXT, value TEXT, PRIMARY KEY (origin, setting))",
+        [],
+    )?;
+    Ok(())
+}
+
+pub fn get_site_settings(config_dir: &str, origin: &str) -> Result<Vec<(String, String)>, Error> {
+    let path = Path::new(&config_dir).join("site_settings.sqlite");
+    let conn = Connection::open(path)?;
+    let mut stmt = conn.prepare("SELECT setting, value FROM site_settings WHERE origin = ?1")?;
+    let rows = stmt.query([&origin])?;
+    rows.mapped(|row| Ok((row.get(0)?, row.get(1)?))).collect()
+}
+
+pub fn get_site_setting(config_dir: &str, origin: &str, setting: &str) -> Option<String> {
+    get_site_settings(config_dir, origin)
+        .ok()?
+        .into_iter()
+        .find(|(key, _)| key == setting)
+        .map(|(_, value)| value)
+}
+
+pub fn set_site_setting(
+    config_dir: &str,
+    origin: &str,
+    setting: &str,
+    value: &str,
+) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("site_settings.sqlite");
+    let conn = Connection::open(path)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO site_settings (origin, setting, value) VALUES (?1, ?2, ?3)",
+        [&origin, &setting, &value],
+    )?;
+    Ok(())
+}
+
+pub fn remove_site_setting(config_dir: &str, origin: &str, setting: &str) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("site_settings.sqlite");
+    let conn = Connection::open(path)?;
+    conn.execute(
+        "DELETE FROM site_settings WHERE origin = ?1 AND setting = ?2",
+        [&origin, &setting],
+    )?;
+    Ok(())
+}
+
+pub fn create_reading_list_db(config_dir: &str) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("reading_list.sqlite");
+    let conn = Connection::open(path)?;
+    conn.execute(
+        "CREATE TABLE reading_list (url TEXT PRIMARY KEY, title TEXT, added TEXT, read INTEGER)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Returns the reading list, most recently added first.
+pub fn get_reading_list(config_dir: &str) -> Result<Vec<ReadingListItem>, Error> {
+    let path = Path::new(&config_dir).join("reading_list.sqlite");
+    let conn = Connection::open(path)?;
+    let mut stmt =
+        conn.prepare("SELECT url, title, added, read FROM reading_list ORDER BY added DESC")?;
+    let rows = stmt.query([])?;
+    rows.mapped(|row| {
+        Ok(ReadingListItem {
+            url: row.get(0)?,
+            title: row.get(1)?,
+            added: row.get(2)?,
+            read: row.get(3)?,
+        })
+    })
+    .collect()
+}
+
+/// Add the given page to the reading list as unread, replacing any existing entry for it.
+pub fn add_to_reading_list(config_dir: &str, url: &str, title: &str) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("reading_list.sqlite");
+    let conn = Connection::open(path)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO reading_list (url, title, added, read) VALUES (?1, ?2, datetime('now'), 0)",
         [&url, &title],
     )?;
     Ok(())
 }
 
-pub fn remove_bookmark(config_dir: &str, url: &str) -> Result<(), Error> {
-    let path = Path::new(&config_dir).join("bookmarks.sqlite");
-    let conn = Connection::open(path).expect("Failed to open connection to DB!");
-    conn.execute("DELETE FROM bookmarks WHERE url = ?1", [&url])?;
+pub fn set_reading_list_item_read(config_dir: &str, url: &str, read: bool) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("reading_list.sqlite");
+    let conn = Connection::open(path)?;
+    conn.execute(
+        "UPDATE reading_list SET read = ?2 WHERE url = ?1",
+        rusqlite::params![url, read],
+    )?;
+    Ok(())
+}
+
+pub fn remove_from_reading_list(config_dir: &str, url: &str) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("reading_list.sqlite");
+    let conn = Connection::open(path)?;
+    conn.execute("DELETE FROM reading_list WHERE url = ?1", [&url])?;
+    Ok(())
+}
+
+pub fn create_web_apps_db(config_dir: &str) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("web_apps.sqlite");
+    let conn = Connection::open(path)?;
+    conn.execute(
+        "CREATE TABLE web_apps (start_url TEXT PRIMARY KEY, name TEXT, icon_path TEXT, installed TEXT)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Record an installed web app, replacing any existing one with the same start URL.
+pub fn add_web_app(
+    config_dir: &str,
+    name: &str,
+    start_url: &str,
+    icon_path: &str,
+) -> Result<(), Error> {
+    let path = Path::new(&config_dir).join("web_apps.sqlite");
+    let conn = Connection::open(path)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO web_apps (start_url, name, icon_path, installed) VALUES (?1, ?2, ?3, datetime('now'))",
+        [&start_url, &name, &icon_path],
+    )?;
     Ok(())
 }
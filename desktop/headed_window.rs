@@ -7,6 +7,7 @@
 use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::rc::Rc;
+use std::time::Instant;
 
 use euclid::{Angle, Length, Point2D, Rotation3D, Scale, Size2D, UnknownUnit, Vector2D, Vector3D};
 use log::{debug, info, trace};
@@ -33,8 +34,27 @@ use winit::window::Icon;
 use super::events_loop::{EventsLoop, WakerEvent};
 use super::geometry::{winit_position_to_euclid_point, winit_size_to_euclid_size};
 use super::keyutils::keyboard_event_from_winit;
+use super::moto_prefs;
 use super::window_trait::{WindowPortsMethods, LINE_HEIGHT};
 
+/// How far the pointer can move from where autoscroll started without scrolling, in device
+/// independent pixels.
+const AUTOSCROLL_DEAD_ZONE: f32 = 10.0;
+
+/// How fast autoscroll scrolls, in pixels per second for each pixel the pointer is beyond the
+/// dead zone.
+const AUTOSCROLL_SPEED: f32 = 8.0;
+
+/// Scrolling by dragging with the middle mouse button held down.
+#[derive(Clone, Copy)]
+struct Autoscroll {
+    /// Where the middle button was pressed. Scrolling is proportional to the distance from here.
+    origin: Point2D<i32, DevicePixel>,
+    /// False until the pointer has been dragged, so middle clicks don't scroll.
+    dragging: bool,
+    last_scroll: Instant,
+}
+
 pub struct Window {
     winit_window: winit::window::Window,
     rendering_context: RenderingContext,
@@ -55,6 +75,7 @@ pub struct Window {
     device_pixel_ratio_override: Option<f32>,
     xr_window_poses: RefCell<Vec<Rc<XRWindowPose>>>,
     modifiers_state: Cell<ModifiersState>,
+    autoscroll: Cell<Option<Autoscroll>>,
 }
 
 #[cfg(not(target_os = "windows"))]
@@ -152,6 +173,7 @@ impl Window {
             device_pixel_ratio_override,
             xr_window_poses: RefCell::new(vec![]),
             modifiers_state: Cell::new(ModifiersState::empty()),
+            autoscroll: Cell::new(None),
             toolbar_height: Cell::new(Default::default()),
         }
     }
@@ -282,11 +304,76 @@ impl Window {
             .borrow_mut()
             .push(EmbedderEvent::MouseWindowEventClass(event));
     }
+
+    /// Start or end autoscroll as the middle button is pressed or released. Returns whether a
+    /// release ended an autoscroll drag.
+    fn handle_middle_button(&self, action: ElementState) -> bool {
+        match action {
+            ElementState::Pressed if moto_prefs::get_bool("general.autoscroll") => {
+                self.autoscroll.set(Some(Autoscroll {
+                    origin: self.mouse_pos.get(),
+                    dragging: false,
+                    last_scroll: Instant::now(),
+                }));
+                false
+            },
+            ElementState::Pressed => false,
+            ElementState::Released => {
+                let dragged = self.autoscroll.take().is_some_and(|a| a.dragging);
+                if dragged {
+                    self.set_cursor(Cursor::Default);
+                }
+                dragged
+            },
+        }
+    }
+
+    /// Once the pointer has been dragged far enough with the middle button held down, start
+    /// scrolling.
+    fn update_autoscroll_drag(&self) {
+        let Some(mut autoscroll) = self.autoscroll.get().filter(|a| !a.dragging) else {
+            return;
+        };
+        let offset = (self.mouse_pos.get() - autoscroll.origin).to_f32();
+        if offset.length() < AUTOSCROLL_DEAD_ZONE * self.hidpi_factor().get() {
+            return;
+        }
+        self.set_cursor(Cursor::AllScroll);
+        autoscroll.dragging = true;
+        autoscroll.last_scroll = Instant::now();
+        self.autoscroll.set(Some(autoscroll));
+    }
+
+    /// Returns the scroll for the time since the last one, if autoscrolling.
+    fn autoscroll_event(&self) -> Option<EmbedderEvent> {
+        let mut autoscroll = self.autoscroll.get().filter(|a| a.dragging)?;
+        let now = Instant::now();
+        let elapsed = (now - autoscroll.last_scroll).as_secs_f32();
+        autoscroll.last_scroll = now;
+        self.autoscroll.set(Some(autoscroll));
+
+        let offset = (self.mouse_pos.get() - autoscroll.origin).to_f32();
+        let distance = offset.length();
+        let dead_zone = AUTOSCROLL_DEAD_ZONE * self.hidpi_factor().get();
+        if distance <= dead_zone {
+            return None;
+        }
+        // Positive deltas scroll up, so move away from the pointer.
+        let delta = -offset / distance * (distance - dead_zone) * AUTOSCROLL_SPEED * elapsed;
+        let scroll_location = ScrollLocation::Delta(Vector2D::new(delta.x, delta.y));
+        Some(EmbedderEvent::Scroll(
+            scroll_location,
+            autoscroll.origin,
+            TouchEventType::Move,
+        ))
+    }
 }
 
 impl WindowPortsMethods for Window {
     fn get_events(&self) -> Vec<EmbedderEvent> {
-        std::mem::take(&mut *self.event_queue.borrow_mut())
+        let mut events = std::mem::take(&mut *self.event_queue.borrow_mut());
+        events.extend(self.autoscroll_event());
+        events
     }
 
     fn device_hidpi_factor(&self) -> Scale<f32, DeviceIndependentPixel, DevicePixel> {
@@ -351,6 +438,11 @@ impl WindowPortsMethods for Window {
     fn set_cursor(&self, cursor: Cursor) {
         use winit::window::CursorIcon;
 
+        // Keep showing the autoscroll cursor, whatever the page is hovering.
+        if cursor != Cursor::AllScroll && self.autoscroll.get().is_some_and(|a| a.dragging) {
+            return;
+        }
+
         let winit_cursor = match cursor {
             Cursor::Default => CursorIcon::Default,
             Cursor::Pointer => CursorIcon::Pointer,
@@ -397,6 +489,7 @@ impl WindowPortsMethods for Window {
 
     fn is_animating(&self) -> bool {
         self.animation_state.get() == AnimationState::Animating
+            || self.autoscroll.get().is_some_and(|a| a.dragging)
     }
 
     fn id(&self) -> winit::window::WindowId {
@@ -414,11 +507,19 @@ impl WindowPortsMethods for Window {
             winit::event::WindowEvent::MouseInput { state, button, .. } => {
                 if button == MouseButton::Left || button == MouseButton::Right {
                     self.handle_mouse(button, state, self.mouse_pos.get());
+                } else if button == MouseButton::Middle {
+                    // Pages still get the middle button, for auxclick, but an autoscroll drag
+                    // mustn't end in a click.
+                    if self.handle_middle_button(state) {
+                        self.mouse_down_button.set(None);
+                    }
+                    self.handle_mouse(button, state, self.mouse_pos.get());
                 }
             },
             winit::event::WindowEvent::CursorMoved { position, .. } => {
                 let position = winit_position_to_euclid_point(position);
                 self.mouse_pos.set(position.to_i32());
+                self.update_autoscroll_drag();
                 self.event_queue
                     .borrow_mut()
                     .push(EmbedderEvent::MouseWindowMoveEventClass(position.to_f32()));
@@ -0,0 +1,456 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! The contents of the location field, and when the page's URL may replace them.
+//!
+//! The field shows the focused page's URL until the user edits it. An edit is kept, however many
+//! URL updates arrive, until the user commits it with Go or the field is reset for another tab.
+//! An edit that ends up back at the shown URL isn't kept, since there's nothing to lose.
+//!
+//! While the user types, pages from the history and bookmarks that match are suggested below the
+//! field. They are only looked up once typing pauses, since the history can be large.
+
+use std::time::{Duration, Instant};
+
+use egui::{Id, Key, Modifiers, Ui};
+
+/// How many earlier contents of the location field can be restored with undo.
+const HISTORY_LIMIT: usize = 50;
+
+/// How long typing has to pause before suggestions are looked up for the new text.
+const SUGGESTION_DELAY: Duration = Duration::from_millis(150);
+
+#[derive(Default)]
+pub struct Location {
+    text: String,
+    /// The text last shown or committed, which the user has edited iff the text differs from it.
+    shown: String,
+    history: History,
+}
+
+impl Location {
+    /// Returns a field showing the given URL.
+    pub fn new(url: &str) -> Self {
+        Self {
+            text: url.to_owned(),
+            shown: url.to_owned(),
+            history: History::default(),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The text for the user to edit, who must call [Location::record_edit] after changing it.
+    pub fn text_mut(&mut self) -> &mut String {
+        &mut self.text
+    }
+
+    /// Remember the text before an edit by the user, so it can be undone.
+    pub fn record_edit(&mut self, before: String) {
+        self.history.record_edit(before, &self.text);
+    }
+
+    /// Replace the text with something other than what the user typed, like a URL to navigate to.
+    pub fn set_text(&mut self, text: String) {
+        let replaced = std::mem::replace(&mut self.text, text);
+        self.history.record_replacement(replaced);
+    }
+
+    /// Returns true iff the user has edited the text without committing it.
+    pub fn is_edited(&self) -> bool {
+        self.text != self.shown
+    }
+
+    /// The text is being navigated to, so it can be replaced by the page's URL again.
+    pub fn commit(&mut self) {
+        self.shown = self.text.clone();
+    }
+
+    /// Give up any edit, so the next URL update replaces it, like when switching tabs. The edit can
+    /// still be undone.
+    pub fn discard_edit(&mut self) {
+        self.commit();
+    }
+
+    /// Show the page's URL, unless the user is editing the text, returning true iff it changed.
+    pub fn update(&mut self, url: &str) -> bool {
+        if self.is_edited() || self.text == url {
+            return false;
+        }
+        self.set_text(url.to_owned());
+        self.shown = self.text.clone();
+        true
+    }
+
+    /// Restore the text before the last edit or replacement, returning true iff there was one.
+    pub fn undo(&mut self) -> bool {
+        match self.history.undo(&self.text) {
+            Some(text) => {
+                self.text = text;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Restore the text that was last undone, returning true iff there was one.
+    pub fn redo(&mut self) -> bool {
+        match self.history.redo(&self.text) {
+            Some(text) => {
+                self.text = text;
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+/// A page suggested below the location field.
+pub struct Suggestion {
+    pub url: String,
+    pub title: String,
+    pub bookmarked: bool,
+}
+
+/// The suggestions for the text in the location field, and the one picked with the arrow keys.
+#[derive(Default)]
+pub struct Suggestions {
+    /// The text the suggestions were looked up for.
+    text: String,
+    /// Text the suggestions haven't been looked up for yet, and when it was first seen.
+    pending: Option<(String, Instant)>,
+    entries: Vec<Suggestion>,
+    selected: Option<usize>,
+}
+
+impl Suggestions {
+    /// Note the text in the field, returning how long to wait before looking up suggestions for
+    /// it, which is zero once typing has paused, or None if they are up to date. Any picked
+    /// suggestion is dropped as soon as the text changes, so Enter can't go to a stale one.
+    pub fn wait(&mut self, text: &str, now: Instant) -> Option<Duration> {
+        if text == self.text {
+            self.pending = None;
+            return None;
+        }
+        self.selected = None;
+        let since = match &self.pending {
+            Some((pending, since)) if pending == text => *since,
+            _ => {
+                self.pending = Some((text.to_owned(), now));
+                now
+            },
+        };
+        Some(SUGGESTION_DELAY.saturating_sub(now - since))
+    }
+
+    /// Replace the suggestions with those looked up for the given text.
+    pub fn set(&mut self, text: &str, entries: Vec<Suggestion>) {
+        self.text = text.to_owned();
+        self.pending = None;
+        self.entries = entries;
+        self.selected = None;
+    }
+
+    /// Forget the suggestions, like when the field loses focus, so they are looked up afresh.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn entries(&self) -> &[Suggestion] {
+        &self.entries
+    }
+
+    /// Returns the index of the suggestion picked with the arrow keys, if any.
+    pub fn selected_index(&self) -> Option<usize> {
+        self.selected
+    }
+
+    pub fn selected(&self) -> Option<&Suggestion> {
+        self.selected.and_then(|index| self.entries.get(index))
+    }
+
+    /// Pick the next suggestion, or none after the last one, so Enter does what the field says.
+    pub fn select_next(&mut self) {
+        self.selected = match self.selected {
+            None if !self.entries.is_empty() => Some(0),
+            Some(index) if index + 1 < self.entries.len() => Some(index + 1),
+            _ => None,
+        };
+    }
+
+    /// Pick the previous suggestion, or the last one if none was picked.
+    pub fn select_previous(&mut self) {
+        self.selected = match self.selected {
+            None => self.entries.len().checked_sub(1),
+            Some(index) => index.checked_sub(1),
+        };
+    }
+}
+
+/// Returns true iff Enter was pressed while the field with the given ID had focus, consuming the
+/// key so the field doesn't react to it as well. Call this before drawing the field.
+///
+/// Waiting for the field to lose focus on Enter instead misses presses when focus moves for
+/// another reason in the same frame. Any number of presses in one frame submit once.
+pub fn consume_enter(ui: &Ui, id: Id) -> bool {
+    ui.memory(|memory| memory.has_focus(id))
+        && ui.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Enter))
+}
+
+/// Earlier contents of the location field, so that edits can be undone even where egui's own
+/// undo wouldn't reach, like when the field is replaced with the page's URL.
+#[derive(Default)]
+struct History {
+    undo: Vec<String>,
+    redo: Vec<String>,
+    /// Whether the last edit typed a single character, so the next one can be undone with it.
+    typing: bool,
+}
+
+impl History {
+    /// Remember the text before an edit by the user. A run of typed characters is one step.
+    fn record_edit(&mut self, before: String, after: &str) {
+        let typed = after.chars().count() == before.chars().count() + 1;
+        if !(typed && self.typing) {
+            self.push(before);
+        }
+        self.typing = typed;
+    }
+
+    /// Remember text that is about to be replaced by something other than the user.
+    fn record_replacement(&mut self, before: String) {
+        if !before.is_empty() {
+            self.push(before);
+        }
+        self.typing = false;
+    }
+
+    fn push(&mut self, text: String) {
+        if self.undo.last() != Some(&text) {
+            self.undo.push(text);
+            if self.undo.len() > HISTORY_LIMIT {
+                self.undo.remove(0);
+            }
+        }
+        self.redo.clear();
+    }
+
+    /// Returns the text to restore, given the current text, or None if there's nothing to undo.
+    fn undo(&mut self, current: &str) -> Option<String> {
+        let text = self.undo.pop()?;
+        self.redo.push(current.to_owned());
+        self.typing = false;
+        Some(text)
+    }
+
+    /// Returns the text to restore, given the current text, or None if there's nothing to redo.
+    fn redo(&mut self, current: &str) -> Option<String> {
+        let text = self.redo.pop()?;
+        self.undo.push(current.to_owned());
+        self.typing = false;
+        Some(text)
+    }
+}
+
+#[cfg(test)]
+const TEST_FIELD_ID: &str = "location_input";
+
+/// Runs a frame showing just the location field with the given input events, returning true iff
+/// it was submitted.
+#[cfg(test)]
+fn run_field_frame(ctx: &egui::Context, text: &mut String, events: Vec<egui::Event>) -> bool {
+    let mut submitted = false;
+    let input = egui::RawInput {
+        events,
+        ..Default::default()
+    };
+    let _ = ctx.run(input, |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let id = Id::new(TEST_FIELD_ID);
+            submitted = consume_enter(ui, id);
+            ui.add(egui::TextEdit::singleline(text).id(id));
+        });
+    });
+    submitted
+}
+
+#[cfg(test)]
+fn enter_pressed() -> egui::Event {
+    egui::Event::Key {
+        key: Key::Enter,
+        physical_key: None,
+        pressed: true,
+        repeat: false,
+        modifiers: Modifiers::NONE,
+    }
+}
+
+#[cfg(test)]
+fn type_text(location: &mut Location, text: &str) {
+    for c in text.chars() {
+        let before = location.text().to_owned();
+        location.text_mut().push(c);
+        location.record_edit(before);
+    }
+}
+
+#[test]
+fn test_load_completing_while_typing() {
+    let mut location = Location::default();
+    assert!(location.update("https://example.com/slow"));
+
+    location.text_mut().clear();
+    location.record_edit("https://example.com/slow".to_owned());
+    type_text(&mut location, "servo.o");
+    assert!(location.is_edited());
+
+    // A redirect and the load completing both report new URLs mid-edit.
+    assert!(!location.update("https://example.com/redirected"));
+    assert!(!location.update("https://example.com/done"));
+    type_text(&mut location, "rg");
+    assert_eq!(location.text(), "servo.org");
+
+    location.commit();
+    assert!(location.update("https://servo.org/"));
+    assert_eq!(location.text(), "https://servo.org/");
+}
+
+#[test]
+fn test_edit_back_to_shown_url() {
+    let mut location = Location::default();
+    location.update("https://example.com/");
+    type_text(&mut location, "x");
+    location.text_mut().pop();
+    location.record_edit("https://example.com/x".to_owned());
+    assert!(!location.is_edited());
+    assert!(location.update("https://example.com/next"));
+}
+
+#[test]
+fn test_undo_replaced_edit() {
+    let mut location = Location::default();
+    location.update("https://example.com/");
+    location.text_mut().clear();
+    location.record_edit("https://example.com/".to_owned());
+    type_text(&mut location, "half-typed");
+
+    // Switching tabs gives up the edit, but it can still be undone.
+    location.discard_edit();
+    assert!(location.update("https://example.org/"));
+    assert!(location.undo());
+    assert_eq!(location.text(), "half-typed");
+    assert!(location.is_edited());
+    assert!(!location.update("https://example.org/other"));
+
+    assert!(location.redo());
+    assert_eq!(location.text(), "https://example.org/");
+}
+
+#[test]
+fn test_rapid_enter_submits_once() {
+    let ctx = egui::Context::default();
+    let mut text = "servo.org".to_owned();
+    run_field_frame(&ctx, &mut text, vec![]);
+    ctx.memory_mut(|memory| memory.request_focus(Id::new(TEST_FIELD_ID)));
+    run_field_frame(&ctx, &mut text, vec![]);
+
+    assert!(run_field_frame(
+        &ctx,
+        &mut text,
+        vec![enter_pressed(), enter_pressed()]
+    ));
+    assert!(!run_field_frame(&ctx, &mut text, vec![]));
+    assert_eq!(text, "servo.org");
+
+    // The field keeps focus, so a later press submits again.
+    assert!(run_field_frame(&ctx, &mut text, vec![enter_pressed()]));
+}
+
+#[test]
+fn test_enter_after_focus_moves_away() {
+    let ctx = egui::Context::default();
+    let mut text = "servo.org".to_owned();
+    run_field_frame(&ctx, &mut text, vec![]);
+    ctx.memory_mut(|memory| memory.request_focus(Id::new(TEST_FIELD_ID)));
+    run_field_frame(&ctx, &mut text, vec![]);
+
+    ctx.memory_mut(|memory| memory.surrender_focus(Id::new(TEST_FIELD_ID)));
+    assert!(!run_field_frame(&ctx, &mut text, vec![enter_pressed()]));
+}
+
+#[test]
+fn test_suggestions_wait_for_typing_to_pause() {
+    let mut suggestions = Suggestions::default();
+    let start = Instant::now();
+    assert_eq!(suggestions.wait("", start), None);
+
+    assert_eq!(suggestions.wait("se", start), Some(SUGGESTION_DELAY));
+    // Each keystroke starts the wait again.
+    let typed = start + Duration::from_millis(100);
+    assert_eq!(suggestions.wait("ser", typed), Some(SUGGESTION_DELAY));
+    let later = typed + Duration::from_millis(100);
+    assert_eq!(
+        suggestions.wait("ser", later),
+        Some(Duration::from_millis(50))
+    );
+    assert_eq!(
+        suggestions.wait("ser", typed + SUGGESTION_DELAY),
+        Some(Duration::ZERO)
+    );
+
+    suggestions.set("ser", vec![]);
+    assert_eq!(suggestions.wait("ser", later), None);
+}
+
+#[test]
+fn test_select_suggestions() {
+    let suggestion = |url: &str| Suggestion {
+        url: url.to_owned(),
+        title: String::new(),
+        bookmarked: false,
+    };
+    let mut suggestions = Suggestions::default();
+    suggestions.select_next();
+    assert!(suggestions.selected().is_none());
+
+    suggestions.set(
+        "servo",
+        vec![
+            suggestion("https://servo.org/"),
+            suggestion("https://servo.rs/"),
+        ],
+    );
+    suggestions.select_next();
+    suggestions.select_next();
+    assert_eq!(suggestions.selected().unwrap().url, "https://servo.rs/");
+    // Going past the last suggestion picks none of them.
+    suggestions.select_next();
+    assert!(suggestions.selected().is_none());
+    suggestions.select_previous();
+    assert_eq!(suggestions.selected_index(), Some(1));
+}
+
+#[test]
+fn test_typing_drops_selected_suggestion() {
+    let mut suggestions = Suggestions::default();
+    suggestions.set(
+        "servo",
+        vec![Suggestion {
+            url: "https://servo.org/".to_owned(),
+            title: String::new(),
+            bookmarked: false,
+        }],
+    );
+    suggestions.select_next();
+    let now = Instant::now();
+    assert_eq!(suggestions.wait("servo", now), None);
+    assert!(suggestions.selected().is_some());
+
+    // The suggestions are still shown while waiting to look up new ones, but none is picked.
+    assert_eq!(suggestions.wait("servox", now), Some(SUGGESTION_DELAY));
+    assert!(suggestions.selected().is_none());
+    assert_eq!(suggestions.entries().len(), 1);
+}
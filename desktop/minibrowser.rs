@@ -3,24 +3,27 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use std::cell::{Cell, RefCell};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::{self, OpenOptions};
 use std::io::Write;
 use std::num::NonZeroU32;
 use std::path::Path;
 use std::process::Command;
 use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread::{self, JoinHandle};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
+use arboard::{Clipboard, ImageData};
 use curl::easy::Easy;
-use directories::{ProjectDirs, UserDirs};
-use egui::text::{CCursor, CCursorRange};
+use directories::UserDirs;
+use egui::text::{CCursor, CCursorRange, LayoutJob, TextFormat};
 use egui::text_edit::TextEditState;
 use egui::{
     menu, pos2, CentralPanel, Color32, Frame, Key, Label, Modifiers, PaintCallback, Pos2, RichText,
-    SelectableLabel, TopBottomPanel, Vec2,
+    SelectableLabel, SidePanel, TopBottomPanel, Vec2,
 };
 use egui_glow::CallbackFn;
 use egui_winit::EventResponse;
@@ -37,44 +40,144 @@ use servo::servo_url::ServoUrl;
 use servo::style_traits::DevicePixel;
 use servo::webrender_traits::RenderingContext;
 use servo::TopLevelBrowsingContextId;
-use tinyfiledialogs::open_file_dialog;
+use tinyfiledialogs::{open_file_dialog, save_file_dialog, MessageBoxIcon, YesNo};
+use url::{Position, Url};
 use winit::event::{ElementState, MouseButton};
 
-use super::db::{add_bookmark, create_bookmarks_db, get_bookmarks, remove_bookmark};
+use super::accent_color::system_accent_color;
+use super::backups::{back_up_db, list_backups, restore_backup, Backup};
+use super::db::{
+    add_bookmark, add_folder, add_to_reading_list, clear_history, config_dir, create_bookmarks_db,
+    create_history_db, create_reading_list_db, create_site_settings_db, get_bookmark_folders,
+    get_bookmarks, get_frecent, get_history, has_persistent_config_dir, migrate_bookmarks_db,
+    migrate_history_db, move_bookmark, remove_bookmark, set_bookmark_order, set_bookmark_url,
+    update_bookmark, update_folder, HistoryEntry, UNSORTED_FOLDER_ID,
+};
 use super::egui_glue::EguiGlow;
 use super::events_loop::EventsLoop;
 use super::geometry::winit_position_to_euclid_point;
-use super::webview::{LoadStatus, WebViewManager};
+use super::location::{consume_enter, Location, Suggestion, Suggestions};
+use super::motion::animations_allowed;
+use super::moto_prefs;
+use super::protocols::moto::{drawn_page_name, take_bookmarks_changed};
+use super::search_engines::{
+    default_engine, default_search_page, make_default, search_engines, SearchEngine,
+};
+use super::session::{unclean_session, Session};
+use super::shortcuts::enabled_shortcuts;
+use super::webview::{
+    checked_navigation_url, disable_history, LoadStatus, PageTextAction, TabGroup, TabGroupId,
+    WebView, WebViewManager,
+};
 use super::window_trait::WindowPortsMethods;
-use crate::parser::location_bar_input_to_url;
+use crate::parser::{
+    file_path_to_url, is_download_url, location_bar_input_to_url,
+    location_bar_input_to_url_with_search, location_bar_input_to_url_without_search,
+};
 
 pub struct Minibrowser {
     pub context: EguiGlow,
     pub event_queue: RefCell<Vec<MinibrowserEvent>>,
     pub toolbar_height: Length<f32, DeviceIndependentPixel>,
 
+    /// The area left for the webview once the toolbar and any side panels are drawn.
+    webview_area: Box2D<f32, DeviceIndependentPixel>,
+
     /// The framebuffer object name for the widget surface we should draw to, or None if our widget
     /// surface does not use a framebuffer object.
     widget_surface_fbo: Option<NativeFramebuffer>,
 
     last_update: Instant,
     last_mouse_position: Option<Point2D<f32, DeviceIndependentPixel>>,
-    location: RefCell<String>,
-
-    /// Whether the location has been edited by the user without clicking Go.
-    location_dirty: Cell<bool>,
+    location: RefCell<Location>,
+    /// The pages suggested for the text being typed in the location field.
+    location_suggestions: RefCell<Suggestions>,
+    /// Whether the pointer was over the suggestions last frame, so they stay open while one is
+    /// clicked, even though the field loses focus.
+    location_suggestions_hovered: Cell<bool>,
 
+    /// The load status of the focused webview, and which webviews are loading at all.
     load_status: LoadStatus,
+    loading_webviews: HashSet<WebViewId>,
 
     status_text: Option<String>,
 
-    show_about_window: Cell<bool>,
+    /// The color shown behind pages before they paint, as last given to Servo.
+    default_background: Cell<Color32>,
+
+    /// The webview that was focused when the tab strip was last drawn, so that a newly focused
+    /// tab can be scrolled into view.
+    tab_strip_focus: Cell<Option<WebViewId>>,
+
+    /// While Ctrl+Tab is cycling through recently used tabs, the index of the chosen one in
+    /// [WebViewManager::recently_focused_webviews].
+    recent_tab_choice: Cell<Option<usize>>,
+
+    /// Where the page context menu is open, if it is.
+    context_menu_position: Cell<Option<Pos2>>,
+
+    /// A short message shown at the bottom of the window until the given time.
+    toast: RefCell<Option<(String, Instant)>>,
+
+    show_devtools_window: Cell<bool>,
+    show_customize_toolbar_window: Cell<bool>,
+    /// The history as read from the DB when the History menu was opened, until it is closed.
+    history_entries: RefCell<Option<Vec<HistoryEntry>>>,
+    /// The bookmark backups found when the Bookmarks menu was opened, until it is closed.
+    bookmark_backups: RefCell<Option<Vec<Backup>>>,
+
+    show_console: Cell<bool>,
+    /// Whether to focus the find bar's field the next time it is drawn.
+    focus_find_bar: Cell<bool>,
+    console: RefCell<Console>,
+
+    /// Whether to ask the user to confirm quitting with multiple tabs open.
+    show_quit_dialog: Cell<bool>,
+    quit_dont_ask_again: Cell<bool>,
+    /// Whether to ask the user to confirm clearing the history.
+    show_clear_history_dialog: Cell<bool>,
+
+    /// The tabs from a session that didn't shut down cleanly, until the user decides whether
+    /// to restore them.
+    crashed_session: RefCell<Option<Session>>,
+
+    /// Whether this is a single-purpose window opened with `--app`, which only has a small toolbar
+    /// and no tabs.
+    app_mode: bool,
+    /// Whether there is a reading list DB to save pages to.
+    reading_list_enabled: bool,
 
     config_dir: String,
     download_dir: String,
 
+    /// The directory the Open File dialog starts in, which is wherever the last file was opened
+    /// from.
+    open_file_dir: RefCell<String>,
+
     bookmarks: RefCell<Vec<Bookmark>>,
+    bookmark_folders: RefCell<Vec<BookmarkFolder>>,
+    /// The bookmark folder being made or changed, while the New Folder or Edit Folder window is
+    /// open.
+    editing_folder: RefCell<Option<FolderDraft>>,
+    /// The id, title and URL of the bookmark being changed in the Edit Bookmark window, while it
+    /// is open.
+    editing_bookmark: RefCell<Option<(i64, String, String)>>,
+    /// The id of a bookmark that couldn't be opened, and the user's fix for its URL, until they
+    /// decide what to do with it.
+    invalid_bookmark: RefCell<Option<(i64, String)>>,
     download_jobs: RefCell<Vec<DownloadJob>>,
+    /// The link to a file that the focused webview is going back from, which has been downloaded,
+    /// so that it is only downloaded once however many frames it takes to go back.
+    downloaded_url: RefCell<Option<String>>,
+    /// The text of numeric fields on the config page being edited, by pref, which is kept even
+    /// while it doesn't parse so the user can see what's wrong.
+    config_edits: RefCell<HashMap<String, String>>,
+    /// The pref last changed on the config page, and until when to show that it was saved.
+    config_saved: RefCell<Option<(String, Instant)>>,
+    /// The thread uploading an image to search for, which returns the URL of the results.
+    image_search: RefCell<Option<JoinHandle<Result<ServoUrl, String>>>>,
+    /// The engine picked in the location bar for the next search, instead of the default one.
+    search_engine: RefCell<Option<SearchEngine>>,
 }
 
 pub enum MinibrowserEvent {
@@ -84,6 +187,255 @@ pub enum MinibrowserEvent {
     Forward,
     Reload,
     NewWebView,
+    /// Open the given URL in a new webview, leaving the focused one focused.
+    OpenInBackground(ServoUrl),
+    /// Open each of the given URLs in a new webview.
+    OpenInNewWebViews(Vec<ServoUrl>),
+    /// Reopen the tabs from a previous session.
+    RestoreSession(Session),
+    /// Copy or save the text of the focused webview's page.
+    PageText(PageTextAction),
+    /// Install the focused webview's site as an app.
+    InstallWebApp,
+    /// Copy a cURL command that requests the focused webview's page.
+    CopyAsCurl,
+    /// Highlight the given text in the focused webview's page and move to the next or previous
+    /// match.
+    Find {
+        query: String,
+        forward: bool,
+    },
+    /// Close the find bar in the focused webview, removing the highlights.
+    CloseFind,
+    /// Evaluate the given script in the focused webview's page for the JavaScript console.
+    EvaluateInConsole(String),
+    /// Show what the focused webview's page has printed in the JavaScript console.
+    ReadConsoleOutput,
+    /// Change the page zoom of the focused webview.
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+    /// Shut down cleanly, as opposed to exiting the process immediately.
+    Quit,
+}
+
+/// A change to a tab or the tab groups made from the tab strip, applied once the tab strip is
+/// drawn.
+enum TabAction {
+    SetAutoReload(WebViewId, Option<Duration>),
+    NewGroup(WebViewId),
+    SetGroup(WebViewId, Option<TabGroupId>),
+    ToggleCollapsed(TabGroupId),
+    Rename(TabGroupId, String),
+    Ungroup(TabGroupId),
+}
+
+/// Something the user did in the Bookmarks menu, applied once the menu is drawn. Bookmarks are
+/// given by their index in [Minibrowser::bookmarks].
+enum BookmarkAction {
+    /// Open the bookmark, in a background tab if the flag is set.
+    Open(usize, bool),
+    /// Move the first bookmark to where the second one is, and into its folder.
+    Reorder(usize, usize),
+    SetNotes(usize, String),
+    /// Open the Edit Bookmark window for the bookmark.
+    Edit(usize),
+    MoveToFolder(usize, i64),
+    /// Ask for the name of a new folder in the given one, or at the top level.
+    NewFolder(Option<i64>),
+    /// Open the Edit Folder window for the folder with the given id.
+    EditFolder(i64),
+    /// Open moto:bookmarks, which lists every bookmark.
+    ShowAll,
+    /// Expand or collapse the top-level folder with the given name, for this and later sessions.
+    ToggleExpanded(String),
+}
+
+/// A bookmark folder as it is being made or changed in the New Folder or Edit Folder window.
+#[derive(Default)]
+struct FolderDraft {
+    /// The id of the folder being changed, or None for a new folder.
+    id: Option<i64>,
+    /// The folder a new folder goes in, or None for the top level.
+    parent: Option<i64>,
+    name: String,
+    icon: Option<String>,
+    color: Option<String>,
+}
+
+/// The intervals offered for reloading a tab automatically.
+const AUTO_RELOAD_INTERVALS: [(Duration, &str); 4] = [
+    (Duration::from_secs(30), "Every 30 Seconds"),
+    (Duration::from_secs(60), "Every Minute"),
+    (Duration::from_secs(5 * 60), "Every 5 Minutes"),
+    (Duration::from_secs(15 * 60), "Every 15 Minutes"),
+];
+
+/// The colors tab groups are drawn in, in the order they are assigned.
+const TAB_GROUP_COLORS: [Color32; 6] = [
+    Color32::from_rgb(26, 115, 232),
+    Color32::from_rgb(217, 48, 37),
+    Color32::from_rgb(30, 142, 62),
+    Color32::from_rgb(227, 116, 0),
+    Color32::from_rgb(161, 66, 244),
+    Color32::from_rgb(0, 131, 143),
+];
+
+/// The glyph shown before the name of a bookmark folder that doesn't have an icon of its own.
+pub const FOLDER_GLYPH: &str = "🗀";
+
+/// The icons that can be picked for a bookmark folder, besides [FOLDER_GLYPH].
+const FOLDER_ICONS: [&str; 10] = ["⭐", "💼", "🏠", "📚", "📰", "🎮", "🎵", "🛒", "🔧", "✈"];
+
+/// The glyph shown in place of a page's favicon, which Moto doesn't fetch yet.
+const FALLBACK_FAVICON: &str = "🌐";
+
+/// The colors of [FALLBACK_FAVICON] in light and dark mode respectively, picked to stand out from
+/// the label text without getting lost against either theme's background.
+const FALLBACK_FAVICON_COLORS: [Color32; 2] = [
+    Color32::from_rgb(0x45, 0x5a, 0x64),
+    Color32::from_rgb(0xb0, 0xbe, 0xc5),
+];
+
+/// Returns the given label preceded by the fallback favicon for the current theme, for anything
+/// that stands for a page, like a tab or a bookmark.
+fn with_favicon(ui: &egui::Ui, label: &str) -> LayoutJob {
+    let font_id = egui::TextStyle::Button.resolve(ui.style());
+    let [light, dark] = FALLBACK_FAVICON_COLORS;
+    let favicon_color = if ui.visuals().dark_mode { dark } else { light };
+    let mut job = LayoutJob::default();
+    job.append(
+        FALLBACK_FAVICON,
+        0.0,
+        TextFormat::simple(font_id.clone(), favicon_color),
+    );
+    job.append(
+        label,
+        6.0,
+        TextFormat::simple(font_id, Color32::PLACEHOLDER),
+    );
+    job
+}
+
+/// Returns a dialog window with the given title, which can't be collapsed or resized and starts
+/// out in the middle of the given window.
+fn modal_dialog(
+    title: impl Into<egui::WidgetText>,
+    window: &winit::window::Window,
+) -> egui::Window<'static> {
+    let size = window.inner_size();
+    egui::Window::new(title)
+        .collapsible(false)
+        .resizable(false)
+        .pivot(egui::Align2::CENTER_CENTER)
+        .default_pos((size.width as f32 / 2.0, size.height as f32 / 2.0))
+        .title_bar(true)
+}
+
+/// Returns the given http or https URL with the other of those schemes, keeping the rest of it.
+fn flip_http_scheme(url: &ServoUrl) -> Option<ServoUrl> {
+    let scheme = match url.scheme() {
+        "http" => "https",
+        "https" => "http",
+        _ => return None,
+    };
+    let mut url = url.clone().into_url();
+    url.set_scheme(scheme).ok()?;
+    Some(ServoUrl::from_url(url))
+}
+
+/// How long typing in the find bar has to pause before the page is searched.
+const FIND_DELAY: Duration = Duration::from_millis(150);
+
+/// The space at the end of the location field for its Go button, which is a toolbar button and
+/// the spacing before it.
+const GO_BUTTON_WIDTH: f32 = 28.0;
+
+/// The most pages suggested below the location field.
+const MAX_SUGGESTIONS: usize = 8;
+
+/// The widest the status text can be, as a fraction of the window's width.
+const STATUS_TEXT_MAX_WIDTH: f32 = 0.5;
+
+/// Shortens text to at most `max_chars` characters by replacing its middle with an ellipsis. URLs
+/// keep their scheme and host at the start and their file name at the end, where they fit.
+fn elide_middle(text: &str, max_chars: usize) -> String {
+    let (text, keep_start, keep_end) = match Url::parse(text) {
+        Ok(url) => {
+            let origin = url[..Position::BeforePath].chars().count();
+            let path = &url[..Position::AfterPath];
+            let file = path
+                .rfind('/')
+                .map_or(0, |slash| url.as_str()[slash..].chars().count());
+            (url.to_string(), origin, file)
+        },
+        Err(_) => (text.to_owned(), 0, 0),
+    };
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= max_chars {
+        return text;
+    }
+
+    // Keep what we can of the origin and file name, and split the rest evenly around the ellipsis.
+    let available = max_chars.saturating_sub(1);
+    let start = keep_start.min(available);
+    let end = keep_end.min(available - start);
+    let rest = available - start - end;
+    let start = start + rest - rest / 2;
+    let end = end + rest / 2;
+    let mut elided: String = chars[..start].iter().collect();
+    elided.push('…');
+    elided.extend(&chars[chars.len() - end..]);
+    elided
+}
+
+/// Opens the config page, like Preferences in other apps.
+const CONFIG_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(Modifiers::COMMAND, Key::Comma);
+
+/// Returns the URL of the given page in the translation service, using the `translate.*` prefs.
+fn translate_url(page_url: &ServoUrl) -> Option<ServoUrl> {
+    let page_url: String =
+        url::form_urlencoded::byte_serialize(page_url.as_str().as_bytes()).collect();
+    let url = moto_prefs::get_str("translate.endpoint")
+        .replace("%t", &moto_prefs::get_str("translate.target-lang"))
+        .replace("%s", &page_url);
+    ServoUrl::parse(&url).ok()
+}
+
+/// Returns true iff a page in the given language should be offered for translation.
+fn needs_translation(language: &str) -> bool {
+    let primary_subtag = |tag: &str| tag.split('-').next().unwrap_or("").to_ascii_lowercase();
+    primary_subtag(language) != primary_subtag(&moto_prefs::get_str("translate.target-lang"))
+}
+
+/// Opening more tabs than this at once asks for confirmation first.
+const MAX_TABS_WITHOUT_CONFIRMATION: usize = 10;
+
+/// The file types offered by the Open File dialog.
+const OPEN_FILE_FILTER: &[&str] = &["*.html", "*.htm", "*.xhtml", "*.svg", "*.pdf"];
+
+/// The buttons that can be shown in the toolbar, by their names in the toolbar.buttons pref, and
+/// their labels in the Customize Toolbar window.
+const TOOLBAR_BUTTONS: &[(&str, &str)] = &[
+    ("back", "Back"),
+    ("forward", "Forward"),
+    ("reload", "Reload"),
+    ("home", "Home"),
+    ("bookmark", "Bookmark"),
+    ("copy-url", "Copy URL"),
+    ("translate", "Translate"),
+    ("install", "Install Site"),
+];
+
+/// Returns the given directory in the form the file dialogs expect, which is with a trailing
+/// separator so it isn't taken as a file name.
+fn dir_for_file_dialog(dir: &Path) -> String {
+    let mut dir = dir.to_string_lossy().into_owned();
+    if !dir.ends_with(std::path::MAIN_SEPARATOR) {
+        dir.push(std::path::MAIN_SEPARATOR);
+    }
+    dir
 }
 
 fn truncate_with_ellipsis(input: &str, max_length: usize) -> String {
@@ -95,16 +447,101 @@ fn truncate_with_ellipsis(input: &str, max_length: usize) -> String {
     }
 }
 
+/// How often the JavaScript console panel shows what the page has printed, while it is open.
+const CONSOLE_READ_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The state of the JavaScript console panel, which evaluates scripts in the focused webview and
+/// shows what its page prints. The lines shown are kept by the webview.
+#[derive(Default)]
+struct Console {
+    input: String,
+    /// When the page's output was last asked for.
+    last_read: Option<Instant>,
+}
+
 pub struct Bookmark {
+    pub id: i64,
     pub url: String,
     pub title: String,
+    /// Free text the user has written about the bookmark, possibly empty.
+    pub notes: String,
+    /// The id of the [BookmarkFolder] the bookmark is in.
+    pub folder: i64,
+}
+
+pub struct BookmarkFolder {
+    pub id: i64,
+    pub name: String,
+    /// The id of the folder this one is in, or None if it is at the top level.
+    pub parent: Option<i64>,
+    /// An emoji shown before the name in place of [FOLDER_GLYPH], if the user picked one.
+    pub icon: Option<String>,
+    /// The color of the name as `#rrggbb`, if the user picked one.
+    pub color: Option<String>,
+}
+
+impl BookmarkFolder {
+    /// Returns the folder's icon, or the plain folder glyph if it doesn't have one.
+    pub fn glyph(&self) -> &str {
+        self.icon.as_deref().unwrap_or(FOLDER_GLYPH)
+    }
+
+    /// Returns the folder's color, if it has one that parses.
+    pub fn color32(&self) -> Option<Color32> {
+        Color32::from_hex(self.color.as_deref()?).ok()
+    }
+}
+
+/// Formats a color as `#rrggbb`, ignoring its alpha.
+pub fn hex_color(color: Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
 }
 
 pub struct DownloadJob {
     pub source_url: String,
     pub filename: String,
     pub downloaded_file_path: String,
-    pub handle: RefCell<Option<JoinHandle<()>>>,
+    /// The thread doing the download, until it has finished and its result is in `state`.
+    pub handle: RefCell<Option<JoinHandle<Result<(), String>>>>,
+    pub state: Cell<DownloadState>,
+    /// Set to make the download thread abort the transfer.
+    cancel: Arc<AtomicBool>,
+}
+
+impl DownloadJob {
+    /// Update the state from the download thread's result, once it has finished.
+    fn update_state(&self) {
+        let finished = self
+            .handle
+            .borrow()
+            .as_ref()
+            .is_some_and(|handle| handle.is_finished());
+        if !finished {
+            return;
+        }
+        let handle = self.handle.borrow_mut().take().unwrap();
+        let state = match handle.join() {
+            Ok(Ok(())) => DownloadState::Completed,
+            _ if self.cancel.load(Ordering::Relaxed) => DownloadState::Cancelled,
+            Ok(Err(e)) => {
+                warn!("Failed to download {} ({})", self.source_url, e);
+                DownloadState::Failed
+            },
+            Err(_) => {
+                warn!("Download thread for {} panicked", self.source_url);
+                DownloadState::Failed
+            },
+        };
+        self.state.set(state);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DownloadState {
+    InProgress,
+    Completed,
+    Failed,
+    Cancelled,
 }
 
 impl Minibrowser {
@@ -112,6 +549,7 @@ impl Minibrowser {
         rendering_context: &RenderingContext,
         events_loop: &EventsLoop,
         initial_url: ServoUrl,
+        app_mode: bool,
     ) -> Self {
         let gl = unsafe {
             glow::Context::from_loader_function(|s| rendering_context.get_proc_address(s))
@@ -120,29 +558,37 @@ impl Minibrowser {
         // Adapted from https://github.com/emilk/egui/blob/9478e50d012c5138551c38cbee16b07bc1fcf283/crates/egui_glow/examples/pure_glow.rs
         #[allow(clippy::arc_with_non_send_sync)]
         let context = EguiGlow::new(events_loop.as_winit(), Arc::new(gl), None);
+        // The zoom shortcuts zoom the page, so egui mustn't zoom the toolbar with them too.
+        context
+            .egui_ctx
+            .options_mut(|options| options.zoom_with_keyboard = false);
         let widget_surface_fbo = match rendering_context.context_surface_info() {
             Ok(Some(info)) => NonZeroU32::new(info.framebuffer_object).map(NativeFramebuffer),
             Ok(None) => panic!("Failed to get widget surface info from surfman!"),
             Err(error) => panic!("Failed to get widget surface info from surfman! {error:?}"),
         };
 
-        let config_dir = if let Some(proj_dirs) = ProjectDirs::from("org", "Moto Browser", "Moto") {
-            String::from(proj_dirs.config_dir().to_str().unwrap_or(""))
-            // Linux:   /home/alice/.config/barapp
-            // Windows: C:\Users\Alice\AppData\Roaming\Foo Corp\Bar App
-            // macOS:   /Users/Alice/Library/Application Support/com.Foo-Corp.Bar-App
-        } else {
-            // TODO: Handle this case somehow
-            String::from("")
-        };
+        let config_dir = config_dir();
 
-        let download_dir = if let Some(dir) = UserDirs::new().unwrap().download_dir() {
-            String::from(dir.to_str().unwrap_or(""))
-        } else {
-            // Assume Unix without XDG download directory set
-            String::from("~/Downloads")
-        };
+        let download_dir = UserDirs::new()
+            .map(|dirs| match dirs.download_dir() {
+                Some(dir) => dir.to_path_buf(),
+                // Assume Unix without XDG download directory set
+                None => dirs.home_dir().join("Downloads"),
+            })
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let open_file_dir = UserDirs::new()
+            .and_then(|dirs| {
+                dirs.download_dir()
+                    .map(Path::to_path_buf)
+                    .or_else(|| Some(dirs.home_dir().to_path_buf()))
+            })
+            .map(|dir| dir_for_file_dialog(&dir))
+            .unwrap_or_default();
 
+        let mut bookmarks_ready = true;
+        let mut reading_list_enabled = !config_dir.is_empty();
         if !config_dir.is_empty() {
             // Set up user config things (bookmarks)
 
@@ -151,31 +597,138 @@ impl Minibrowser {
             if !path.exists() {
                 fs::create_dir_all(path).unwrap();
             }
+            if let Err(e) = back_up_db(&config_dir, "bookmarks.sqlite") {
+                warn!("Failed to back up bookmarks DB: {}", e);
+            }
             // Check if bookmarks.sqlite exists, if not then create it
             let path = Path::new(&config_dir).join("bookmarks.sqlite");
+            let result = if !path.exists() {
+                create_bookmarks_db(config_dir.as_str())
+            } else {
+                migrate_bookmarks_db(&config_dir)
+            };
+            // Reading bookmarks from a DB in the wrong shape would fail, so leave them be.
+            if let Err(e) = result {
+                warn!(
+                    "Failed to set up bookmarks DB, not loading bookmarks: {}",
+                    e
+                );
+                bookmarks_ready = false;
+            }
+            let path = Path::new(&config_dir).join("history.sqlite");
+            let result = if !path.exists() {
+                create_history_db(&config_dir)
+            } else {
+                migrate_history_db(&config_dir)
+            };
+            if let Err(e) = result {
+                warn!("Failed to set up history DB, not recording history: {}", e);
+                disable_history();
+            }
+            let path = Path::new(&config_dir).join("site_settings.sqlite");
+            // Without the DB, permissions are asked for every time and zoom isn't remembered.
             if !path.exists() {
-                create_bookmarks_db(config_dir.as_str()).expect("Failed to create bookmarks DB!");
+                if let Err(e) = create_site_settings_db(config_dir.as_str()) {
+                    warn!("Failed to create site settings DB: {}", e);
+                }
+            }
+            let path = Path::new(&config_dir).join("reading_list.sqlite");
+            if !path.exists() {
+                if let Err(e) = create_reading_list_db(config_dir.as_str()) {
+                    warn!("Failed to create reading list DB, disabling it: {}", e);
+                    reading_list_enabled = false;
+                }
             }
+            moto_prefs::init(&config_dir);
         }
 
-        let bookmarks = get_bookmarks(&config_dir);
+        // Tell the user when their data won't outlive this session, rather than losing it quietly.
+        let toast = if config_dir.is_empty() {
+            warn!("No usable config directory, bookmarks and settings will not be saved");
+            Some("Bookmarks and settings can't be saved on this system")
+        } else if !has_persistent_config_dir() {
+            warn!("No config directory, falling back to {}", config_dir);
+            Some("Bookmarks and settings are being kept in a temporary directory")
+        } else if !bookmarks_ready {
+            Some("Your bookmarks couldn't be opened")
+        } else {
+            None
+        };
+        let toast = toast.map(|text| {
+            let until = Instant::now() + Duration::from_secs(10);
+            (text.to_owned(), until)
+        });
+
+        let crashed_session = if config_dir.is_empty() || app_mode {
+            None
+        } else {
+            unclean_session(&config_dir)
+        };
+
+        let (bookmarks, bookmark_folders) = if config_dir.is_empty() || !bookmarks_ready {
+            (vec![], vec![])
+        } else {
+            let bookmarks = get_bookmarks(&config_dir).unwrap_or_else(|e| {
+                warn!("Failed to read bookmarks: {}", e);
+                vec![]
+            });
+            let folders = get_bookmark_folders(&config_dir).unwrap_or_else(|e| {
+                warn!("Failed to read bookmark folders: {}", e);
+                vec![]
+            });
+            (bookmarks, folders)
+        };
+
+        // Give Servo the background before it makes its first document, not on the first update.
+        let default_background = Cell::new(Color32::WHITE);
+        Self::update_default_background(&default_background, &context.egui_ctx.style().visuals);
 
         Self {
             context,
             event_queue: RefCell::new(vec![]),
             toolbar_height: Default::default(),
+            webview_area: Box2D::zero(),
             widget_surface_fbo,
             last_update: Instant::now(),
             last_mouse_position: None,
-            location: RefCell::new(initial_url.to_string()),
-            location_dirty: false.into(),
+            location: RefCell::new(Location::new(initial_url.as_str())),
+            location_suggestions: RefCell::new(Suggestions::default()),
+            location_suggestions_hovered: false.into(),
             load_status: LoadStatus::LoadComplete,
+            loading_webviews: HashSet::new(),
             status_text: None,
-            show_about_window: false.into(),
+            default_background,
+            tab_strip_focus: Cell::new(None),
+            recent_tab_choice: Cell::new(None),
+            context_menu_position: Cell::new(None),
+            toast: RefCell::new(toast),
+            show_devtools_window: false.into(),
+            show_customize_toolbar_window: false.into(),
+            history_entries: RefCell::new(None),
+            bookmark_backups: RefCell::new(None),
+            show_console: false.into(),
+            focus_find_bar: false.into(),
+            console: Default::default(),
+            show_quit_dialog: false.into(),
+            show_clear_history_dialog: false.into(),
+            quit_dont_ask_again: false.into(),
+            crashed_session: RefCell::new(crashed_session),
+            app_mode,
+            reading_list_enabled,
             config_dir,
             download_dir,
+            open_file_dir: RefCell::new(open_file_dir),
             bookmarks: RefCell::new(bookmarks),
+            bookmark_folders: RefCell::new(bookmark_folders),
+            editing_folder: RefCell::new(None),
+            editing_bookmark: RefCell::new(None),
+            invalid_bookmark: RefCell::new(None),
             download_jobs: RefCell::new(vec![]),
+            downloaded_url: RefCell::new(None),
+            config_edits: RefCell::new(HashMap::new()),
+            config_saved: RefCell::new(None),
+            image_search: RefCell::new(None),
+            search_engine: RefCell::new(None),
         }
     }
 
@@ -196,7 +749,7 @@ impl Minibrowser {
                 self.last_mouse_position =
                     Some(winit_position_to_euclid_point(*position).to_f32() / scale);
                 self.last_mouse_position
-                    .map_or(false, |p| self.is_in_browser_rect(p))
+                    .map_or(false, |p| self.is_outside_webview(p))
             },
             winit::event::WindowEvent::MouseInput {
                 state: ElementState::Pressed,
@@ -216,18 +769,140 @@ impl Minibrowser {
                 self.event_queue.borrow_mut().push(MinibrowserEvent::Back);
                 true
             },
+            // Pressing a button over the page moves the keyboard focus to it, so that shortcuts like
+            // Ctrl+C go to the page rather than a toolbar widget that had focus before.
+            winit::event::WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                ..
+            } if self
+                .last_mouse_position
+                .is_some_and(|p| self.webview_area.contains(p)) =>
+            {
+                self.context.egui_ctx.memory_mut(|memory| {
+                    if let Some(id) = memory.focused() {
+                        memory.surrender_focus(id);
+                    }
+                });
+                false
+            },
+            // Keys go to the page unless a widget has focus. egui also claims Tab, which moves the
+            // focus between widgets.
+            winit::event::WindowEvent::KeyboardInput { event, .. } => {
+                event.logical_key == winit::keyboard::Key::Named(winit::keyboard::NamedKey::Tab)
+                    || self
+                        .context
+                        .egui_ctx
+                        .memory(|memory| memory.focused().is_some())
+            },
+            // Always let the window see the middle button being released, so autoscroll ends even
+            // if the pointer has left the webview.
+            winit::event::WindowEvent::MouseInput {
+                state: ElementState::Released,
+                button: MouseButton::Middle,
+                ..
+            } => false,
             winit::event::WindowEvent::MouseWheel { .. }
             | winit::event::WindowEvent::MouseInput { .. } => self
                 .last_mouse_position
-                .map_or(false, |p| self.is_in_browser_rect(p)),
+                .map_or(false, |p| self.is_outside_webview(p)),
             _ => true,
         };
         result
     }
 
-    /// Return true iff the given position is in the Servo browser rect.
-    fn is_in_browser_rect(&self, position: Point2D<f32, DeviceIndependentPixel>) -> bool {
-        position.y < self.toolbar_height.get()
+    /// Return true iff the given position is outside the webview area, so in the minibrowser's UI.
+    fn is_outside_webview(&self, position: Point2D<f32, DeviceIndependentPixel>) -> bool {
+        !self.webview_area.contains(position)
+    }
+
+    /// Returns true iff quitting with the given number of open tabs should be confirmed first.
+    /// Closing an app window always just closes it.
+    pub fn should_confirm_quit(&self, num_tabs: usize) -> bool {
+        !self.app_mode && num_tabs > 1 && moto_prefs::get_bool("warn-on-quit")
+    }
+
+    /// Replace the bookmarks with those in the given backup, once the user confirms.
+    fn restore_bookmarks(&self, backup: &Backup) {
+        let answer = tinyfiledialogs::message_box_yes_no(
+            "Restore Bookmarks",
+            &format!(
+                "Replace your bookmarks with the backup from {}?",
+                backup.age()
+            ),
+            MessageBoxIcon::Question,
+            YesNo::No,
+        );
+        if answer == YesNo::No {
+            return;
+        }
+        if let Err(e) = restore_backup(&self.config_dir, "bookmarks.sqlite", backup) {
+            warn!("Failed to restore bookmarks: {}", e);
+            *self.toast.borrow_mut() = Some((
+                "Failed to restore bookmarks".to_owned(),
+                Instant::now() + Duration::from_secs(3),
+            ));
+            return;
+        }
+        // The backup may be from before the last migration.
+        if let Err(e) = migrate_bookmarks_db(&self.config_dir) {
+            warn!("Failed to migrate bookmarks DB: {}", e);
+            *self.toast.borrow_mut() = Some((
+                "The restored bookmarks couldn't be opened".to_owned(),
+                Instant::now() + Duration::from_secs(3),
+            ));
+            return;
+        }
+        self.reload_bookmarks();
+    }
+
+    /// Read the bookmarks and folders from the DB again, after something other than the
+    /// minibrowser changed them.
+    fn reload_bookmarks(&self) {
+        match get_bookmarks(&self.config_dir) {
+            Ok(bookmarks) => *self.bookmarks.borrow_mut() = bookmarks,
+            Err(e) => warn!("Failed to read bookmarks: {}", e),
+        }
+        match get_bookmark_folders(&self.config_dir) {
+            Ok(folders) => *self.bookmark_folders.borrow_mut() = folders,
+            Err(e) => warn!("Failed to read bookmark folders: {}", e),
+        }
+    }
+
+    /// Show the dialog asking the user to confirm quitting.
+    pub fn request_quit_confirmation(&self) {
+        self.show_quit_dialog.set(true);
+    }
+
+    /// Returns the URLs on each line of the clipboard, along with the number of lines that
+    /// aren't URLs, or None if the user decides not to open that many tabs.
+    fn urls_from_clipboard() -> Option<(Vec<ServoUrl>, usize)> {
+        let text = match Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+            Ok(text) => text,
+            Err(e) => {
+                warn!("Failed to read clipboard ({})", e);
+                return None;
+            },
+        };
+        let lines = text.lines().filter(|line| !line.trim().is_empty());
+        // Lines that aren't URLs are skipped rather than searched for.
+        let (urls, invalid): (Vec<_>, Vec<_>) = lines
+            .map(location_bar_input_to_url_without_search)
+            .partition(Option::is_some);
+        let urls = urls.into_iter().flatten().collect::<Vec<_>>();
+
+        if urls.len() > MAX_TABS_WITHOUT_CONFIRMATION {
+            let message = format!("Open {} tabs from the clipboard?", urls.len());
+            let answer = tinyfiledialogs::message_box_yes_no(
+                "Open All from Clipboard",
+                &message,
+                MessageBoxIcon::Question,
+                YesNo::No,
+            );
+            if answer == YesNo::No {
+                return None;
+            }
+        }
+        Some((urls, invalid.len()))
     }
 
     /// Create a frameless button with square sizing, as used in the toolbar.
@@ -237,15 +912,412 @@ impl Minibrowser {
             .min_size(Vec2 { x: 20.0, y: 20.0 })
     }
 
-    /// Draws a browser tab, checking for clicks and returns an appropriate [EmbedderEvent]
-    /// Using a custom widget here would've been nice, but it doesn't seem as though egui
-    /// supports that, so we arrange multiple Widgets in a way that they look connected.
+    /// Draws the menu for picking the search engine used by the next search from the location bar,
+    /// showing the picked engine's initial, or a magnifying glass for the default engine.
+    fn search_engine_picker(ui: &mut egui::Ui, picked: &mut Option<SearchEngine>) {
+        let engines = search_engines();
+        let Some(default) = engines.first().cloned() else {
+            return;
+        };
+        let (icon, hint) = match picked {
+            Some(engine) => (engine.icon(), format!("Search with {}", engine.name)),
+            None => ("🔍".to_owned(), format!("Search with {}", default.name)),
+        };
+        ui.menu_button(icon, |ui| {
+            for engine in &engines {
+                let current = picked.as_ref().unwrap_or(&default) == engine;
+                let label = format!("{}  {}", engine.icon(), engine.name);
+                if ui.selectable_label(current, label).clicked() {
+                    *picked = (*engine != default).then(|| engine.clone());
+                    ui.close_menu();
+                }
+            }
+            if let Some(engine) = picked.as_ref() {
+                ui.separator();
+                if ui
+                    .button(format!("Always Search with {}", engine.name))
+                    .clicked()
+                {
+                    if let Err(e) = make_default(engine) {
+                        warn!("Failed to set default search engine: {}", e);
+                    }
+                    *picked = None;
+                    ui.close_menu();
+                }
+            }
+        })
+        .response
+        .on_hover_text(hint);
+    }
+
+    /// Returns what pressing Enter in the location field will do with the given text, which is
+    /// either going to the URL it parses as, or searching for it with the given engine if one was
+    /// picked, or the default one.
+    fn location_hint(text: &str, picked: Option<&SearchEngine>) -> String {
+        if let Some(url) = location_bar_input_to_url_without_search(text) {
+            return format!("Go to {}", truncate_with_ellipsis(url.as_str(), 80));
+        }
+        let terms = truncate_with_ellipsis(text, 60);
+        match picked.cloned().or_else(default_engine) {
+            Some(engine) => format!("Search {} for “{}”", engine.name, terms),
+            None => format!("Search for “{}”", terms),
+        }
+    }
+
+    /// Returns the bookmarks and the most frecent visited pages whose URL or title contains the
+    /// text typed in the location field, with those whose URL starts with it first.
+    fn look_up_suggestions(
+        config_dir: &str,
+        bookmarks: &[Bookmark],
+        text: &str,
+    ) -> Vec<Suggestion> {
+        let needle = text.to_lowercase();
+        let mut suggestions: Vec<Suggestion> = bookmarks
+            .iter()
+            .filter(|bookmark| {
+                bookmark.url.to_lowercase().contains(&needle)
+                    || bookmark.title.to_lowercase().contains(&needle)
+            })
+            .take(MAX_SUGGESTIONS)
+            .map(|bookmark| Suggestion {
+                url: bookmark.url.clone(),
+                title: bookmark.title.clone(),
+                bookmarked: true,
+            })
+            .collect();
+        // Without a config dir there is no history DB to search.
+        if !config_dir.is_empty() {
+            let history = get_frecent(config_dir, text, MAX_SUGGESTIONS).unwrap_or_else(|e| {
+                warn!("Failed to search history: {}", e);
+                vec![]
+            });
+            for entry in history {
+                if suggestions.len() == MAX_SUGGESTIONS {
+                    break;
+                }
+                if suggestions
+                    .iter()
+                    .all(|suggestion| suggestion.url != entry.url)
+                {
+                    suggestions.push(Suggestion {
+                        url: entry.url,
+                        title: entry.title,
+                        bookmarked: false,
+                    });
+                }
+            }
+        }
+        let starts_with_needle = |url: &str| {
+            let url = url.to_lowercase();
+            let rest = url.split_once("://").map_or(url.as_str(), |(_, rest)| rest);
+            rest.strip_prefix("www.")
+                .unwrap_or(rest)
+                .starts_with(&needle)
+                || rest.starts_with(&needle)
+        };
+        suggestions.sort_by_key(|suggestion| !starts_with_needle(&suggestion.url));
+        suggestions
+    }
+
+    /// Draws the toolbar of an app window, which has history buttons and shows where the page is
+    /// from in place of the location field, since the page could be anywhere by now.
+    fn app_toolbar(
+        ui: &mut egui::Ui,
+        focused_webview: Option<&WebView>,
+        event_queue: &RefCell<Vec<MinibrowserEvent>>,
+    ) {
+        ui.horizontal(|ui| {
+            // There are no tabs, so these never open history entries in new ones.
+            let can_go_back = focused_webview.and_then(WebView::back_url).is_some();
+            let can_go_forward = focused_webview.and_then(WebView::forward_url).is_some();
+            let buttons = [
+                ("⏴", can_go_back, MinibrowserEvent::Back),
+                ("⏵", can_go_forward, MinibrowserEvent::Forward),
+                ("↻", focused_webview.is_some(), MinibrowserEvent::Reload),
+            ];
+            for (glyph, enabled, event) in buttons {
+                if ui
+                    .add_enabled(enabled, Minibrowser::toolbar_button(glyph))
+                    .clicked()
+                {
+                    event_queue.borrow_mut().push(event);
+                }
+            }
+
+            let url = focused_webview.and_then(|webview| webview.url.as_ref());
+            Self::security_indicator(ui, url, event_queue);
+            if let Some(host) = url.and_then(|url| url.host_str()) {
+                ui.label(RichText::new(host).weak());
+            }
+        });
+    }
+
+    /// Draws a padlock for pages loaded over a secure connection, or a warning for insecure ones,
+    /// which shows the details of the connection and a link to the site's settings when clicked.
+    fn security_indicator(
+        ui: &mut egui::Ui,
+        url: Option<&ServoUrl>,
+        event_queue: &RefCell<Vec<MinibrowserEvent>>,
+    ) {
+        let Some(url) = url else {
+            return;
+        };
+        let host = url.host_str().unwrap_or_default();
+        let (icon, heading, details) = match url.scheme() {
+            "https" | "wss" => (
+                "🔒",
+                "Connection is secure",
+                format!("Your connection to {} is encrypted.", host),
+            ),
+            "http" | "ws" => (
+                "⚠",
+                "Connection is not secure",
+                format!(
+                    "Information you send to {} could be read or changed by others.",
+                    host
+                ),
+            ),
+            _ => return,
+        };
+
+        let response = ui
+            .add(Minibrowser::toolbar_button(icon))
+            .on_hover_text(heading);
+        let popup_id = ui.make_persistent_id("security_popup");
+        if response.clicked() {
+            ui.memory_mut(|memory| memory.toggle_popup(popup_id));
+        }
+        egui::popup_below_widget(ui, popup_id, &response, |ui| {
+            ui.set_min_width(300.0);
+            ui.label(RichText::new(heading).strong());
+            ui.label(details);
+            if url.scheme() == "https" || url.scheme() == "wss" {
+                ui.add_space(4.0);
+                ui.label(
+                    "Certificate and TLS details aren't available, because Servo doesn't share \
+                     them with the browser yet.",
+                );
+            }
+            ui.add_space(4.0);
+            if ui.button("Site Settings…").clicked() {
+                let query = url::form_urlencoded::Serializer::new(String::new())
+                    .append_pair("origin", &url.origin().ascii_serialization())
+                    .finish();
+                if let Ok(settings_url) = ServoUrl::parse(&format!("moto:sitesettings?{}", query)) {
+                    event_queue
+                        .borrow_mut()
+                        .push(MinibrowserEvent::OpenInNewWebViews(vec![settings_url]));
+                }
+                ui.memory_mut(|memory| memory.close_popup());
+            }
+        });
+    }
+
+    /// Draws the page zoom, unless it is 100%, as a button that resets it.
+    fn zoom_indicator(ui: &mut egui::Ui, zoom: f32, event_queue: &RefCell<Vec<MinibrowserEvent>>) {
+        let percent = (zoom * 100.0).round();
+        if percent == 100.0 {
+            return;
+        }
+        if ui
+            .button(format!("{}%", percent))
+            .on_hover_text("Reset Zoom")
+            .clicked()
+        {
+            event_queue.borrow_mut().push(MinibrowserEvent::ZoomReset);
+        }
+    }
+
+    /// Draws the field for a numeric pref on the config page, returning the new value if the user
+    /// changed it to something that parses. Text that doesn't parse is kept and outlined in red,
+    /// rather than being replaced by the current value.
+    fn config_number_field<T: FromStr + ToString>(
+        ui: &mut egui::Ui,
+        key: &str,
+        value: T,
+        hint: &str,
+        edits: &mut HashMap<String, String>,
+    ) -> (egui::Response, Option<T>) {
+        let mut text = edits.get(key).cloned().unwrap_or_else(|| value.to_string());
+        let field = ui.text_edit_singleline(&mut text);
+        let parsed = text.parse().ok();
+        if field.changed() {
+            edits.insert(key.to_owned(), text);
+        }
+        if parsed.is_none() {
+            ui.painter().rect_stroke(
+                field.rect.expand(1.0),
+                2.0,
+                egui::Stroke::new(1.5, Color32::RED),
+            );
+            ui.label(RichText::new(hint).color(Color32::RED).small());
+        } else if field.lost_focus() {
+            edits.remove(key);
+        }
+        let changed = field.changed();
+        (field, parsed.filter(|_| changed))
+    }
+
+    /// Draws the editor for an array pref on the config page, returning the new array if the user
+    /// changed it. Each element gets the field for its type and can be moved or removed, and new
+    /// elements take the type of the first one, or are strings if the array is empty. Numbers that
+    /// don't parse are kept out of the array until they do.
+    fn config_array_field(
+        ui: &mut egui::Ui,
+        key: &str,
+        mut values: Vec<PrefValue>,
+        edits: &mut HashMap<String, String>,
+    ) -> (egui::Response, Option<Vec<PrefValue>>) {
+        let new_element = match values.first() {
+            Some(PrefValue::Float(_)) => PrefValue::Float(0.0),
+            Some(PrefValue::Int(_)) => PrefValue::Int(0),
+            Some(PrefValue::Bool(_)) => PrefValue::Bool(false),
+            _ => PrefValue::Str(String::new()),
+        };
+        let mut changed = false;
+        let mut moved = None;
+        let mut removed = None;
+        let mut added = false;
+        let response = ui
+            .vertical(|ui| {
+                let len = values.len();
+                for (index, value) in values.iter_mut().enumerate() {
+                    let element_key = format!("{}[{}]", key, index);
+                    ui.horizontal(|ui| {
+                        match value {
+                            PrefValue::Float(f) => {
+                                let (_, new_value) = Self::config_number_field(
+                                    ui,
+                                    &element_key,
+                                    *f,
+                                    "Not a number",
+                                    edits,
+                                );
+                                if let Some(new_value) = new_value {
+                                    *f = new_value;
+                                    changed = true;
+                                }
+                            },
+                            PrefValue::Int(i) => {
+                                let (_, new_value) = Self::config_number_field(
+                                    ui,
+                                    &element_key,
+                                    *i,
+                                    "Not a whole number",
+                                    edits,
+                                );
+                                if let Some(new_value) = new_value {
+                                    *i = new_value;
+                                    changed = true;
+                                }
+                            },
+                            PrefValue::Str(s) => changed |= ui.text_edit_singleline(s).changed(),
+                            PrefValue::Bool(b) => changed |= ui.checkbox(b, "").clicked(),
+                            PrefValue::Array(_) | PrefValue::Missing => {
+                                ui.label(
+                                    RichText::new("Can't be edited here").color(Color32::GRAY),
+                                );
+                            },
+                        }
+                        let up = ui.add_enabled(index > 0, Minibrowser::toolbar_button("⏶"));
+                        if up.on_hover_text("Move up").clicked() {
+                            moved = Some((index, index - 1));
+                        }
+                        let down =
+                            ui.add_enabled(index + 1 < len, Minibrowser::toolbar_button("⏷"));
+                        if down.on_hover_text("Move down").clicked() {
+                            moved = Some((index, index + 1));
+                        }
+                        let remove = ui.add(Minibrowser::toolbar_button("✖"));
+                        if remove.on_hover_text("Remove").clicked() {
+                            removed = Some(index);
+                        }
+                    });
+                }
+                added = ui.button("Add").clicked();
+            })
+            .response;
+
+        if let Some((from, to)) = moved {
+            values.swap(from, to);
+        }
+        if let Some(index) = removed {
+            values.remove(index);
+        }
+        if added {
+            values.push(new_element);
+        }
+        let restructured = moved.is_some() || removed.is_some() || added;
+        if restructured {
+            // Unparsed numbers belong to the element that was at their index.
+            let prefix = format!("{}[", key);
+            edits.retain(|edit_key, _| !edit_key.starts_with(&prefix));
+        }
+        (response, (changed || restructured).then_some(values))
+    }
+
+    /// Returns how many entries the Bookmarks and History menus show. A limit below one shows
+    /// every entry.
+    fn menu_max_entries() -> usize {
+        match usize::try_from(moto_prefs::get_int("ui.menu-max-entries")) {
+            Ok(max_entries) if max_entries > 0 => max_entries,
+            _ => usize::MAX,
+        }
+    }
+
+    /// Moves focus between the given menu entries with the up and down arrow keys. Focused
+    /// entries are activated with Enter like any other egui button. Arrow keys are left alone
+    /// while a text field, such as the bookmark notes editor, has focus.
+    fn navigate_menu_with_keys(ui: &mut egui::Ui, entries: &[egui::Response]) {
+        if entries.is_empty() || ui.ctx().wants_keyboard_input() {
+            return;
+        }
+        let (down, up) = ui.input_mut(|i| {
+            (
+                i.consume_key(Modifiers::NONE, Key::ArrowDown),
+                i.consume_key(Modifiers::NONE, Key::ArrowUp),
+            )
+        });
+        let focused = entries.iter().position(|entry| entry.has_focus());
+        let next = match (focused, down, up) {
+            (Some(index), true, false) => (index + 1) % entries.len(),
+            (Some(index), false, true) => index.checked_sub(1).unwrap_or(entries.len() - 1),
+            (None, true, false) => 0,
+            (None, false, true) => entries.len() - 1,
+            _ => return,
+        };
+        entries[next].request_focus();
+        entries[next].scroll_to_me(None);
+    }
+
+    /// Returns the event for a click on the Back or Forward button, which opens the adjacent
+    /// history entry in a new webview on Ctrl+click or middle-click, if there is one.
+    fn history_button_event(
+        response: &egui::Response,
+        url: Option<&ServoUrl>,
+        navigate: MinibrowserEvent,
+    ) -> Option<MinibrowserEvent> {
+        let new_webview = response.middle_clicked()
+            || (response.clicked() && response.ctx.input(|i| i.modifiers.command));
+        match url {
+            Some(url) if new_webview => {
+                Some(MinibrowserEvent::OpenInNewWebViews(vec![url.clone()]))
+            },
+            _ if response.clicked() || response.middle_clicked() => Some(navigate),
+            _ => None,
+        }
+    }
+
+    /// Draws a browser tab, checking for clicks and returns an appropriate [EmbedderEvent] along
+    /// with the tab's [egui::Response]. Using a custom widget here would've been nice, but it
+    /// doesn't seem as though egui supports that, so we arrange multiple Widgets in a way that they
+    /// look connected.
     fn browser_tab(
         ui: &mut egui::Ui,
         label: &str,
         selected: bool,
         webview_id: TopLevelBrowsingContextId,
-    ) -> Option<EmbedderEvent> {
+    ) -> (Option<EmbedderEvent>, egui::Response) {
         let old_item_spacing = ui.spacing().item_spacing;
         let old_visuals = ui.visuals().clone();
         let active_bg_color = old_visuals.widgets.active.weak_bg_fill;
@@ -283,7 +1355,7 @@ impl Minibrowser {
 
         let tab = ui.add(SelectableLabel::new(
             selected,
-            truncate_with_ellipsis(label, 20),
+            with_favicon(ui, &truncate_with_ellipsis(label, 20)),
         ));
         let tab = tab.on_hover_ui(|ui| {
             ui.label(label);
@@ -309,39 +1381,468 @@ impl Minibrowser {
         ui.spacing_mut().item_spacing = old_item_spacing;
         let close_button = ui.add(egui::Button::new("X").fill(fill_color));
         *ui.visuals_mut() = old_visuals;
-        if close_button.clicked() || close_button.middle_clicked() || tab.middle_clicked() {
-            Some(EmbedderEvent::CloseWebView(webview_id))
-        } else if !selected && tab.clicked() {
-            Some(EmbedderEvent::FocusWebView(webview_id))
-        } else {
-            None
-        }
+        let event =
+            if close_button.clicked() || close_button.middle_clicked() || tab.middle_clicked() {
+                Some(EmbedderEvent::CloseWebView(webview_id))
+            } else if !selected && tab.clicked() {
+                Some(EmbedderEvent::FocusWebView(webview_id))
+            } else {
+                None
+            };
+        (event, tab)
     }
 
-    /// Update the minibrowser, but don’t paint.
-    /// If `servo_framebuffer_id` is given, set up a paint callback to blit its contents to our
-    /// CentralPanel when [`Minibrowser::paint`] is called.
-    pub fn update(
-        &mut self,
-        window: &winit::window::Window,
-        webviews: &mut WebViewManager<dyn WindowPortsMethods>,
-        servo_framebuffer_id: Option<gl::GLuint>,
-        reason: &'static str,
+    /// Draws a browser tab as a row of the vertical tab strip, like [Minibrowser::browser_tab].
+    /// The close button is only shown while the row is hovered.
+    fn vertical_browser_tab(
+        ui: &mut egui::Ui,
+        label: &str,
+        selected: bool,
+        webview_id: TopLevelBrowsingContextId,
+    ) -> (Option<EmbedderEvent>, egui::Response) {
+        ui.horizontal(|ui| {
+            let hovered = ui.rect_contains_pointer(ui.max_rect());
+            let tab = ui.add_sized(
+                [ui.available_width() - 24.0, 20.0],
+                SelectableLabel::new(
+                    selected,
+                    with_favicon(ui, &truncate_with_ellipsis(label, 28)),
+                ),
+            );
+            let tab = tab.on_hover_ui(|ui| {
+                ui.label(label);
+            });
+            let close_clicked = hovered && {
+                let close_button = ui.add(Minibrowser::toolbar_button("X"));
+                close_button.clicked() || close_button.middle_clicked()
+            };
+
+            let event = if close_clicked || tab.middle_clicked() {
+                Some(EmbedderEvent::CloseWebView(webview_id))
+            } else if !selected && tab.clicked() {
+                Some(EmbedderEvent::FocusWebView(webview_id))
+            } else {
+                None
+            };
+            (event, tab)
+        })
+        .inner
+    }
+
+    /// Draws the chip that starts a tab group in the tab strip. Clicking it collapses or expands
+    /// the group, and its context menu can rename or ungroup it.
+    fn tab_group_chip(
+        ui: &mut egui::Ui,
+        group_id: TabGroupId,
+        group: &TabGroup,
+        tab_actions: &mut Vec<TabAction>,
     ) {
-        let now = Instant::now();
-        trace!(
-            "{:?} since last update ({})",
-            now - self.last_update,
-            reason
-        );
+        let color = TAB_GROUP_COLORS[group.color % TAB_GROUP_COLORS.len()];
+        let arrow = if group.collapsed { "▸" } else { "▾" };
+        let text = RichText::new(format!("{} {}", arrow, group.name)).color(Color32::WHITE);
+        let chip = ui.add(egui::Button::new(text).fill(color).rounding(8.0));
+        if chip.clicked() {
+            tab_actions.push(TabAction::ToggleCollapsed(group_id));
+        }
+        chip.context_menu(|ui| {
+            let mut name = group.name.clone();
+            if ui.text_edit_singleline(&mut name).changed() {
+                tab_actions.push(TabAction::Rename(group_id, name));
+            }
+            if ui.button("Ungroup").clicked() {
+                tab_actions.push(TabAction::Ungroup(group_id));
+                ui.close_menu();
+            }
+        });
+    }
+
+    /// Fills the context menu of a tab with entries for moving it between tab groups.
+    fn tab_group_menu(
+        ui: &mut egui::Ui,
+        webviews: &WebViewManager<dyn WindowPortsMethods>,
+        webview_id: TopLevelBrowsingContextId,
+        tab_actions: &mut Vec<TabAction>,
+    ) {
+        let current_group_id = webviews.tab_group(webview_id).map(|(id, _)| id);
+        if ui.button("Add to New Group").clicked() {
+            tab_actions.push(TabAction::NewGroup(webview_id));
+            ui.close_menu();
+        }
+        for (group_id, group) in webviews.tab_groups() {
+            if Some(group_id) != current_group_id
+                && ui.button(format!("Add to {}", group.name)).clicked()
+            {
+                tab_actions.push(TabAction::SetGroup(webview_id, Some(group_id)));
+                ui.close_menu();
+            }
+        }
+        if current_group_id.is_some() && ui.button("Remove from Group").clicked() {
+            tab_actions.push(TabAction::SetGroup(webview_id, None));
+            ui.close_menu();
+        }
+    }
+
+    /// Returns true iff the given menu entry was middle-clicked or Ctrl-clicked (Cmd-clicked on
+    /// macOS), which opens it in a new background tab.
+    fn open_in_background_clicked(ui: &egui::Ui, entry: &egui::Response) -> bool {
+        entry.middle_clicked() || (entry.clicked() && ui.input(|i| i.modifiers.command))
+    }
+
+    /// Draws the entries of the given bookmark folder in the Bookmarks menu, or those of the top
+    /// level if `folder` is None: its subfolders as submenus, then its bookmarks. Top-level
+    /// folders named in the bookmarks.expanded-folders pref are drawn inline instead.
+    fn bookmark_menu_entries(
+        ui: &mut egui::Ui,
+        folders: &[BookmarkFolder],
+        bookmarks: &[Bookmark],
+        folder: Option<i64>,
+        actions: &mut Vec<BookmarkAction>,
+    ) {
+        let expanded_folders = if folder.is_none() {
+            moto_prefs::get_str_array("bookmarks.expanded-folders")
+        } else {
+            vec![]
+        };
+        let mut entries = vec![];
+        for subfolder in folders
+            .iter()
+            .filter(|subfolder| subfolder.parent == folder)
+        {
+            let mut label = RichText::new(format!("{} {}", subfolder.glyph(), subfolder.name));
+            if let Some(color) = subfolder.color32() {
+                label = label.color(color);
+            }
+            if folder.is_none() {
+                let expanded = expanded_folders.contains(&subfolder.name);
+                let entry = ui
+                    .horizontal(|ui| {
+                        let (arrow, hover_text) = if expanded {
+                            ("▾", "Collapse")
+                        } else {
+                            ("▸", "Expand")
+                        };
+                        let toggle = ui.small_button(arrow).on_hover_text(hover_text);
+                        if toggle.clicked() {
+                            actions.push(BookmarkAction::ToggleExpanded(subfolder.name.clone()));
+                        }
+                        if expanded {
+                            ui.label(label);
+                            return toggle;
+                        }
+                        ui.menu_button(label, |ui| {
+                            Self::bookmark_menu_entries(
+                                ui,
+                                folders,
+                                bookmarks,
+                                Some(subfolder.id),
+                                actions,
+                            );
+                        })
+                        .response
+                    })
+                    .inner;
+                entries.push(entry);
+                if expanded {
+                    ui.indent(subfolder.id, |ui| {
+                        Self::bookmark_menu_entries(
+                            ui,
+                            folders,
+                            bookmarks,
+                            Some(subfolder.id),
+                            actions,
+                        );
+                    });
+                }
+                continue;
+            }
+            let submenu = ui.menu_button(label, |ui| {
+                Self::bookmark_menu_entries(ui, folders, bookmarks, Some(subfolder.id), actions);
+            });
+            entries.push(submenu.response);
+        }
+
+        let folder_bookmarks: Vec<_> = bookmarks
+            .iter()
+            .enumerate()
+            .filter(|(_, bookmark)| Some(bookmark.folder) == folder)
+            .collect();
+        let max_entries = Self::menu_max_entries();
+        for &(index, bookmark) in folder_bookmarks.iter().take(max_entries) {
+            let button = egui::Button::new(with_favicon(ui, &bookmark.title))
+                .min_size((256.0, 20.0).into())
+                .sense(egui::Sense::click_and_drag());
+            let mut button = ui
+                .horizontal(|ui| {
+                    let button = ui.add(button);
+                    if ui
+                        .small_button("✏")
+                        .on_hover_text("Edit Bookmark")
+                        .clicked()
+                    {
+                        actions.push(BookmarkAction::Edit(index));
+                        ui.close_menu();
+                    }
+                    button
+                })
+                .inner;
+            entries.push(button.clone());
+
+            // Bookmarks can be dragged onto each other to reorder them, including onto those in
+            // other folders to move them there.
+            button.dnd_set_drag_payload(index);
+            if button.dnd_hover_payload::<usize>().is_some() {
+                let rect = button.rect;
+                ui.painter()
+                    .hline(rect.x_range(), rect.top(), ui.visuals().selection.stroke);
+            }
+            if let Some(from) = button.dnd_release_payload::<usize>() {
+                actions.push(BookmarkAction::Reorder(*from, index));
+            }
+            if !bookmark.notes.is_empty() {
+                button = button.on_hover_text(&bookmark.notes);
+            }
+            let open_in_background = Self::open_in_background_clicked(ui, &button);
+            if open_in_background || button.clicked() {
+                actions.push(BookmarkAction::Open(index, open_in_background));
+                ui.close_menu();
+            }
+            button.context_menu(|ui| {
+                ui.label("Notes");
+                let mut notes = bookmark.notes.clone();
+                if ui.text_edit_multiline(&mut notes).changed() {
+                    actions.push(BookmarkAction::SetNotes(index, notes));
+                }
+                ui.menu_button("Move to Folder", |ui| {
+                    for other in folders.iter().filter(|other| other.id != bookmark.folder) {
+                        if ui.button(&other.name).clicked() {
+                            actions.push(BookmarkAction::MoveToFolder(index, other.id));
+                            ui.close_menu();
+                        }
+                    }
+                });
+            });
+        }
+        if folder_bookmarks.len() > max_entries {
+            let show_all = ui.button("Show All Bookmarks");
+            if show_all.clicked() {
+                actions.push(BookmarkAction::ShowAll);
+            }
+            entries.push(show_all);
+        }
+
+        let new_folder = ui.button("New Folder…");
+        if new_folder.clicked() {
+            actions.push(BookmarkAction::NewFolder(folder));
+            ui.close_menu();
+        }
+        entries.push(new_folder);
+        if let Some(folder) = folder {
+            let edit_folder = ui.button("Edit Folder…");
+            if edit_folder.clicked() {
+                actions.push(BookmarkAction::EditFolder(folder));
+                ui.close_menu();
+            }
+            entries.push(edit_folder);
+        }
+
+        // Arrow keys move through the open submenu that has focus, or the top level until one does.
+        if folder.is_none() || entries.iter().any(|entry| entry.has_focus()) {
+            Self::navigate_menu_with_keys(ui, &entries);
+        }
+    }
+
+    /// Draws the Auto Reload submenu of a tab's context menu.
+    fn auto_reload_menu(
+        ui: &mut egui::Ui,
+        webview: &WebView,
+        webview_id: WebViewId,
+        tab_actions: &mut Vec<TabAction>,
+    ) {
+        let current = webview.auto_reload_interval();
+        ui.menu_button("Auto Reload", |ui| {
+            let mut choice = None;
+            if ui.radio(current.is_none(), "Off").clicked() {
+                choice = Some(None);
+            }
+            for (interval, label) in AUTO_RELOAD_INTERVALS {
+                if ui.radio(current == Some(interval), label).clicked() {
+                    choice = Some(Some(interval));
+                }
+            }
+            if let Some(interval) = choice {
+                tab_actions.push(TabAction::SetAutoReload(webview_id, interval));
+                ui.close_menu();
+            }
+        });
+        ui.separator();
+    }
+
+    /// Draws every tab of the tab strip, preceded by a chip wherever a tab group starts, and
+    /// returns the [EmbedderEvent]s for any clicks. The tabs of collapsed groups are skipped,
+    /// unless focused. If `scroll_to_focused` is true, the focused tab is scrolled into view.
+    fn tab_strip_tabs(
+        ui: &mut egui::Ui,
+        webviews: &WebViewManager<dyn WindowPortsMethods>,
+        vertical: bool,
+        scroll_to_focused: bool,
+        tab_actions: &mut Vec<TabAction>,
+    ) -> Vec<EmbedderEvent> {
+        let mut events = vec![];
+        let mut previous_group_id = None;
+        for (webview_id, webview) in webviews.webviews().into_iter() {
+            let group = webviews.tab_group(webview_id);
+            let group_id = group.map(|(id, _)| id);
+            if let Some((group_id, group)) = group.filter(|_| group_id != previous_group_id) {
+                Self::tab_group_chip(ui, group_id, group, tab_actions);
+            }
+            previous_group_id = group_id;
+            if group.is_some_and(|(_, group)| group.collapsed) && !webview.focused {
+                continue;
+            }
+
+            let label = Self::tab_label(webview);
+            let (event, tab) = if vertical {
+                Self::vertical_browser_tab(ui, &label, webview.focused, webview_id)
+            } else {
+                Self::browser_tab(ui, &label, webview.focused, webview_id)
+            };
+            if webview.focused && scroll_to_focused {
+                tab.scroll_to_me(None);
+            }
+            tab.context_menu(|ui| {
+                Self::auto_reload_menu(ui, webview, webview_id, tab_actions);
+                Self::tab_group_menu(ui, webviews, webview_id, tab_actions);
+            });
+            events.extend(event);
+        }
+        events
+    }
+
+    /// Returns the label shown for the given webview in the tab strip.
+    fn tab_label(webview: &WebView) -> String {
+        let label = match (&webview.title, &webview.url) {
+            (Some(title), _) if !title.is_empty() => title.clone(),
+            (_, Some(url)) => url.to_string(),
+            _ => "New Tab".to_owned(),
+        };
+        let label = if webview.auto_reload_interval().is_some() {
+            format!("↻ {}", label)
+        } else {
+            label
+        };
+        if webview.load_status == LoadStatus::LoadComplete {
+            label
+        } else {
+            format!("⏳ {}", label)
+        }
+    }
+
+    /// Asks the user whether to reload a secure page without encryption.
+    fn confirm_http_downgrade() -> bool {
+        let answer = tinyfiledialogs::message_box_yes_no(
+            "Reload over HTTP",
+            "The page will be reloaded without encryption, so others on the network could see \
+             or change it. Continue?",
+            MessageBoxIcon::Warning,
+            YesNo::No,
+        );
+        answer == YesNo::Yes
+    }
+
+    /// Open the config page in the focused webview, or go back if it's already there.
+    fn toggle_config_page(
+        is_config: bool,
+        location: &RefCell<Location>,
+        event_queue: &RefCell<Vec<MinibrowserEvent>>,
+    ) {
+        if is_config {
+            event_queue.borrow_mut().push(MinibrowserEvent::Back);
+        } else {
+            location.borrow_mut().set_text("moto:config".to_owned());
+            event_queue.borrow_mut().push(MinibrowserEvent::Go);
+        }
+    }
+
+    /// Returns the color to show behind pages before they paint. The browser.default-background
+    /// pref is a hex color like "#1b1b1b", or "auto" to match the light or dark theme.
+    fn default_background(visuals: &egui::Visuals) -> Color32 {
+        let pref = moto_prefs::get_str("browser.default-background");
+        if pref != "auto" {
+            if let Ok(color) = Color32::from_hex(&pref) {
+                return color;
+            }
+        }
+        if visuals.dark_mode {
+            visuals.panel_fill
+        } else {
+            Color32::WHITE
+        }
+    }
+
+    /// Gives Servo the default background for the theme if it changed, and returns the color that
+    /// Servo shows behind pages. `written` is the color we last gave Servo, starting with Servo's
+    /// own white; if shell.background-color.rgba no longer matches it, the user set it themselves
+    /// in moto:config, and that wins over the theme.
+    fn update_default_background(written: &Cell<Color32>, visuals: &egui::Visuals) -> Color32 {
+        let prefs = servo::config::prefs::pref_map();
+        let current = match prefs.get("shell.background-color.rgba") {
+            PrefValue::Array(values) if values.len() == 4 => {
+                let mut rgba = [0.0; 4];
+                for (component, value) in rgba.iter_mut().zip(values) {
+                    if let PrefValue::Float(value) = value {
+                        *component = value as f32;
+                    }
+                }
+                let [r, g, b, a] =
+                    rgba.map(|component| (component.clamp(0.0, 1.0) * 255.0).round() as u8);
+                Some(Color32::from_rgba_premultiplied(r, g, b, a))
+            },
+            _ => None,
+        };
+        if let Some(current) = current.filter(|current| *current != written.get()) {
+            return current;
+        }
+        let default_background = Self::default_background(visuals);
+        if written.get() != default_background {
+            written.set(default_background);
+            let rgba = default_background
+                .to_normalized_gamma_f32()
+                .map(|component| PrefValue::Float(component.into()));
+            let value = PrefValue::Array(rgba.into());
+            if let Err(e) = prefs.set("shell.background-color.rgba", value) {
+                warn!("Failed to set default background: {:?}", e);
+            }
+        }
+        default_background
+    }
+
+    /// Update the minibrowser, but don’t paint.
+    /// If `servo_framebuffer_id` is given, set up a paint callback to blit its contents to our
+    /// CentralPanel when [`Minibrowser::paint`] is called.
+    pub fn update(
+        &mut self,
+        window: &winit::window::Window,
+        webviews: &mut WebViewManager<dyn WindowPortsMethods>,
+        servo_framebuffer_id: Option<gl::GLuint>,
+        reason: &'static str,
+    ) {
+        let now = Instant::now();
+        trace!(
+            "{:?} since last update ({})",
+            now - self.last_update,
+            reason
+        );
+        let confirm_quit = self.should_confirm_quit(webviews.webviews().len());
+        let mut backup_to_restore = None;
+        let mut download = None;
         let Self {
             context,
             event_queue,
             toolbar_height,
+            webview_area,
             widget_surface_fbo,
             last_update,
             location,
-            location_dirty,
             ..
         } = self;
         let widget_fbo = *widget_surface_fbo;
@@ -349,16 +1850,94 @@ impl Minibrowser {
             // TODO: While in fullscreen add some way to mitigate the increased phishing risk
             // when not displaying the URL bar: https://github.com/servo/servo/issues/32443
             let current_url = webviews.current_url_string().unwrap_or("".to_owned());
-            let is_config = current_url == "moto:config";
-            // Check if the URL is a file URL by seeing if it contains a file extension
-            if current_url.split('/').last().unwrap_or("").contains('.') {
+            // Pages like moto:config are drawn here rather than by Servo.
+            let drawn_page = webviews
+                .focused_webview()
+                .and_then(|webview| webview.url.as_ref())
+                .and_then(drawn_page_name);
+            let is_config = drawn_page.as_deref() == Some("config");
+            // Go back from links to files, and download them instead.
+            let is_download = ServoUrl::parse(&current_url).is_ok_and(|url| is_download_url(&url));
+            if !is_download {
+                self.downloaded_url.take();
+            } else if self.downloaded_url.borrow().as_ref() != Some(&current_url) {
+                self.downloaded_url.replace(Some(current_url.clone()));
                 event_queue.borrow_mut().push(MinibrowserEvent::Back);
 
-                let download_dir = self.download_dir.clone();
-                let job = Minibrowser::download_file(current_url.clone(), download_dir);
-                self.download_jobs.borrow_mut().push(job);
+                let filename = current_url.split('/').last().unwrap_or("").to_owned();
+                download = Some((current_url.clone(), filename));
+            }
+
+            // Servo shows this behind the document until it paints, and so do we, so that pages
+            // don't flash white in dark mode.
+            let default_background =
+                Self::update_default_background(&self.default_background, &ctx.style().visuals);
+
+            // Selected text and links follow the system's accent color if the user wants that.
+            let accent = moto_prefs::get_bool("ui.use-system-accent")
+                .then(system_accent_color)
+                .flatten();
+            let default_visuals = if ctx.style().visuals.dark_mode {
+                egui::Visuals::dark()
+            } else {
+                egui::Visuals::light()
+            };
+            let mut selection = default_visuals.selection;
+            selection.bg_fill = accent.unwrap_or(selection.bg_fill);
+            let hyperlink_color = accent.unwrap_or(default_visuals.hyperlink_color);
+            let style = ctx.style();
+            if style.visuals.selection != selection
+                || style.visuals.hyperlink_color != hyperlink_color
+            {
+                ctx.style_mut(|style| {
+                    style.visuals.selection = selection;
+                    style.visuals.hyperlink_color = hyperlink_color;
+                });
+            }
+
+            // egui's own transitions, such as menus fading in, are animations too.
+            let animation_time = if animations_allowed() {
+                egui::Style::default().animation_time
+            } else {
+                0.0
+            };
+            if ctx.style().animation_time != animation_time {
+                ctx.style_mut(|style| style.animation_time = animation_time);
+            }
+
+            let shortcut_enabled = enabled_shortcuts();
+            if shortcut_enabled("preferences")
+                && ctx.input_mut(|i| i.consume_shortcut(&CONFIG_SHORTCUT))
+            {
+                Self::toggle_config_page(is_config, location, event_queue);
+            }
+
+            // F6 moves keyboard focus between the toolbar and the page, and Ctrl+Shift+E always
+            // gives it back to the page, so it can't get stuck in an egui widget.
+            let toolbar_focus = shortcut_enabled("toolbar-focus");
+            let f6_pressed =
+                toolbar_focus && ctx.input_mut(|i| i.consume_key(Modifiers::NONE, Key::F6));
+            let focus_page_pressed = toolbar_focus
+                && ctx.input_mut(|i| i.consume_key(Modifiers::COMMAND | Modifiers::SHIFT, Key::E));
+            match ctx.memory(|memory| memory.focused()) {
+                Some(id) if f6_pressed || focus_page_pressed => {
+                    ctx.memory_mut(|memory| memory.surrender_focus(id));
+                },
+                None if f6_pressed && window.fullscreen().is_none() => {
+                    let location_id = egui::Id::new("location_input");
+                    ctx.memory_mut(|memory| memory.request_focus(location_id));
+                },
+                _ => {},
             }
-            if window.fullscreen().is_none() {
+
+            if window.fullscreen().is_none() && self.app_mode {
+                let frame = egui::Frame::default()
+                    .fill(ctx.style().visuals.window_fill)
+                    .inner_margin(4.0);
+                TopBottomPanel::top("toolbar").frame(frame).show(ctx, |ui| {
+                    Self::app_toolbar(ui, webviews.focused_webview(), event_queue);
+                });
+            } else if window.fullscreen().is_none() {
                 let frame = egui::Frame::default()
                     .fill(ctx.style().visuals.window_fill)
                     .inner_margin(4.0);
@@ -366,249 +1945,1618 @@ impl Minibrowser {
                     menu::bar(ui, |ui| {
                         ui.menu_button("File", |ui| {
                             if ui.button("Open File").clicked() {
-                                let mut file_url = String::from("file:///");
-                                let file_path = open_file_dialog("File Picker", "C:/", None);
+                                let file_path = open_file_dialog(
+                                    "Open File",
+                                    &self.open_file_dir.borrow(),
+                                    Some((OPEN_FILE_FILTER, "Web pages, images and documents")),
+                                );
                                 if let Some(path) = file_path {
-                                    file_url.push_str(&path);
-                                    *location.borrow_mut() = file_url;
+                                    if let Some(dir) = Path::new(&path).parent() {
+                                        *self.open_file_dir.borrow_mut() = dir_for_file_dialog(dir);
+                                    }
+                                    match file_path_to_url(&path) {
+                                        Some(file_url) => {
+                                            location.borrow_mut().set_text(file_url.into_string());
+                                            event_queue.borrow_mut().push(MinibrowserEvent::Go);
+                                        },
+                                        None => warn!("Failed to convert {} to a URL", path),
+                                    }
+                                }
+                                ui.close_menu();
+                            }
+                            ui.separator();
+                            if ui.button("Exit").clicked() {
+                                if confirm_quit {
+                                    self.show_quit_dialog.set(true);
+                                } else {
+                                    event_queue.borrow_mut().push(MinibrowserEvent::Quit);
+                                }
+                                ui.close_menu();
+                            }
+                        });
+                        let bookmarks_menu = ui.menu_button("Bookmarks", |ui| {
+                            let mut actions = vec![];
+                            Self::bookmark_menu_entries(
+                                ui,
+                                &self.bookmark_folders.borrow(),
+                                &self.bookmarks.borrow(),
+                                None,
+                                &mut actions,
+                            );
+                            for action in actions {
+                                let mut bookmarks = self.bookmarks.borrow_mut();
+                                match action {
+                                    BookmarkAction::Open(index, open_in_background) => {
+                                        let bookmark = &bookmarks[index];
+                                        match location_bar_input_to_url(&bookmark.url) {
+                                            None => {
+                                                *self.toast.borrow_mut() = Some((
+                                                    "Invalid bookmark URL".to_owned(),
+                                                    Instant::now() + Duration::from_secs(3),
+                                                ));
+                                                *self.invalid_bookmark.borrow_mut() =
+                                                    Some((bookmark.id, bookmark.url.clone()));
+                                            },
+                                            Some(url) if open_in_background => {
+                                                event_queue
+                                                    .borrow_mut()
+                                                    .push(MinibrowserEvent::OpenInBackground(url));
+                                            },
+                                            Some(_) => {
+                                                location
+                                                    .borrow_mut()
+                                                    .set_text(bookmark.url.clone());
+                                                event_queue.borrow_mut().push(MinibrowserEvent::Go);
+                                            },
+                                        }
+                                    },
+                                    BookmarkAction::Reorder(from, to) if from != to => {
+                                        let folder = bookmarks[to].folder;
+                                        let mut bookmark = bookmarks.remove(from);
+                                        if bookmark.folder != folder {
+                                            match move_bookmark(
+                                                &self.config_dir,
+                                                bookmark.id,
+                                                folder,
+                                            ) {
+                                                Ok(()) => bookmark.folder = folder,
+                                                Err(e) => warn!("Failed to move bookmark: {}", e),
+                                            }
+                                        }
+                                        // Removing the bookmark shifts those after it back one.
+                                        let to = if from < to { to - 1 } else { to };
+                                        bookmarks.insert(to, bookmark);
+                                        let ids: Vec<i64> = bookmarks
+                                            .iter()
+                                            .filter(|b| b.folder == folder)
+                                            .map(|b| b.id)
+                                            .collect();
+                                        if let Err(e) =
+                                            set_bookmark_order(&self.config_dir, folder, &ids)
+                                        {
+                                            warn!("Failed to reorder bookmarks: {}", e);
+                                        }
+                                    },
+                                    BookmarkAction::Reorder(..) => {},
+                                    BookmarkAction::SetNotes(index, notes) => {
+                                        let bookmark = &mut bookmarks[index];
+                                        match update_bookmark(
+                                            &self.config_dir,
+                                            bookmark.id,
+                                            &bookmark.url,
+                                            &bookmark.title,
+                                            &notes,
+                                        ) {
+                                            Ok(()) => bookmark.notes = notes,
+                                            Err(e) => warn!("Failed to update bookmark: {}", e),
+                                        }
+                                    },
+                                    BookmarkAction::Edit(index) => {
+                                        let bookmark = &bookmarks[index];
+                                        *self.editing_bookmark.borrow_mut() = Some((
+                                            bookmark.id,
+                                            bookmark.title.clone(),
+                                            bookmark.url.clone(),
+                                        ));
+                                    },
+                                    BookmarkAction::MoveToFolder(index, folder) => {
+                                        let id = bookmarks[index].id;
+                                        match move_bookmark(&self.config_dir, id, folder) {
+                                            // Like in the DB, it goes at the end of the folder.
+                                            Ok(()) => {
+                                                let mut bookmark = bookmarks.remove(index);
+                                                bookmark.folder = folder;
+                                                bookmarks.push(bookmark);
+                                            },
+                                            Err(e) => warn!("Failed to move bookmark: {}", e),
+                                        }
+                                    },
+                                    BookmarkAction::NewFolder(parent) => {
+                                        *self.editing_folder.borrow_mut() = Some(FolderDraft {
+                                            parent,
+                                            ..FolderDraft::default()
+                                        });
+                                    },
+                                    BookmarkAction::EditFolder(id) => {
+                                        let folders = self.bookmark_folders.borrow();
+                                        let folder = folders.iter().find(|folder| folder.id == id);
+                                        if let Some(folder) = folder {
+                                            *self.editing_folder.borrow_mut() = Some(FolderDraft {
+                                                id: Some(id),
+                                                parent: folder.parent,
+                                                name: folder.name.clone(),
+                                                icon: folder.icon.clone(),
+                                                color: folder.color.clone(),
+                                            });
+                                        }
+                                    },
+                                    BookmarkAction::ShowAll => {
+                                        location.borrow_mut().set_text("moto:bookmarks".to_owned());
+                                        event_queue.borrow_mut().push(MinibrowserEvent::Go);
+                                        ui.close_menu();
+                                    },
+                                    BookmarkAction::ToggleExpanded(name) => {
+                                        let mut expanded =
+                                            moto_prefs::get_str_array("bookmarks.expanded-folders");
+                                        match expanded.iter().position(|other| *other == name) {
+                                            Some(position) => {
+                                                expanded.remove(position);
+                                            },
+                                            None => expanded.push(name),
+                                        }
+                                        let value = PrefValue::Array(
+                                            expanded.into_iter().map(PrefValue::Str).collect(),
+                                        );
+                                        if let Err(e) =
+                                            moto_prefs::set("bookmarks.expanded-folders", value)
+                                        {
+                                            warn!("Failed to save expanded folders: {}", e);
+                                        }
+                                    },
+                                }
+                            }
+                            ui.separator();
+                            if ui.button("Manage Bookmarks").clicked() {
+                                location.borrow_mut().set_text("moto:bookmarks".to_owned());
+                                event_queue.borrow_mut().push(MinibrowserEvent::Go);
+                                ui.close_menu();
+                            }
+                            let mut bookmark_backups = self.bookmark_backups.borrow_mut();
+                            let backups = bookmark_backups.get_or_insert_with(|| {
+                                list_backups(&self.config_dir, "bookmarks.sqlite")
+                            });
+                            ui.add_enabled_ui(!backups.is_empty(), |ui| {
+                                ui.menu_button("Restore from Backup", |ui| {
+                                    for backup in backups.iter() {
+                                        let label = format!("Backup from {}", backup.age());
+                                        if ui.button(label).clicked() {
+                                            ui.close_menu();
+                                            backup_to_restore = Some(backup.clone());
+                                        }
+                                    }
+                                });
+                            });
+                        });
+                        if bookmarks_menu.inner.is_none() {
+                            *self.bookmark_backups.borrow_mut() = None;
+                        }
+                        ui.add_enabled_ui(self.reading_list_enabled, |ui| {
+                            ui.menu_button("Reading List", |ui| {
+                                if ui.button("Save to Reading List").clicked() {
+                                    if let Some(webview) = webviews.focused_webview() {
+                                        if let Some(url) = &webview.url {
+                                            let title = webview.title.clone().unwrap_or_default();
+                                            if let Err(e) = add_to_reading_list(
+                                                &self.config_dir,
+                                                url.as_str(),
+                                                &title,
+                                            ) {
+                                                warn!("Failed to save to reading list: {}", e);
+                                            }
+                                        }
+                                    }
+                                    ui.close_menu();
+                                }
+                                if ui.button("Show Reading List").clicked() {
+                                    location
+                                        .borrow_mut()
+                                        .set_text("moto:readinglist".to_owned());
+                                    event_queue.borrow_mut().push(MinibrowserEvent::Go);
+                                    ui.close_menu();
+                                }
+                            });
+                        });
+                        let history_menu = ui.menu_button("History", |ui| {
+                            let mut history_entries = self.history_entries.borrow_mut();
+                            let history = history_entries.get_or_insert_with(|| {
+                                // Without a config dir there is no DB, only this session's history.
+                                if self.config_dir.is_empty() {
+                                    return webviews
+                                        .history()
+                                        .into_iter()
+                                        .map(|url| HistoryEntry {
+                                            url: url.to_string(),
+                                            title: String::new(),
+                                            visited: String::new(),
+                                        })
+                                        .collect();
+                                }
+                                // One more than is shown, to know whether to offer the rest.
+                                let limit = Self::menu_max_entries().saturating_add(1);
+                                get_history(&self.config_dir, limit).unwrap_or_else(|e| {
+                                    warn!("Failed to read history: {}", e);
+                                    vec![]
+                                })
+                            });
+                            let history_len = history.len();
+                            let max_entries = Self::menu_max_entries();
+                            let mut entries = vec![];
+                            for entry in history.iter().take(max_entries) {
+                                let Ok(url) = ServoUrl::parse(&entry.url) else {
+                                    continue;
+                                };
+                                let label = if entry.title.is_empty() {
+                                    &entry.url
+                                } else {
+                                    &entry.title
+                                };
+                                // TODO: Prevent Servo from receiving cursor events while hovering these
+                                let button = egui::Button::new(with_favicon(ui, label))
+                                    .min_size((256.0, 20.0).into());
+                                let button = ui.add(button).on_hover_text(&entry.url);
+                                if Self::open_in_background_clicked(ui, &button) {
+                                    event_queue
+                                        .borrow_mut()
+                                        .push(MinibrowserEvent::OpenInBackground(url));
+                                    ui.close_menu();
+                                } else if button.clicked() {
+                                    location.borrow_mut().set_text(url.to_string());
                                     event_queue.borrow_mut().push(MinibrowserEvent::Go);
+                                    ui.close_menu();
+                                }
+                                entries.push(button);
+                            }
+                            if history_len > max_entries {
+                                let show_all = ui.button("Show All History");
+                                if show_all.clicked() {
+                                    location.borrow_mut().set_text("moto:history".to_owned());
+                                    event_queue.borrow_mut().push(MinibrowserEvent::Go);
+                                    ui.close_menu();
+                                }
+                                entries.push(show_all);
+                            }
+                            ui.separator();
+                            let clear = ui
+                                .add_enabled(history_len > 0, egui::Button::new("Clear History…"));
+                            if clear.clicked() {
+                                self.show_clear_history_dialog.set(true);
+                                ui.close_menu();
+                            }
+                            entries.push(clear);
+                            Self::navigate_menu_with_keys(ui, &entries);
+                        });
+                        if history_menu.inner.is_none() {
+                            *self.history_entries.borrow_mut() = None;
+                        }
+                        ui.menu_button("View", |ui| {
+                            if ui.button("Customize Toolbar…").clicked() {
+                                self.show_customize_toolbar_window.set(true);
+                                ui.close_menu();
+                            }
+                            let mut vertical_tabs = moto_prefs::get_bool("tabs.vertical");
+                            if ui.checkbox(&mut vertical_tabs, "Vertical Tabs").clicked() {
+                                let value = PrefValue::Bool(vertical_tabs);
+                                if let Err(e) = moto_prefs::set("tabs.vertical", value) {
+                                    warn!("Failed to set pref: {}", e);
+                                }
+                                ui.close_menu();
+                            }
+                        });
+                        ui.menu_button("Tools", |ui| {
+                            if ui.button("Translate Page").clicked() {
+                                let url = webviews
+                                    .focused_webview()
+                                    .and_then(|webview| webview.url.as_ref())
+                                    .and_then(translate_url);
+                                if let Some(url) = url {
+                                    event_queue
+                                        .borrow_mut()
+                                        .push(MinibrowserEvent::OpenInNewWebViews(vec![url]));
+                                }
+                                ui.close_menu();
+                            }
+                            let config_button = egui::Button::new("Preferences")
+                                .shortcut_text(ui.ctx().format_shortcut(&CONFIG_SHORTCUT));
+                            if ui.add(config_button).clicked() {
+                                Self::toggle_config_page(is_config, location, event_queue);
+                                ui.close_menu();
+                            }
+                            if ui.button("Developer Tools").clicked() {
+                                self.show_devtools_window.set(true);
+                                ui.close_menu();
+                            }
+                        });
+                        ui.menu_button("Help", |ui| {
+                            if ui.button("About Moto").clicked() {
+                                location.borrow_mut().set_text("moto:about".to_owned());
+                                event_queue.borrow_mut().push(MinibrowserEvent::Go);
+                                ui.close_menu();
+                            }
+                        });
+                    });
+                    ui.allocate_ui_with_layout(
+                        ui.available_size(),
+                        egui::Layout::left_to_right(egui::Align::Center),
+                        |ui| {
+                            let focused_webview = webviews.focused_webview();
+                            for name in moto_prefs::get_str_array("toolbar.buttons") {
+                                match name.as_str() {
+                                    // Ctrl+click or middle-click opens the history entry in a new tab.
+                                    "back" => {
+                                        let back_button = ui.add(Minibrowser::toolbar_button("⏴"));
+                                        if let Some(event) = Self::history_button_event(
+                                            &back_button,
+                                            focused_webview.and_then(WebView::back_url),
+                                            MinibrowserEvent::Back,
+                                        ) {
+                                            event_queue.borrow_mut().push(event);
+                                        }
+                                    },
+                                    "forward" => {
+                                        let forward_button =
+                                            ui.add(Minibrowser::toolbar_button("⏵"));
+                                        if let Some(event) = Self::history_button_event(
+                                            &forward_button,
+                                            focused_webview.and_then(WebView::forward_url),
+                                            MinibrowserEvent::Forward,
+                                        ) {
+                                            event_queue.borrow_mut().push(event);
+                                        }
+                                    },
+                                    "reload" => match self.load_status {
+                                        LoadStatus::LoadStart | LoadStatus::HeadParsed => {
+                                            if ui.add(Minibrowser::toolbar_button("X")).clicked() {
+                                                warn!("Do not support stop yet.");
+                                            }
+                                        },
+                                        LoadStatus::LoadComplete => {
+                                            if ui.add(Minibrowser::toolbar_button("↻")).clicked()
+                                            {
+                                                event_queue
+                                                    .borrow_mut()
+                                                    .push(MinibrowserEvent::Reload);
+                                            }
+                                        },
+                                    },
+                                    "home" => {
+                                        if ui
+                                            .add(Minibrowser::toolbar_button("⌂"))
+                                            .on_hover_text("Home")
+                                            .clicked()
+                                        {
+                                            location
+                                                .borrow_mut()
+                                                .set_text("moto:newtab".to_owned());
+                                            event_queue.borrow_mut().push(MinibrowserEvent::Go);
+                                        }
+                                    },
+                                    "bookmark" => {
+                                        let has_bookmark = self
+                                            .bookmarks
+                                            .borrow()
+                                            .iter()
+                                            .any(|b| b.url == location.borrow().text());
+                                        let star = if has_bookmark { "★" } else { "☆" };
+                                        // A tab that is still loading may not have a URL yet.
+                                        let page = focused_webview.and_then(|webview| {
+                                            Some((webview.url.as_ref()?, &webview.title))
+                                        });
+                                        let star_button = ui.add_enabled(
+                                            page.is_some(),
+                                            Minibrowser::toolbar_button(star),
+                                        );
+                                        let Some((url, title)) =
+                                            page.filter(|_| star_button.clicked())
+                                        else {
+                                            continue;
+                                        };
+                                        let url = url.to_string();
+                                        let title = title.clone().unwrap_or_default();
+                                        if !has_bookmark {
+                                            // Add bookmark to DB
+                                            if let Ok(id) =
+                                                add_bookmark(&self.config_dir, &url, &title, "")
+                                            {
+                                                self.bookmarks.borrow_mut().push(Bookmark {
+                                                    id,
+                                                    url,
+                                                    title,
+                                                    notes: String::new(),
+                                                    folder: UNSORTED_FOLDER_ID,
+                                                });
+                                            }
+                                        } else {
+                                            // Remove bookmark from DB
+                                            let mut bookmarks = self.bookmarks.borrow_mut();
+                                            let ids: Vec<i64> = bookmarks
+                                                .iter()
+                                                .filter(|b| b.url == url)
+                                                .map(|b| b.id)
+                                                .collect();
+                                            for id in ids {
+                                                if remove_bookmark(&self.config_dir, id).is_ok() {
+                                                    bookmarks.retain(|b| b.id != id);
+                                                }
+                                            }
+                                        }
+                                    },
+                                    "copy-url" => {
+                                        let url = focused_webview
+                                            .and_then(|webview| webview.url.as_ref());
+                                        if ui
+                                            .add(Minibrowser::toolbar_button("⎘"))
+                                            .on_hover_text("Copy URL")
+                                            .clicked()
+                                        {
+                                            if let Some(url) = url {
+                                                let text = url.to_string();
+                                                ui.ctx().output_mut(|o| o.copied_text = text);
+                                            }
+                                        }
+                                    },
+                                    "translate" => {
+                                        let translatable_url = focused_webview
+                                            .filter(|webview| {
+                                                webview
+                                                    .language
+                                                    .as_deref()
+                                                    .is_some_and(needs_translation)
+                                            })
+                                            .and_then(|webview| webview.url.as_ref())
+                                            .and_then(translate_url);
+                                        if let Some(url) = translatable_url {
+                                            if ui
+                                                .add(Minibrowser::toolbar_button("文A"))
+                                                .on_hover_text("Translate this page")
+                                                .clicked()
+                                            {
+                                                event_queue.borrow_mut().push(
+                                                    MinibrowserEvent::OpenInNewWebViews(vec![url]),
+                                                );
+                                            }
+                                        }
+                                    },
+                                    "install" => {
+                                        let installable = focused_webview
+                                            .is_some_and(|webview| webview.has_manifest);
+                                        if installable
+                                            && ui
+                                                .add(Minibrowser::toolbar_button("⊕"))
+                                                .on_hover_text("Install this site as an app")
+                                                .clicked()
+                                        {
+                                            event_queue
+                                                .borrow_mut()
+                                                .push(MinibrowserEvent::InstallWebApp);
+                                        }
+                                    },
+                                    // Buttons this version doesn't know about are left out.
+                                    _ => {},
+                                }
+                            }
+                            ui.add_space(2.0);
+
+                            ui.allocate_ui_with_layout(
+                                ui.available_size(),
+                                egui::Layout::right_to_left(egui::Align::Center),
+                                |ui| {
+                                    for job in self.download_jobs.borrow().iter() {
+                                        job.update_state();
+                                    }
+                                    let num_jobs = self
+                                        .download_jobs
+                                        .borrow()
+                                        .iter()
+                                        .filter(|job| job.state.get() == DownloadState::InProgress)
+                                        .count();
+                                    let download_text = if num_jobs > 0 {
+                                        format!("Downloads ({})", num_jobs)
+                                    } else {
+                                        "Downloads".to_string()
+                                    };
+                                    ui.menu_button(download_text, |ui| {
+                                        for job in self.download_jobs.borrow_mut().iter_mut().rev()
+                                        {
+                                            // TODO: Prevent Servo from receiving cursor events while hovering these
+                                            let state = job.state.get();
+                                            let text = match state {
+                                                DownloadState::InProgress => "Downloading...",
+                                                DownloadState::Completed => "Completed",
+                                                DownloadState::Failed => "Failed",
+                                                DownloadState::Cancelled => "Cancelled",
+                                            };
+                                            ui.label(format!("{}\n{}", &job.filename, text));
+                                            ui.horizontal(|ui| match state {
+                                                DownloadState::InProgress => {
+                                                    if ui.button("Cancel").clicked() {
+                                                        job.cancel.store(true, Ordering::Relaxed);
+                                                    }
+                                                },
+                                                DownloadState::Completed => {
+                                                    if ui.button("Open").clicked() {
+                                                        Minibrowser::open_file(
+                                                            &job.downloaded_file_path,
+                                                        );
+                                                        ui.close_menu();
+                                                    }
+                                                    if ui.button("Open containing folder").clicked()
+                                                    {
+                                                        let path =
+                                                            Path::new(&job.downloaded_file_path);
+                                                        if let Some(dir) = path.parent() {
+                                                            Minibrowser::open_file(
+                                                                &dir.to_string_lossy(),
+                                                            );
+                                                        }
+                                                        ui.close_menu();
+                                                    }
+                                                },
+                                                DownloadState::Failed
+                                                | DownloadState::Cancelled => {
+                                                    if ui.button("Retry").clicked() {
+                                                        *job = Minibrowser::download_file(
+                                                            job.source_url.clone(),
+                                                            job.downloaded_file_path.clone(),
+                                                        );
+                                                    }
+                                                },
+                                            });
+                                            ui.separator();
+                                        }
+                                    });
+                                    let location_id = egui::Id::new("location_input");
+
+                                    // Undo and redo are ours rather than egui's, so that they
+                                    // also cover text replaced by the page's URL.
+                                    if ui.memory(|memory| memory.has_focus(location_id)) {
+                                        let redo = ui.input_mut(|i| {
+                                            i.consume_key(
+                                                Modifiers::COMMAND | Modifiers::SHIFT,
+                                                Key::Z,
+                                            ) || i.consume_key(Modifiers::COMMAND, Key::Y)
+                                        });
+                                        let undo = !redo
+                                            && ui.input_mut(|i| {
+                                                i.consume_key(Modifiers::COMMAND, Key::Z)
+                                            });
+                                        let restored = if redo {
+                                            location.borrow_mut().redo()
+                                        } else if undo {
+                                            location.borrow_mut().undo()
+                                        } else {
+                                            false
+                                        };
+                                        if restored {
+                                            if let Some(mut state) =
+                                                TextEditState::load(ui.ctx(), location_id)
+                                            {
+                                                let end = CCursor::new(
+                                                    location.borrow().text().chars().count(),
+                                                );
+                                                state
+                                                    .cursor
+                                                    .set_char_range(Some(CCursorRange::one(end)));
+                                                state.store(ui.ctx(), location_id);
+                                            }
+                                        }
+                                    }
+
+                                    // The arrow keys pick a suggestion rather than moving the
+                                    // cursor.
+                                    if ui.memory(|memory| memory.has_focus(location_id))
+                                        && location.borrow().is_edited()
+                                    {
+                                        let mut suggestions =
+                                            self.location_suggestions.borrow_mut();
+                                        if ui.input_mut(|i| {
+                                            i.consume_key(Modifiers::NONE, Key::ArrowDown)
+                                        }) {
+                                            suggestions.select_next();
+                                        }
+                                        if ui.input_mut(|i| {
+                                            i.consume_key(Modifiers::NONE, Key::ArrowUp)
+                                        }) {
+                                            suggestions.select_previous();
+                                        }
+                                    }
+
+                                    // Enter is taken before the field sees it, so each press
+                                    // submits exactly once, whatever happens to focus.
+                                    let submitted = consume_enter(ui, location_id);
+                                    let location_before_edit = location.borrow().text().to_owned();
+                                    let location_field = ui
+                                        .with_layout(
+                                            egui::Layout::left_to_right(egui::Align::Center),
+                                            |ui| {
+                                                let url = webviews
+                                                    .focused_webview()
+                                                    .and_then(|webview| webview.url.as_ref());
+                                                Self::search_engine_picker(
+                                                    ui,
+                                                    &mut self.search_engine.borrow_mut(),
+                                                );
+                                                Self::security_indicator(ui, url, event_queue);
+                                                let zoom = webviews
+                                                    .focused_webview()
+                                                    .map_or(1.0, |webview| webview.zoom());
+                                                Self::zoom_indicator(ui, zoom, event_queue);
+                                                let field_size = ui.available_size()
+                                                    - Vec2::new(GO_BUTTON_WIDTH, 0.0);
+                                                let field = ui.add_sized(
+                                                    field_size,
+                                                    egui::TextEdit::singleline(
+                                                        location.borrow_mut().text_mut(),
+                                                    )
+                                                    .id(location_id),
+                                                );
+                                                // Go once the location has been edited, so
+                                                // Enter isn't the only way, or reload otherwise.
+                                                let (glyph, hint, event) =
+                                                    if location.borrow().is_edited() {
+                                                        ("→", "Go", MinibrowserEvent::Go)
+                                                    } else {
+                                                        ("↻", "Reload", MinibrowserEvent::Reload)
+                                                    };
+                                                if ui
+                                                    .add(Minibrowser::toolbar_button(glyph))
+                                                    .on_hover_text(hint)
+                                                    .clicked()
+                                                {
+                                                    event_queue.borrow_mut().push(event);
+                                                }
+                                                field
+                                            },
+                                        )
+                                        .inner;
+
+                                    if location_field.changed() {
+                                        location.borrow_mut().record_edit(location_before_edit);
+                                    }
+                                    location_field.context_menu(|ui| {
+                                        if ui.button("Open All from Clipboard").clicked() {
+                                            if let Some((urls, skipped)) =
+                                                Self::urls_from_clipboard()
+                                            {
+                                                if skipped > 0 {
+                                                    *self.toast.borrow_mut() = Some((
+                                                        format!(
+                                                            "Skipped {} invalid lines",
+                                                            skipped
+                                                        ),
+                                                        Instant::now() + Duration::from_secs(3),
+                                                    ));
+                                                }
+                                                event_queue.borrow_mut().push(
+                                                    MinibrowserEvent::OpenInNewWebViews(urls),
+                                                );
+                                            }
+                                            ui.close_menu();
+                                        }
+                                        let image_endpoint =
+                                            moto_prefs::get_str("search.image-endpoint");
+                                        if !image_endpoint.is_empty()
+                                            && ui.button("Search Image from Clipboard").clicked()
+                                        {
+                                            *self.image_search.borrow_mut() =
+                                                Some(Self::search_clipboard_image(image_endpoint));
+                                            ui.close_menu();
+                                        }
+                                    });
+                                    if shortcut_enabled("focus-location")
+                                        && ui.input(|i| {
+                                            i.clone().consume_key(Modifiers::COMMAND, Key::L)
+                                        })
+                                    {
+                                        location_field.request_focus();
+                                        if let Some(mut state) =
+                                            TextEditState::load(ui.ctx(), location_id)
+                                        {
+                                            // Select the whole input.
+                                            state.cursor.set_char_range(Some(CCursorRange::two(
+                                                CCursor::new(0),
+                                                CCursor::new(
+                                                    location.borrow().text().chars().count(),
+                                                ),
+                                            )));
+                                            state.store(ui.ctx(), location_id);
+                                        }
+                                    }
+                                    // Say whether Enter will go to a URL or search, so that is
+                                    // never a surprise, followed by any matching bookmarks and
+                                    // visited pages.
+                                    let text = location.borrow().text().trim().to_owned();
+                                    let mut suggestions = self.location_suggestions.borrow_mut();
+                                    let mut picked = None;
+                                    let mut go = submitted;
+                                    if (location_field.has_focus()
+                                        || self.location_suggestions_hovered.get())
+                                        && location.borrow().is_edited()
+                                        && !text.is_empty()
+                                    {
+                                        match suggestions.wait(&text, Instant::now()) {
+                                            Some(delay) if delay.is_zero() => {
+                                                let entries = Self::look_up_suggestions(
+                                                    &self.config_dir,
+                                                    &self.bookmarks.borrow(),
+                                                    &text,
+                                                );
+                                                suggestions.set(&text, entries);
+                                            },
+                                            Some(delay) => ui.ctx().request_repaint_after(delay),
+                                            None => {},
+                                        }
+                                        let hint = Self::location_hint(
+                                            &text,
+                                            self.search_engine.borrow().as_ref(),
+                                        );
+                                        let width = location_field.rect.width();
+                                        let area = egui::Area::new("location_hint".into())
+                                            .fixed_pos(location_field.rect.left_bottom())
+                                            .order(egui::Order::Foreground)
+                                            .show(ui.ctx(), |ui| {
+                                                Frame::popup(ui.style()).show(ui, |ui| {
+                                                    ui.set_width(width);
+                                                    // The hint is what Enter does until a
+                                                    // suggestion is picked.
+                                                    let hint_selected =
+                                                        suggestions.selected().is_none();
+                                                    go |= ui
+                                                        .add(SelectableLabel::new(
+                                                            hint_selected,
+                                                            hint,
+                                                        ))
+                                                        .clicked();
+                                                    for (index, suggestion) in
+                                                        suggestions.entries().iter().enumerate()
+                                                    {
+                                                        let badge = if suggestion.bookmarked {
+                                                            "★"
+                                                        } else {
+                                                            "🕘"
+                                                        };
+                                                        let url = truncate_with_ellipsis(
+                                                            &suggestion.url,
+                                                            80,
+                                                        );
+                                                        let label = if suggestion.title.is_empty() {
+                                                            format!("{} {}", badge, url)
+                                                        } else {
+                                                            format!(
+                                                                "{} {} — {}",
+                                                                badge,
+                                                                truncate_with_ellipsis(
+                                                                    &suggestion.title,
+                                                                    60
+                                                                ),
+                                                                url
+                                                            )
+                                                        };
+                                                        let selected = suggestions.selected_index()
+                                                            == Some(index);
+                                                        if ui
+                                                            .add(SelectableLabel::new(
+                                                                selected, label,
+                                                            ))
+                                                            .clicked()
+                                                        {
+                                                            picked = Some(suggestion.url.clone());
+                                                        }
+                                                    }
+                                                });
+                                            });
+                                        self.location_suggestions_hovered
+                                            .set(area.response.contains_pointer());
+                                        if submitted {
+                                            picked = picked.or_else(|| {
+                                                suggestions
+                                                    .selected()
+                                                    .map(|suggestion| suggestion.url.clone())
+                                            });
+                                        }
+                                    } else {
+                                        suggestions.clear();
+                                        self.location_suggestions_hovered.set(false);
+                                    }
+                                    if let Some(url) = picked {
+                                        location.borrow_mut().set_text(url);
+                                        go = true;
+                                    }
+                                    if go {
+                                        event_queue.borrow_mut().push(MinibrowserEvent::Go);
+                                        // Leave the keyboard to the page being loaded.
+                                        location_field.surrender_focus();
+                                    }
+                                },
+                            );
+                        },
+                    );
+                });
+
+                // Customize Toolbar Window
+                if self.show_customize_toolbar_window.get() {
+                    modal_dialog("Customize Toolbar", window).show(ctx, |ui| {
+                        let mut buttons = moto_prefs::get_str_array("toolbar.buttons");
+                        let mut changed = false;
+                        for (name, label) in TOOLBAR_BUTTONS {
+                            let mut shown = buttons.iter().any(|button| button == name);
+                            if ui.checkbox(&mut shown, *label).changed() {
+                                if shown {
+                                    buttons.push(name.to_string());
+                                } else {
+                                    buttons.retain(|button| button != name);
+                                }
+                                changed = true;
+                            }
+                        }
+                        if changed {
+                            let value =
+                                PrefValue::Array(buttons.into_iter().map(PrefValue::Str).collect());
+                            if let Err(e) = moto_prefs::set("toolbar.buttons", value) {
+                                warn!("Failed to set pref: {}", e);
+                            }
+                        }
+                        ui.add_space(8.0);
+                        if ui.button("Close").clicked() {
+                            self.show_customize_toolbar_window.set(false);
+                        }
+                    });
+                }
+            };
+
+            if shortcut_enabled("find")
+                && ctx.input_mut(|i| i.consume_key(Modifiers::COMMAND, Key::F))
+            {
+                webviews.open_find_bar();
+                self.focus_find_bar.set(true);
+            }
+
+            // These are taken even when the page has focus, so they zoom the page and not egui.
+            if shortcut_enabled("zoom") {
+                let zoom = ctx.input_mut(|i| {
+                    if i.consume_key(Modifiers::COMMAND, Key::Equals)
+                        || i.consume_key(Modifiers::COMMAND, Key::Plus)
+                    {
+                        Some(MinibrowserEvent::ZoomIn)
+                    } else if i.consume_key(Modifiers::COMMAND, Key::Minus) {
+                        Some(MinibrowserEvent::ZoomOut)
+                    } else if i.consume_key(Modifiers::COMMAND, Key::Num0) {
+                        Some(MinibrowserEvent::ZoomReset)
+                    } else {
+                        None
+                    }
+                });
+                if let Some(event) = zoom {
+                    event_queue.borrow_mut().push(event);
+                }
+            }
+
+            if shortcut_enabled("devtools")
+                // Ctrl+F12 is left to the WebRender profiler binding.
+                && ctx.input_mut(|i| {
+                    i.consume_key(Modifiers::NONE, Key::F12)
+                        || i.consume_key(Modifiers::COMMAND | Modifiers::SHIFT, Key::I)
+                })
+            {
+                self.show_devtools_window
+                    .set(!self.show_devtools_window.get());
+            }
+
+            // Developer Tools Window
+            if self.show_devtools_window.get() {
+                modal_dialog("Developer Tools", window).show(ctx, |ui| {
+                    if let Some(port) = webviews.devtools_port() {
+                        ui.label(format!(
+                            "The devtools server is listening on port {}.",
+                            port
+                        ));
+                        ui.label("Connect to it from about:debugging in Firefox.");
+                        if ui.button("Copy Connect Address").clicked() {
+                            ctx.output_mut(|o| o.copied_text = format!("localhost:{}", port));
+                        }
+                    } else if moto_prefs::get_bool("devtools.enabled") {
+                        ui.label("The devtools server will start when Moto is restarted.");
+                    } else {
+                        ui.label("The devtools server is not running.");
+                        if ui.button("Enable Devtools Server").clicked() {
+                            let value = PrefValue::Bool(true);
+                            if let Err(e) = moto_prefs::set("devtools.enabled", value) {
+                                warn!("Failed to set pref: {}", e);
+                            }
+                        }
+                    }
+                    ui.add_space(8.0);
+                    if ui.button("Close").clicked() {
+                        self.show_devtools_window.set(false);
+                    }
+                });
+            }
+
+            let mut embedder_events = vec![];
+            let mut tab_actions = vec![];
+            let focused_webview_id = webviews.focused_webview_id();
+            let focus_changed =
+                self.tab_strip_focus.replace(focused_webview_id) != focused_webview_id;
+
+            // Ctrl+Tab walks the tabs from most to least recently used while Ctrl is held, like
+            // Alt+Tab, and switches to the chosen one when Ctrl is released.
+            let num_recent_tabs = webviews.recently_focused_webviews().len();
+            if shortcut_enabled("recent-tabs") && !self.app_mode && num_recent_tabs > 1 {
+                // Ctrl+Tab would also match Ctrl+Shift+Tab, so that goes first.
+                let backward =
+                    ctx.input_mut(|i| i.consume_key(Modifiers::CTRL | Modifiers::SHIFT, Key::Tab));
+                let forward =
+                    !backward && ctx.input_mut(|i| i.consume_key(Modifiers::CTRL, Key::Tab));
+                let choice = match (self.recent_tab_choice.get(), forward, backward) {
+                    (Some(index), true, _) => Some((index + 1) % num_recent_tabs),
+                    (Some(index), _, true) => Some((index + num_recent_tabs - 1) % num_recent_tabs),
+                    (None, true, _) => Some(1),
+                    (None, _, true) => Some(num_recent_tabs - 1),
+                    (choice, false, false) => choice,
+                };
+                self.recent_tab_choice.set(choice);
+            }
+            if let Some(choice) = self.recent_tab_choice.get() {
+                let recent_tabs = webviews.recently_focused_webviews();
+                if !ctx.input(|i| i.modifiers.ctrl) {
+                    self.recent_tab_choice.set(None);
+                    if let Some((webview_id, _)) = recent_tabs.get(choice) {
+                        location.borrow_mut().discard_edit();
+                        embedder_events.push(EmbedderEvent::FocusWebView(*webview_id));
+                    }
+                    // Leave the keyboard to the page, even if egui moved focus on Tab.
+                    if let Some(id) = ctx.memory(|memory| memory.focused()) {
+                        ctx.memory_mut(|memory| memory.surrender_focus(id));
+                    }
+                } else {
+                    egui::Area::new("recent_tabs".into())
+                        .anchor(egui::Align2::CENTER_CENTER, (0.0, 0.0))
+                        .order(egui::Order::Foreground)
+                        .interactable(false)
+                        .show(ctx, |ui| {
+                            Frame::popup(ui.style()).show(ui, |ui| {
+                                ui.set_min_width(320.0);
+                                for (index, (_, webview)) in recent_tabs.iter().enumerate() {
+                                    let label =
+                                        truncate_with_ellipsis(&Self::tab_label(webview), 48);
+                                    let label = with_favicon(ui, &label);
+                                    ui.add(SelectableLabel::new(index == choice, label));
+                                }
+                            });
+                        });
+                }
+            }
+
+            if self.app_mode {
+                // App windows show one page, without tabs.
+            } else if moto_prefs::get_bool("tabs.vertical") {
+                // A vertical Tab list, for when there are too many tabs to fit across
+                SidePanel::left("tabs")
+                    .resizable(false)
+                    .exact_width(200.0)
+                    .show(ctx, |ui| {
+                        // egui keeps the scroll offset from frame to frame, so it only moves to
+                        // bring a newly focused tab into view.
+                        let scroll_area =
+                            egui::containers::ScrollArea::vertical().id_source("tab_strip");
+                        scroll_area.show(ui, |ui| {
+                            let tabs = Self::tab_strip_tabs(
+                                ui,
+                                webviews,
+                                true,
+                                focus_changed,
+                                &mut tab_actions,
+                            );
+                            for event in tabs {
+                                location.borrow_mut().discard_edit();
+                                embedder_events.push(event);
+                            }
+                            if ui.add(Minibrowser::toolbar_button("+")).clicked() {
+                                event_queue.borrow_mut().push(MinibrowserEvent::NewWebView);
+                            }
+                        });
+                    });
+            } else {
+                // A simple Tab header strip
+                TopBottomPanel::top("tabs").show(ctx, |ui| {
+                    ui.allocate_ui_with_layout(
+                        ui.available_size(),
+                        egui::Layout::left_to_right(egui::Align::Center),
+                        |ui| {
+                            let tabs = Self::tab_strip_tabs(
+                                ui,
+                                webviews,
+                                false,
+                                focus_changed,
+                                &mut tab_actions,
+                            );
+                            for event in tabs {
+                                location.borrow_mut().discard_edit();
+                                embedder_events.push(event);
+                            }
+                            if ui.add(Minibrowser::toolbar_button("+")).clicked() {
+                                event_queue.borrow_mut().push(MinibrowserEvent::NewWebView);
+                            }
+                        },
+                    );
+                });
+            }
+
+            for action in tab_actions {
+                match action {
+                    TabAction::SetAutoReload(webview_id, interval) => {
+                        webviews.set_auto_reload(webview_id, interval)
+                    },
+                    TabAction::NewGroup(webview_id) => webviews.new_tab_group(webview_id),
+                    TabAction::SetGroup(webview_id, group_id) => {
+                        webviews.set_tab_group(webview_id, group_id)
+                    },
+                    TabAction::ToggleCollapsed(group_id) => {
+                        if let Some(group) = webviews.tab_group_mut(group_id) {
+                            group.collapsed = !group.collapsed;
+                        }
+                    },
+                    TabAction::Rename(group_id, name) => {
+                        if let Some(group) = webviews.tab_group_mut(group_id) {
+                            group.name = name;
+                        }
+                    },
+                    TabAction::Ungroup(group_id) => webviews.ungroup_tabs(group_id),
+                }
+            }
+
+            // Quit confirmation dialog
+            if self.show_quit_dialog.get() {
+                let num_tabs = webviews.webviews().len();
+                modal_dialog("Quit Moto", window).show(ctx, |ui| {
+                    ui.label(format!("Close {} tabs?", num_tabs));
+                    let mut dont_ask_again = self.quit_dont_ask_again.get();
+                    if ui
+                        .checkbox(&mut dont_ask_again, "Don't ask again")
+                        .changed()
+                    {
+                        self.quit_dont_ask_again.set(dont_ask_again);
+                    }
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Quit").clicked() {
+                            if dont_ask_again {
+                                let value = PrefValue::Bool(false);
+                                if let Err(e) = moto_prefs::set("warn-on-quit", value) {
+                                    warn!("Failed to set pref: {}", e);
+                                }
+                            }
+                            self.show_quit_dialog.set(false);
+                            event_queue.borrow_mut().push(MinibrowserEvent::Quit);
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.show_quit_dialog.set(false);
+                        }
+                    });
+                });
+            }
+
+            // Clear history confirmation dialog
+            if self.show_clear_history_dialog.get() {
+                modal_dialog("Clear History", window).show(ctx, |ui| {
+                    ui.label("Forget every page you have visited? This can't be undone.");
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Clear History").clicked() {
+                            if !self.config_dir.is_empty() {
+                                if let Err(e) = clear_history(&self.config_dir) {
+                                    warn!("Failed to clear history: {}", e);
                                 }
-                                ui.close_menu();
                             }
-                            ui.separator();
-                            if ui.button("Exit").clicked() {
-                                std::process::exit(0);
+                            webviews.clear_history();
+                            *self.history_entries.borrow_mut() = None;
+                            self.show_clear_history_dialog.set(false);
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.show_clear_history_dialog.set(false);
+                        }
+                    });
+                });
+            }
+
+            // Ask before leaving a page whose beforeunload handler wants to keep the user there
+            if webviews.unload_prompt_pending() {
+                modal_dialog("Leave Page", window).show(ctx, |ui| {
+                    ui.label("Leave this page? Changes you made may not be saved.");
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Leave").clicked() {
+                            webviews.answer_unload_prompt(true);
+                        }
+                        if ui.button("Stay").clicked() {
+                            webviews.answer_unload_prompt(false);
+                        }
+                    });
+                });
+            }
+
+            // Offer to fix or remove a bookmark that couldn't be opened
+            let mut invalid_bookmark = self.invalid_bookmark.borrow_mut();
+            if let Some((id, fixed_url)) = invalid_bookmark.as_mut() {
+                let id = *id;
+                let mut close = false;
+                modal_dialog("Invalid Bookmark", window).show(ctx, |ui| {
+                    ui.label(
+                        "This bookmark's URL can't be opened. Fix it, or remove the bookmark.",
+                    );
+                    ui.text_edit_singleline(fixed_url);
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        let valid = location_bar_input_to_url(fixed_url).is_some();
+                        if ui.add_enabled(valid, egui::Button::new("Save")).clicked() {
+                            match set_bookmark_url(&self.config_dir, id, fixed_url) {
+                                Ok(()) => {
+                                    let mut bookmarks = self.bookmarks.borrow_mut();
+                                    for bookmark in bookmarks.iter_mut().filter(|b| b.id == id) {
+                                        bookmark.url = fixed_url.clone();
+                                    }
+                                },
+                                Err(e) => warn!("Failed to update bookmark: {}", e),
                             }
-                        });
-                        ui.menu_button("Bookmarks", |ui| {
-                            let bookmarks = self.bookmarks.borrow();
-                            for bookmark in bookmarks.as_slice() {
-                                let button = egui::Button::new(bookmark.title.as_str())
-                                    .min_size((256.0, 20.0).into());
-                                if ui.add(button).clicked() {
-                                    *location.borrow_mut() = bookmark.url.clone();
-                                    event_queue.borrow_mut().push(MinibrowserEvent::Go);
-                                    ui.close_menu();
-                                }
+                            close = true;
+                        }
+                        if ui.button("Remove Bookmark").clicked() {
+                            match remove_bookmark(&self.config_dir, id) {
+                                Ok(()) => self.bookmarks.borrow_mut().retain(|b| b.id != id),
+                                Err(e) => warn!("Failed to remove bookmark: {}", e),
                             }
+                            close = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            close = true;
+                        }
+                    });
+                });
+                if close {
+                    *invalid_bookmark = None;
+                }
+            }
+            drop(invalid_bookmark);
+
+            // Let the user change a bookmark's title and URL
+            let mut editing_bookmark = self.editing_bookmark.borrow_mut();
+            if let Some((id, title, url)) = editing_bookmark.as_mut() {
+                let mut close = false;
+                modal_dialog("Edit Bookmark", window).show(ctx, |ui| {
+                    egui::Grid::new("edit_bookmark")
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            ui.label("Title");
+                            ui.text_edit_singleline(title);
+                            ui.end_row();
+                            ui.label("URL");
+                            ui.text_edit_singleline(url);
+                            ui.end_row();
                         });
-                        ui.menu_button("History", |ui| {
-                            let history = webviews.history();
-                            for url in history {
-                                // TODO: Prevent Servo from receiving cursor events while hovering these
-                                let button =
-                                    egui::Button::new(url.as_str()).min_size((256.0, 20.0).into());
-                                if ui.add(button).clicked() {
-                                    *location.borrow_mut() = url.to_string();
-                                    event_queue.borrow_mut().push(MinibrowserEvent::Go);
-                                    ui.close_menu();
+                    // Bookmarks are told apart by URL, so two can't share one.
+                    let duplicate = self
+                        .bookmarks
+                        .borrow()
+                        .iter()
+                        .any(|bookmark| bookmark.id != *id && bookmark.url == *url);
+                    if duplicate {
+                        ui.label("There is already a bookmark with this URL.");
+                    }
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        let valid = !duplicate && location_bar_input_to_url(url).is_some();
+                        if ui.add_enabled(valid, egui::Button::new("Save")).clicked() {
+                            let mut bookmarks = self.bookmarks.borrow_mut();
+                            if let Some(bookmark) = bookmarks.iter_mut().find(|b| b.id == *id) {
+                                match update_bookmark(
+                                    &self.config_dir,
+                                    *id,
+                                    url,
+                                    title,
+                                    &bookmark.notes,
+                                ) {
+                                    Ok(()) => {
+                                        bookmark.url = url.clone();
+                                        bookmark.title = title.clone();
+                                    },
+                                    Err(e) => warn!("Failed to update bookmark: {}", e),
                                 }
                             }
-                        });
-                        ui.menu_button("Help", |ui| {
-                            if ui.button("About Moto").clicked() {
-                                self.show_about_window.set(true);
-                                ui.close_menu();
+                            close = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            close = true;
+                        }
+                    });
+                });
+                if close {
+                    *editing_bookmark = None;
+                }
+            }
+            drop(editing_bookmark);
+
+            // Ask for the name, icon and color of a new bookmark folder, or changes to them
+            let mut editing_folder = self.editing_folder.borrow_mut();
+            if let Some(draft) = editing_folder.as_mut() {
+                let mut close = false;
+                let title = if draft.id.is_some() {
+                    "Edit Folder"
+                } else {
+                    "New Folder"
+                };
+                modal_dialog(title, window).show(ctx, |ui| {
+                    let name_id = egui::Id::new("bookmark_folder_name");
+                    let submitted = consume_enter(ui, name_id);
+                    let name_field =
+                        ui.add(egui::TextEdit::singleline(&mut draft.name).id(name_id));
+                    if name_field.ctx.memory(|memory| memory.focused()).is_none() {
+                        name_field.request_focus();
+                    }
+                    ui.add_space(8.0);
+                    ui.label("Icon");
+                    ui.horizontal_wrapped(|ui| {
+                        let icons = [None].into_iter().chain(FOLDER_ICONS.map(Some));
+                        for icon in icons {
+                            let selected = draft.icon.as_deref() == icon;
+                            let glyph = icon.unwrap_or(FOLDER_GLYPH);
+                            if ui.selectable_label(selected, glyph).clicked() {
+                                draft.icon = icon.map(str::to_owned);
                             }
-                        });
+                        }
                     });
-                    ui.allocate_ui_with_layout(
-                        ui.available_size(),
-                        egui::Layout::left_to_right(egui::Align::Center),
-                        |ui| {
-                            if ui.add(Minibrowser::toolbar_button("⏴")).clicked() {
-                                event_queue.borrow_mut().push(MinibrowserEvent::Back);
+                    ui.label("Color");
+                    ui.horizontal(|ui| {
+                        let colors = [None].into_iter().chain(TAB_GROUP_COLORS.map(Some));
+                        for color in colors {
+                            let hex = color.map(hex_color);
+                            let selected = draft.color == hex;
+                            let mut swatch = match color {
+                                Some(color) => egui::Button::new("").fill(color),
+                                None => egui::Button::new("∅"),
+                            };
+                            swatch = swatch.min_size(Vec2::splat(20.0));
+                            if selected {
+                                swatch = swatch.stroke(ui.visuals().selection.stroke);
                             }
-                            if ui.add(Minibrowser::toolbar_button("⏵")).clicked() {
-                                event_queue.borrow_mut().push(MinibrowserEvent::Forward);
+                            let mut swatch = ui.add(swatch);
+                            if color.is_none() {
+                                swatch = swatch.on_hover_text("No Color");
                             }
-
-                            match self.load_status {
-                                LoadStatus::LoadStart | LoadStatus::HeadParsed => {
-                                    if ui.add(Minibrowser::toolbar_button("X")).clicked() {
-                                        warn!("Do not support stop yet.");
-                                    }
+                            if swatch.clicked() {
+                                draft.color = hex;
+                            }
+                        }
+                    });
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        let valid = !draft.name.trim().is_empty();
+                        let label = if draft.id.is_some() { "Save" } else { "Create" };
+                        let save = ui.add_enabled(valid, egui::Button::new(label));
+                        if valid && (save.clicked() || submitted) {
+                            let name = draft.name.trim();
+                            let (icon, color) = (draft.icon.as_deref(), draft.color.as_deref());
+                            let result = match draft.id {
+                                Some(id) => update_folder(&self.config_dir, id, name, icon, color)
+                                    .map(|()| id),
+                                None => {
+                                    add_folder(&self.config_dir, name, draft.parent, icon, color)
                                 },
-                                LoadStatus::LoadComplete => {
-                                    if ui.add(Minibrowser::toolbar_button("↻")).clicked() {
-                                        event_queue.borrow_mut().push(MinibrowserEvent::Reload);
-                                    }
+                            };
+                            match result {
+                                Ok(id) => {
+                                    let mut folders = self.bookmark_folders.borrow_mut();
+                                    folders.retain(|folder| folder.id != id);
+                                    folders.push(BookmarkFolder {
+                                        id,
+                                        name: name.to_owned(),
+                                        parent: draft.parent,
+                                        icon: draft.icon.clone(),
+                                        color: draft.color.clone(),
+                                    });
+                                    folders.sort_by_key(|folder| folder.name.to_lowercase());
                                 },
+                                Err(e) => warn!("Failed to save bookmark folder: {}", e),
                             }
-                            ui.add_space(2.0);
+                            close = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            close = true;
+                        }
+                    });
+                });
+                if close {
+                    *editing_folder = None;
+                }
+            }
+            drop(editing_folder);
 
-                            ui.allocate_ui_with_layout(
-                                ui.available_size(),
-                                egui::Layout::right_to_left(egui::Align::Center),
-                                |ui| {
-                                    let num_jobs = self
-                                        .download_jobs
-                                        .borrow()
-                                        .iter()
-                                        .filter(|job| {
-                                            let handle = job.handle.borrow();
-                                            handle.as_ref().is_some_and(|job| job.is_finished())
-                                        })
-                                        .count();
-                                    let download_text = if num_jobs > 0 {
-                                        format!("Downloads ({})", num_jobs)
-                                    } else {
-                                        "Downloads".to_string()
-                                    };
-                                    ui.menu_button(download_text, |ui| {
-                                        for mut job in self.download_jobs.borrow_mut().iter().rev()
-                                        {
-                                            // TODO: Prevent Servo from receiving cursor events while hovering these
-                                            let has_job = job.handle.borrow().is_some();
-                                            let has_finished_job = job
-                                                .handle
-                                                .borrow()
-                                                .as_ref()
-                                                .is_some_and(|job| job.is_finished());
-                                            let text = if has_finished_job {
-                                                let handle =
-                                                    job.handle.borrow_mut().take().unwrap();
-                                                handle.join().unwrap();
-                                                "Completed"
-                                            } else if has_job {
-                                                "Downloading..."
-                                            } else {
-                                                "Completed"
-                                            };
-                                            let button = egui::Button::new(format!(
-                                                "{}\n{}",
-                                                &job.filename, text
-                                            ))
-                                            .min_size((256.0, 20.0).into());
-                                            if ui.add(button).clicked() {
-                                                Minibrowser::open_file(&job.downloaded_file_path);
-                                                ui.close_menu();
-                                            }
-                                        }
-                                    });
-                                    let has_bookmark = self
-                                        .bookmarks
-                                        .borrow()
-                                        .iter()
-                                        .any(|b| b.url == *location.borrow());
-                                    let star = if has_bookmark { "★" } else { "☆" };
-                                    if ui.add(Minibrowser::toolbar_button(star)).clicked() {
-                                        let Some(webview) = webviews.focused_webview() else {
-                                            return;
-                                        };
-                                        let url = webview.url.clone().unwrap().to_string();
-                                        let title = webview.title.clone().unwrap_or("".into());
-                                        if !has_bookmark {
-                                            // Add bookmark to DB
-                                            if add_bookmark(&self.config_dir, &url, &title).is_ok()
-                                            {
-                                                self.bookmarks
-                                                    .borrow_mut()
-                                                    .push(Bookmark { url, title });
-                                            }
-                                        } else {
-                                            // Remove bookmark from DB
-                                            if remove_bookmark(&self.config_dir, &url).is_ok() {
-                                                self.bookmarks
-                                                    .borrow_mut()
-                                                    .retain(|b| b.url != url);
-                                            }
-                                        }
-                                    }
-                                    let location_id = egui::Id::new("location_input");
-                                    let location_field = ui.add_sized(
-                                        ui.available_size(),
-                                        egui::TextEdit::singleline(&mut *location.borrow_mut())
-                                            .id(location_id),
-                                    );
+            // Offer to restore the tabs from a session that crashed
+            let crashed_session_len = self
+                .crashed_session
+                .borrow()
+                .as_ref()
+                .map(|session| session.urls.len());
+            if let Some(num_tabs) = crashed_session_len {
+                modal_dialog("Restore Session", window).show(ctx, |ui| {
+                    ui.label("Moto didn't shut down properly.");
+                    ui.label(format!(
+                        "Restore the {} tabs from your previous session?",
+                        num_tabs
+                    ));
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Restore previous session").clicked() {
+                            if let Some(session) = self.crashed_session.borrow_mut().take() {
+                                event_queue
+                                    .borrow_mut()
+                                    .push(MinibrowserEvent::RestoreSession(session));
+                            }
+                        }
+                        if ui.button("Don't restore").clicked() {
+                            *self.crashed_session.borrow_mut() = None;
+                        }
+                    });
+                });
+            }
 
-                                    if location_field.changed() {
-                                        location_dirty.set(true);
+            // Page context menu
+            if let Some(position) = self.context_menu_position.get() {
+                let area = egui::Area::new("page_context_menu".into())
+                    .fixed_pos(position)
+                    .order(egui::Order::Foreground)
+                    .show(ctx, |ui| {
+                        Frame::menu(ui.style())
+                            .show(ui, |ui| {
+                                let mut chosen = None;
+                                if ui.button("Copy Page Text").clicked() {
+                                    chosen = Some(MinibrowserEvent::PageText(PageTextAction::Copy));
+                                }
+                                if ui.button("Save Page Text…").clicked() {
+                                    chosen = Some(MinibrowserEvent::PageText(PageTextAction::Save));
+                                }
+                                ui.menu_button("Developer", |ui| {
+                                    if ui.button("Copy as cURL").clicked() {
+                                        chosen = Some(MinibrowserEvent::CopyAsCurl);
+                                        ui.close_menu();
                                     }
-                                    if ui.input(|i| {
-                                        i.clone().consume_key(Modifiers::COMMAND, Key::L)
-                                    }) {
-                                        location_field.request_focus();
-                                        if let Some(mut state) =
-                                            TextEditState::load(ui.ctx(), location_id)
+                                    let flipped_url = webviews
+                                        .focused_webview()
+                                        .and_then(|webview| webview.url.as_ref())
+                                        .and_then(flip_http_scheme);
+                                    if let Some(url) = flipped_url {
+                                        let downgrade = url.scheme() == "http";
+                                        let label = if downgrade {
+                                            "Reload over HTTP"
+                                        } else {
+                                            "Reload over HTTPS"
+                                        };
+                                        if ui.button(label).clicked()
+                                            && (!downgrade || Self::confirm_http_downgrade())
                                         {
-                                            // Select the whole input.
-                                            state.cursor.set_char_range(Some(CCursorRange::two(
-                                                CCursor::new(0),
-                                                CCursor::new(location.borrow().len()),
-                                            )));
-                                            state.store(ui.ctx(), location_id);
+                                            location.borrow_mut().set_text(url.into_string());
+                                            chosen = Some(MinibrowserEvent::Go);
+                                            ui.close_menu();
                                         }
                                     }
-                                    if location_field.lost_focus()
-                                        && ui.input(|i| i.clone().key_pressed(Key::Enter))
-                                    {
-                                        event_queue.borrow_mut().push(MinibrowserEvent::Go);
-                                        location_dirty.set(false);
-                                    }
-                                },
-                            );
-                        },
-                    );
+                                });
+                                chosen
+                            })
+                            .inner
+                    });
+                // The menu opens as the right button is released, so only a new click closes it.
+                // Submenus are foreground areas too, so pressing in one doesn't count.
+                let pressed_at =
+                    ctx.input(|i| i.pointer.interact_pos().filter(|_| i.pointer.any_pressed()));
+                let clicked_outside = pressed_at.is_some_and(|p| {
+                    !area.response.rect.contains(p)
+                        && ctx
+                            .layer_id_at(p)
+                            .map_or(true, |layer| layer.order != egui::Order::Foreground)
                 });
+                let chosen = area.inner;
+                if chosen.is_some() || clicked_outside || ctx.input(|i| i.key_pressed(Key::Escape))
+                {
+                    event_queue.borrow_mut().extend(chosen);
+                    webviews.close_context_menu();
+                    self.context_menu_position.set(None);
+                }
+            }
+
+            if shortcut_enabled("console")
+                && ctx.input_mut(|i| i.consume_key(Modifiers::COMMAND | Modifiers::SHIFT, Key::J))
+            {
+                self.show_console.set(!self.show_console.get());
+            }
+
+            let image_search_finished = self
+                .image_search
+                .borrow()
+                .as_ref()
+                .map(|handle| handle.is_finished());
+            match image_search_finished {
+                Some(true) => {
+                    let handle = self.image_search.borrow_mut().take().unwrap();
+                    match handle.join() {
+                        Ok(Ok(url)) => event_queue
+                            .borrow_mut()
+                            .push(MinibrowserEvent::OpenInNewWebViews(vec![url])),
+                        Ok(Err(e)) => {
+                            warn!("Image search failed: {}", e);
+                            *self.toast.borrow_mut() = Some((
+                                "Image search failed".to_owned(),
+                                Instant::now() + Duration::from_secs(3),
+                            ));
+                        },
+                        Err(_) => warn!("Image search thread panicked"),
+                    }
+                },
+                Some(false) => ctx.request_repaint_after(Duration::from_millis(100)),
+                None => {},
+            }
+
+            if let Some(notice) = webviews.take_notice() {
+                *self.toast.borrow_mut() = Some((notice, Instant::now() + Duration::from_secs(3)));
+            }
 
-                // About Window
-                if self.show_about_window.get() {
-                    let size = window.inner_size();
-                    egui::Window::new("About")
-                        .min_size((256.0, 256.0))
-                        .collapsible(false)
-                        .pivot(egui::Align2::CENTER_CENTER)
-                        .default_pos((size.width as f32 / 2.0, size.height as f32 / 2.0))
-                        .title_bar(true)
+            let toast = self.toast.borrow().clone();
+            if let Some((text, until)) = toast {
+                let now = Instant::now();
+                if now < until {
+                    egui::Area::new("toast".into())
+                        .anchor(egui::Align2::CENTER_BOTTOM, (0.0, -16.0))
                         .show(ctx, |ui| {
-                            ui.vertical_centered(|ui| {
-                                ui.add(egui::Image::new(egui::include_image!(
-                                    "../resources/moto_1024.png"
-                                )));
-                                let text = egui::RichText::new(crate::moto_version()).size(16.0);
-                                ui.label(text);
-                                ui.add_space(8.0);
-                                if ui.button("Close").clicked() {
-                                    self.show_about_window.set(false);
-                                }
-                            });
+                            Frame::popup(ui.style()).show(ui, |ui| ui.label(text));
                         });
+                    ctx.request_repaint_after(until - now);
+                } else {
+                    *self.toast.borrow_mut() = None;
                 }
-            };
+            }
 
-            let mut embedder_events = vec![];
+            // JavaScript Console
+            if self.show_console.get() {
+                let mut console = self.console.borrow_mut();
+                let Console { input, last_read } = &mut *console;
+                if last_read.map_or(true, |time| time.elapsed() >= CONSOLE_READ_INTERVAL) {
+                    event_queue
+                        .borrow_mut()
+                        .push(MinibrowserEvent::ReadConsoleOutput);
+                    *last_read = Some(Instant::now());
+                }
+                ctx.request_repaint_after(CONSOLE_READ_INTERVAL);
 
-            // A simple Tab header strip
-            TopBottomPanel::top("tabs").show(ctx, |ui| {
-                ui.allocate_ui_with_layout(
-                    ui.available_size(),
-                    egui::Layout::left_to_right(egui::Align::Center),
-                    |ui| {
-                        for (webview_id, webview) in webviews.webviews().into_iter() {
-                            let label = match (&webview.title, &webview.url) {
-                                (Some(title), _) if !title.is_empty() => title,
-                                (_, Some(url)) => &url.to_string(),
-                                _ => "New Tab",
-                            };
-                            if let Some(event) =
-                                Self::browser_tab(ui, label, webview.focused, webview_id)
-                            {
-                                location_dirty.set(false);
-                                embedder_events.push(event);
+                let lines = webviews
+                    .focused_webview_id()
+                    .and_then(|webview_id| webviews.get_mut(webview_id))
+                    .map(|webview| &mut webview.console);
+                SidePanel::right("console")
+                    .default_width(320.0)
+                    .show(ctx, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.heading("Console");
+                            if ui.button("Clear").clicked() {
+                                if let Some(lines) = lines.as_deref_mut() {
+                                    lines.clear();
+                                }
                             }
+                        });
+                        let input_id = egui::Id::new("console_input");
+                        let submitted = consume_enter(ui, input_id);
+                        ui.add(
+                            egui::TextEdit::singleline(input)
+                                .id(input_id)
+                                .hint_text("Evaluate JavaScript")
+                                .code_editor()
+                                .desired_width(f32::INFINITY),
+                        );
+                        if submitted && !input.is_empty() {
+                            event_queue
+                                .borrow_mut()
+                                .push(MinibrowserEvent::EvaluateInConsole(std::mem::take(input)));
+                        }
+                        ui.separator();
+                        egui::containers::ScrollArea::vertical()
+                            .stick_to_bottom(true)
+                            .show(ui, |ui| {
+                                for line in lines.iter().flat_map(|lines| lines.iter()) {
+                                    ui.monospace(line);
+                                }
+                            });
+                    });
+            }
+
+            // Find bar
+            let find_bar = webviews
+                .focused_webview_id()
+                .and_then(|webview_id| webviews.get_mut(webview_id))
+                .and_then(|webview| webview.find_bar.as_mut());
+            if let Some(find_bar) = find_bar {
+                TopBottomPanel::bottom("find_bar").show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        // Enter goes to the next match and Shift+Enter to the previous one. These
+                        // are taken before the field sees them, as is Escape, which closes the bar.
+                        let field_id = egui::Id::new("find_bar_field");
+                        let field_focused = ui.memory(|memory| memory.has_focus(field_id));
+                        let previous = field_focused
+                            && ui.input_mut(|i| i.consume_key(Modifiers::SHIFT, Key::Enter));
+                        let next = consume_enter(ui, field_id);
+                        let escape = field_focused
+                            && ui.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Escape));
+                        let field = ui.add(
+                            egui::TextEdit::singleline(&mut find_bar.query)
+                                .id(field_id)
+                                .hint_text("Find in page")
+                                .desired_width(240.0),
+                        );
+                        if self.focus_find_bar.take() {
+                            field.request_focus();
                         }
-                        if ui.add(Minibrowser::toolbar_button("+")).clicked() {
-                            event_queue.borrow_mut().push(MinibrowserEvent::NewWebView);
+                        let has_query = !find_bar.query.is_empty();
+                        let previous = ui
+                            .add_enabled(has_query, egui::Button::new("▲"))
+                            .on_hover_text("Previous Match")
+                            .clicked()
+                            || previous;
+                        let next = ui
+                            .add_enabled(has_query, egui::Button::new("▼"))
+                            .on_hover_text("Next Match")
+                            .clicked()
+                            || next;
+                        // Typing searches once it pauses, but clearing the field clears the
+                        // highlights straight away.
+                        if field.changed() {
+                            find_bar.edited = Some(Instant::now());
                         }
-                    },
-                );
-            });
+                        let typed = match find_bar.edited {
+                            Some(_) if !has_query => true,
+                            Some(edited) => {
+                                let waited = edited.elapsed();
+                                if waited < FIND_DELAY {
+                                    ui.ctx().request_repaint_after(FIND_DELAY - waited);
+                                }
+                                waited >= FIND_DELAY
+                            },
+                            None => false,
+                        };
+                        if typed || previous || next {
+                            find_bar.edited = None;
+                            event_queue.borrow_mut().push(MinibrowserEvent::Find {
+                                query: find_bar.query.clone(),
+                                forward: !previous,
+                            });
+                        }
+                        match find_bar.matches.filter(|_| has_query) {
+                            Some((_, 0)) => ui.label("No matches"),
+                            Some((current, count)) => ui.label(format!("{} of {}", current, count)),
+                            None => ui.label(""),
+                        };
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.button("✕").on_hover_text("Close").clicked() || escape {
+                                event_queue.borrow_mut().push(MinibrowserEvent::CloseFind);
+                            }
+                        });
+                    });
+                });
+            }
 
             // The toolbar height is where the Context’s available rect starts.
             // For reasons that are unclear, the TopBottomPanel’s ui cursor exceeds this by one egui
             // point, but the Context is correct and the TopBottomPanel is wrong.
             *toolbar_height = Length::new(ctx.available_rect().min.y);
+            let available_rect = ctx.available_rect();
+            *webview_area = Box2D::new(
+                Point2D::new(available_rect.min.x, available_rect.min.y),
+                Point2D::new(available_rect.max.x, available_rect.max.y),
+            );
 
             let scale =
                 Scale::<_, DeviceIndependentPixel, DevicePixel>::new(ctx.pixels_per_point());
@@ -626,8 +3574,15 @@ impl Minibrowser {
                     .show(ctx, |ui| {
                         let prefs = servo::config::prefs::pref_map();
                         let mut sorted_prefs = prefs.iter().collect::<Vec<_>>();
+                        sorted_prefs.extend(moto_prefs::pref_map());
                         sorted_prefs.sort_by_key(|k| k.0.clone());
                         let mut prefs_to_set = HashMap::new();
+                        let mut edits = self.config_edits.borrow_mut();
+                        let saved = self
+                            .config_saved
+                            .borrow()
+                            .clone()
+                            .filter(|(_, until)| Instant::now() < *until);
                         egui::containers::ScrollArea::vertical().show(ui, |ui| {
                             for (k, v) in sorted_prefs {
                                 ui.columns(2, |cols| {
@@ -639,59 +3594,99 @@ impl Minibrowser {
                                         );
                                     });
                                     cols[1].vertical(|ui| {
-                                        match v {
+                                        let field = match v {
                                             PrefValue::Float(f) => {
-                                                let mut num_text = f.to_string();
-                                                if ui.text_edit_singleline(&mut num_text).changed()
-                                                {
-                                                    let value = num_text.parse();
-                                                    if let Ok(v) = value {
-                                                        prefs_to_set.insert(
-                                                            k.to_owned(),
-                                                            PrefValue::Float(v),
-                                                        );
-                                                    }
+                                                let (field, value) = Self::config_number_field(
+                                                    ui,
+                                                    &k,
+                                                    f,
+                                                    "Not a number",
+                                                    &mut edits,
+                                                );
+                                                if let Some(v) = value {
+                                                    prefs_to_set
+                                                        .insert(k.to_owned(), PrefValue::Float(v));
                                                 }
+                                                Some(field)
                                             },
                                             PrefValue::Int(i) => {
-                                                let mut num_text = i.to_string();
-                                                if ui.text_edit_singleline(&mut num_text).changed()
-                                                {
-                                                    let value = num_text.parse();
-                                                    if let Ok(v) = value {
-                                                        prefs_to_set.insert(
-                                                            k.to_owned(),
-                                                            PrefValue::Int(v),
-                                                        );
-                                                    }
+                                                let (field, value) = Self::config_number_field(
+                                                    ui,
+                                                    &k,
+                                                    i,
+                                                    "Not a whole number",
+                                                    &mut edits,
+                                                );
+                                                if let Some(v) = value {
+                                                    prefs_to_set
+                                                        .insert(k.to_owned(), PrefValue::Int(v));
                                                 }
+                                                Some(field)
                                             },
                                             PrefValue::Str(mut s) => {
-                                                if ui.text_edit_singleline(&mut s).changed() {
+                                                let field = ui.text_edit_singleline(&mut s);
+                                                if field.changed() {
                                                     prefs_to_set
                                                         .insert(k.to_owned(), PrefValue::Str(s));
                                                 }
+                                                Some(field)
                                             },
                                             PrefValue::Bool(mut b) => {
-                                                if ui.checkbox(&mut b, "").clicked() {
+                                                let field = ui.checkbox(&mut b, "");
+                                                if field.clicked() {
                                                     prefs_to_set
                                                         .insert(k.to_owned(), PrefValue::Bool(b));
                                                 }
+                                                Some(field)
                                             },
-                                            PrefValue::Array(_) => {
-                                                // TODO: Support this
-                                                // There's only a single pref that takes this right now,
-                                                // shell.background-color.rgba
+                                            PrefValue::Array(values) => {
+                                                let (field, values) = Self::config_array_field(
+                                                    ui, &k, values, &mut edits,
+                                                );
+                                                if let Some(values) = values {
+                                                    prefs_to_set.insert(
+                                                        k.to_owned(),
+                                                        PrefValue::Array(values),
+                                                    );
+                                                }
+                                                Some(field)
                                             },
-                                            PrefValue::Missing => {},
+                                            PrefValue::Missing => None,
+                                        };
+                                        // Briefly outline the field whose change was just saved.
+                                        if let (Some(field), Some((saved_key, until))) =
+                                            (field, &saved)
+                                        {
+                                            if *saved_key == k {
+                                                ui.painter().rect_stroke(
+                                                    field.rect.expand(1.0),
+                                                    2.0,
+                                                    egui::Stroke::new(
+                                                        1.5,
+                                                        Color32::from_rgb(30, 142, 62),
+                                                    ),
+                                                );
+                                                ui.ctx().request_repaint_after(
+                                                    until.saturating_duration_since(Instant::now()),
+                                                );
+                                            }
                                         }
                                     });
                                 });
                             }
                         });
                         prefs_to_set.iter().for_each(|(k, v)| {
-                            if let Err(e) = prefs.set(k, v.clone()) {
-                                warn!("Failed to set pref: {}", e);
+                            let result = if moto_prefs::is_moto_pref(k) {
+                                moto_prefs::set(k, v.clone())
+                            } else {
+                                prefs.set(k, v.clone()).map_err(|e| e.to_string())
+                            };
+                            match result {
+                                Ok(()) => {
+                                    *self.config_saved.borrow_mut() =
+                                        Some((k.clone(), Instant::now() + Duration::from_secs(1)));
+                                },
+                                Err(e) => warn!("Failed to set pref: {}", e),
                             }
                         });
                     });
@@ -723,13 +3718,31 @@ impl Minibrowser {
                             return;
                         };
 
-                        if let Some(status_text) = &self.status_text {
+                        // Say that the page is loading when there's nothing else to say, rather
+                        // than relying on the spinner alone.
+                        let status_text = self.status_text.clone().or_else(|| {
+                            (self.load_status != LoadStatus::LoadComplete)
+                                .then(|| format!("{}…", self.load_status.description()))
+                        });
+                        if let Some(status_text) = &status_text {
                             egui::containers::popup::show_tooltip_at(
                                 ctx,
                                 ui.layer_id(),
                                 "tooltip layer".into(),
                                 pos2(0.0, ctx.available_rect().max.y),
-                                |ui| ui.add(Label::new(status_text.clone()).extend()),
+                                |ui| {
+                                    let max_width =
+                                        ctx.available_rect().width() * STATUS_TEXT_MAX_WIDTH;
+                                    let font_id = egui::TextStyle::Body.resolve(ui.style());
+                                    let char_width =
+                                        ui.fonts(|fonts| fonts.glyph_width(&font_id, 'n'));
+                                    let max_chars = (max_width / char_width) as usize;
+                                    ui.set_max_width(max_width);
+                                    ui.add(
+                                        Label::new(elide_middle(status_text, max_chars)).extend(),
+                                    )
+                                    .on_hover_text(status_text);
+                                },
                             );
                         }
 
@@ -743,7 +3756,8 @@ impl Minibrowser {
                                 let width = clip.width_px as gl::GLsizei;
                                 let height = clip.height_px as gl::GLsizei;
                                 unsafe {
-                                    painter.gl().clear_color(0.0, 0.0, 0.0, 0.0);
+                                    let [r, g, b, a] = default_background.to_normalized_gamma_f32();
+                                    painter.gl().clear_color(r, g, b, a);
                                     painter.gl().scissor(x, y, width, height);
                                     painter.gl().enable(gl::SCISSOR_TEST);
                                     painter.gl().clear(gl::COLOR_BUFFER_BIT);
@@ -782,6 +3796,21 @@ impl Minibrowser {
 
             *last_update = now;
         });
+
+        // These ask for confirmation or a path in a dialog, so they wait until the frame is drawn.
+        if let Some(backup) = backup_to_restore {
+            self.restore_bookmarks(&backup);
+        }
+        if let Some((url, filename)) = download {
+            if let Some(path) = Self::download_path(&self.download_dir, &filename) {
+                let job = Minibrowser::download_file(url, path);
+                self.download_jobs.borrow_mut().push(job);
+            }
+        }
+        // moto:bookmarks changes the DB without telling the minibrowser otherwise.
+        if take_bookmarks_changed() {
+            self.reload_bookmarks();
+        }
     }
 
     /// Paint the minibrowser, as of the last update.
@@ -800,42 +3829,122 @@ impl Minibrowser {
     /// routing those to the App event queue.
     pub fn queue_embedder_events_for_minibrowser_events(
         &self,
-        browser: &WebViewManager<dyn WindowPortsMethods>,
+        browser: &mut WebViewManager<dyn WindowPortsMethods>,
         app_event_queue: &mut Vec<EmbedderEvent>,
     ) {
+        let focused_webview_id = browser.focused_webview_id();
         for event in self.event_queue.borrow_mut().drain(..) {
-            let browser_id = browser.focused_webview_id().unwrap();
-            match event {
-                MinibrowserEvent::Go => {
-                    let location = self.location.borrow();
-                    if let Some(url) = location_bar_input_to_url(&location.clone()) {
+            match (event, focused_webview_id) {
+                (MinibrowserEvent::Go, Some(browser_id)) => {
+                    let mut location = self.location.borrow_mut();
+                    location.commit();
+                    // A picked engine is only for this search.
+                    let search_page = match self.search_engine.borrow_mut().take() {
+                        Some(engine) => engine.template,
+                        None => default_search_page(),
+                    };
+                    if let Some(url) =
+                        location_bar_input_to_url_with_search(location.text(), &search_page)
+                    {
+                        let url = checked_navigation_url(url);
                         app_event_queue.push(EmbedderEvent::LoadUrl(browser_id, url));
                     } else {
                         warn!("failed to parse location");
-                        break;
                     }
                 },
-                MinibrowserEvent::Back => {
-                    app_event_queue.push(EmbedderEvent::Navigation(
-                        browser_id,
-                        TraversalDirection::Back(1),
-                    ));
+                (MinibrowserEvent::Back, Some(browser_id)) => {
+                    // Going back several times in a row is a single traversal.
+                    match app_event_queue.last_mut() {
+                        Some(EmbedderEvent::Navigation(id, TraversalDirection::Back(steps)))
+                            if *id == browser_id =>
+                        {
+                            *steps += 1;
+                        },
+                        _ => app_event_queue.push(EmbedderEvent::Navigation(
+                            browser_id,
+                            TraversalDirection::Back(1),
+                        )),
+                    }
                 },
-                MinibrowserEvent::Forward => {
-                    app_event_queue.push(EmbedderEvent::Navigation(
+                (MinibrowserEvent::Forward, Some(browser_id)) => match app_event_queue.last_mut() {
+                    Some(EmbedderEvent::Navigation(id, TraversalDirection::Forward(steps)))
+                        if *id == browser_id =>
+                    {
+                        *steps += 1;
+                    },
+                    _ => app_event_queue.push(EmbedderEvent::Navigation(
                         browser_id,
                         TraversalDirection::Forward(1),
-                    ));
+                    )),
+                },
+                (MinibrowserEvent::Reload, Some(browser_id)) => {
+                    // Reloading again straight away would only restart the same load.
+                    if !matches!(
+                        app_event_queue.last(),
+                        Some(EmbedderEvent::Reload(id)) if *id == browser_id
+                    ) {
+                        app_event_queue.push(EmbedderEvent::Reload(browser_id));
+                    }
                 },
-                MinibrowserEvent::Reload => {
-                    let browser_id = browser.focused_webview_id().unwrap();
-                    app_event_queue.push(EmbedderEvent::Reload(browser_id));
+                (
+                    MinibrowserEvent::Go
+                    | MinibrowserEvent::Back
+                    | MinibrowserEvent::Forward
+                    | MinibrowserEvent::Reload,
+                    None,
+                ) => {
+                    warn!("No focused webview to navigate");
                 },
-                MinibrowserEvent::NewWebView => {
-                    self.location_dirty.set(false);
+                (MinibrowserEvent::NewWebView, _) => {
+                    self.location.borrow_mut().discard_edit();
                     let url = ServoUrl::parse("moto:newtab").unwrap();
                     app_event_queue.push(EmbedderEvent::NewWebView(url, WebViewId::new()));
                 },
+                (MinibrowserEvent::OpenInBackground(url), _) => {
+                    browser.open_in_background(url);
+                },
+                (MinibrowserEvent::OpenInNewWebViews(urls), _) => {
+                    self.location.borrow_mut().discard_edit();
+                    for url in urls {
+                        app_event_queue.push(EmbedderEvent::NewWebView(url, WebViewId::new()));
+                    }
+                },
+                (MinibrowserEvent::RestoreSession(session), _) => {
+                    browser.restore_session(session);
+                },
+                (MinibrowserEvent::PageText(action), _) => {
+                    browser.request_page_text(action);
+                },
+                (MinibrowserEvent::InstallWebApp, _) => {
+                    browser.install_web_app();
+                },
+                (MinibrowserEvent::CopyAsCurl, _) => {
+                    browser.copy_as_curl();
+                },
+                (MinibrowserEvent::Find { query, forward }, _) => {
+                    browser.find_in_page(query, forward);
+                },
+                (MinibrowserEvent::CloseFind, _) => {
+                    browser.close_find_bar();
+                },
+                (MinibrowserEvent::EvaluateInConsole(script), _) => {
+                    browser.evaluate_in_console(script);
+                },
+                (MinibrowserEvent::ReadConsoleOutput, _) => {
+                    browser.read_console_output();
+                },
+                (MinibrowserEvent::ZoomIn, _) => {
+                    browser.zoom_in();
+                },
+                (MinibrowserEvent::ZoomOut, _) => {
+                    browser.zoom_out();
+                },
+                (MinibrowserEvent::ZoomReset, _) => {
+                    browser.reset_zoom();
+                },
+                (MinibrowserEvent::Quit, _) => {
+                    app_event_queue.push(EmbedderEvent::Quit);
+                },
             }
         }
     }
@@ -846,17 +3955,9 @@ impl Minibrowser {
         &mut self,
         browser: &mut WebViewManager<dyn WindowPortsMethods>,
     ) -> bool {
-        // User edited without clicking Go?
-        if self.location_dirty.get() {
-            return false;
-        }
-
         match browser.current_url_string() {
-            Some(location) if location != *self.location.get_mut() => {
-                self.location = RefCell::new(location.to_owned());
-                true
-            },
-            _ => false,
+            Some(url) => self.location.get_mut().update(&url),
+            None => false,
         }
     }
 
@@ -866,8 +3967,16 @@ impl Minibrowser {
         &mut self,
         browser: &mut WebViewManager<dyn WindowPortsMethods>,
     ) -> bool {
-        let need_update = browser.load_status() != self.load_status;
+        let loading_webviews = browser
+            .webviews()
+            .into_iter()
+            .filter(|(_, webview)| webview.load_status != LoadStatus::LoadComplete)
+            .map(|(webview_id, _)| webview_id)
+            .collect();
+        let need_update =
+            browser.load_status() != self.load_status || loading_webviews != self.loading_webviews;
         self.load_status = browser.load_status();
+        self.loading_webviews = loading_webviews;
         need_update
     }
 
@@ -880,6 +3989,24 @@ impl Minibrowser {
         need_update
     }
 
+    /// Opens the page context menu at the pointer when the page asks for one, returning true iff
+    /// it has opened or closed (needing an egui update).
+    pub fn update_context_menu(
+        &mut self,
+        browser: &mut WebViewManager<dyn WindowPortsMethods>,
+    ) -> bool {
+        let open = browser.has_context_menu();
+        if open == self.context_menu_position.get().is_some() {
+            return false;
+        }
+        let position = self
+            .last_mouse_position
+            .map(|p| Pos2::new(p.x, p.y))
+            .unwrap_or_default();
+        self.context_menu_position.set(open.then_some(position));
+        true
+    }
+
     /// Updates all fields taken from the given [WebViewManager], such as the location field.
     /// Returns true iff the egui needs an update.
     pub fn update_webview_data(
@@ -893,39 +4020,146 @@ impl Minibrowser {
         self.update_location_in_toolbar(browser)
             | self.update_spinner_in_toolbar(browser)
             | self.update_status_text(browser)
+            | self.update_context_menu(browser)
+    }
+
+    /// Returns where to save a download with the given file name, which is in the directory from
+    /// the `downloads.directory` pref (or the given default), or wherever the user chooses if the
+    /// `downloads.always-ask` pref is set. Returns None if the user cancels.
+    fn download_path(default_dir: &str, filename: &str) -> Option<String> {
+        let mut dir = moto_prefs::get_str("downloads.directory");
+        if dir.is_empty() {
+            dir = default_dir.to_owned();
+        }
+        if moto_prefs::get_bool("downloads.always-ask") {
+            let default_path = Path::new(&dir).join(filename);
+            return save_file_dialog("Save File", &default_path.to_string_lossy());
+        }
+
+        if let Err(e) = fs::create_dir_all(&dir) {
+            warn!("Failed to create download directory {} ({})", dir, e);
+        }
+        // Don't overwrite earlier downloads, instead name this one "file (1).ext" and so on.
+        let path = Path::new(&dir).join(filename);
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let extension = path
+            .extension()
+            .map(|extension| format!(".{}", extension.to_string_lossy()))
+            .unwrap_or_default();
+        let mut unique_path = path.clone();
+        let mut copy = 1;
+        while unique_path.exists() {
+            unique_path = path.with_file_name(format!("{} ({}){}", stem, copy, extension));
+            copy += 1;
+        }
+        Some(unique_path.to_string_lossy().into_owned())
+    }
+
+    /// Upload the image on the clipboard to the given reverse image search endpoint, returning
+    /// the thread doing so. The image is posted as a PNG in the `image` field of a multipart
+    /// form, and the endpoint is expected to redirect to the results.
+    fn search_clipboard_image(endpoint: String) -> JoinHandle<Result<ServoUrl, String>> {
+        // Read the clipboard now rather than on the thread, in case it changes in the meantime.
+        let clipboard_image = Clipboard::new().and_then(|mut clipboard| clipboard.get_image());
+        thread::spawn(move || {
+            let clipboard_image = clipboard_image.map_err(|e| e.to_string())?;
+            let png = Self::encode_png(clipboard_image)?;
+
+            let mut form = curl::easy::Form::new();
+            form.part("image")
+                .buffer("image.png", png)
+                .content_type("image/png")
+                .add()
+                .map_err(|e| e.to_string())?;
+            let mut easy = Easy::new();
+            easy.fail_on_error(true).map_err(|e| e.to_string())?;
+            easy.url(&endpoint).map_err(|e| e.to_string())?;
+            easy.httppost(form).map_err(|e| e.to_string())?;
+            // Only the redirect matters, so drop the body rather than letting curl print it.
+            easy.write_function(|data| Ok(data.len()))
+                .map_err(|e| e.to_string())?;
+            easy.perform().map_err(|e| e.to_string())?;
+
+            let results = easy
+                .redirect_url()
+                .map_err(|e| e.to_string())?
+                .ok_or("The image search endpoint didn't redirect to any results")?;
+            ServoUrl::parse(results).map_err(|e| e.to_string())
+        })
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    fn encode_png(clipboard_image: ImageData) -> Result<Vec<u8>, String> {
+        let image = image::RgbaImage::from_raw(
+            clipboard_image.width as u32,
+            clipboard_image.height as u32,
+            clipboard_image.bytes.into_owned(),
+        )
+        .ok_or("Clipboard image has the wrong size")?;
+        let mut png = vec![];
+        image
+            .write_to(
+                &mut std::io::Cursor::new(&mut png),
+                image::ImageOutputFormat::Png,
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(png)
     }
 
-    pub fn download_file(source_url: String, download_dir: String) -> DownloadJob {
-        let filename = source_url.split('/').last().unwrap().to_owned();
-        let downloaded_file_path = format!("{}/{}", &download_dir, &filename);
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    fn encode_png(_clipboard_image: ImageData) -> Result<Vec<u8>, String> {
+        Err("Image search is not supported on this platform".to_owned())
+    }
+
+    pub fn download_file(source_url: String, downloaded_file_path: String) -> DownloadJob {
+        let filename = Path::new(&downloaded_file_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
 
         let url = source_url.clone();
         let path = downloaded_file_path.clone();
 
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_transfer = cancel.clone();
+
         let handle = thread::spawn(move || {
             let mut easy = Easy::new();
-            easy.follow_location(true).unwrap();
-            easy.url(&url).unwrap();
+            easy.follow_location(true).map_err(|e| e.to_string())?;
+            easy.fail_on_error(true).map_err(|e| e.to_string())?;
+            easy.url(&url).map_err(|e| e.to_string())?;
 
+            // Start from scratch, in case this is a retry of a download that didn't finish.
             let mut file = OpenOptions::new()
-                .append(true)
+                .write(true)
                 .create(true)
-                .open(path)
-                .unwrap();
+                .truncate(true)
+                .open(&path)
+                .map_err(|e| e.to_string())?;
 
-            easy.write_function(move |data| {
-                file.write_all(data).unwrap();
-                Ok(data.len())
+            easy.write_function(move |data| match file.write_all(data) {
+                Ok(()) => Ok(data.len()),
+                // Writing less than we were given makes curl abort the transfer.
+                Err(_) => Ok(0),
             })
-            .unwrap();
+            .map_err(|e| e.to_string())?;
+            easy.progress(true).map_err(|e| e.to_string())?;
+            easy.progress_function(move |_, _, _, _| !cancel_transfer.load(Ordering::Relaxed))
+                .map_err(|e| e.to_string())?;
 
-            easy.perform().unwrap();
+            let result = easy.perform().map_err(|e| e.to_string());
+            if result.is_err() {
+                let _ = fs::remove_file(&path);
+            }
+            result
         });
         DownloadJob {
             source_url: source_url.clone(),
             downloaded_file_path: downloaded_file_path.clone(),
             filename,
             handle: RefCell::new(Some(handle)),
+            state: Cell::new(DownloadState::InProgress),
+            cancel,
         }
     }
 
@@ -4,7 +4,10 @@
 
 //! Contains files specific to the Moto app for Desktop systems.
 
+mod accent_color;
 pub(crate) mod app;
+mod automation;
+mod backups;
 pub(crate) mod cli;
 mod db;
 mod egui_glue;
@@ -14,8 +17,16 @@ pub mod geometry;
 mod headed_window;
 mod headless_window;
 mod keyutils;
+mod location;
 mod minibrowser;
+mod motion;
+mod moto_prefs;
+mod navigation_log;
 mod protocols;
+mod search_engines;
+mod session;
+mod shortcuts;
 mod tracing;
+mod web_apps;
 mod webview;
 mod window_trait;
@@ -0,0 +1,74 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Whether the browser chrome may animate, for users who want less motion on screen.
+
+use std::sync::LazyLock;
+
+use super::moto_prefs;
+
+/// Whether the operating system's accessibility settings ask apps to avoid animations, which is
+/// only read once.
+static PREFERS_REDUCED_MOTION: LazyLock<bool> = LazyLock::new(os_prefers_reduced_motion);
+
+/// Returns true iff animations are allowed. Anything that animates should check this first, and
+/// change instantly instead if it returns false.
+///
+/// The `ui.animations` pref can be "on" or "off" to override the operating system's setting,
+/// which is followed otherwise.
+pub fn animations_allowed() -> bool {
+    match moto_prefs::get_str("ui.animations").as_str() {
+        "on" => true,
+        "off" => false,
+        _ => !*PREFERS_REDUCED_MOTION,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn os_prefers_reduced_motion() -> bool {
+    use std::process::Command;
+
+    // GNOME, and desktops that follow its settings, only have a switch for all animations.
+    Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.interface", "enable-animations"])
+        .output()
+        .is_ok_and(|output| {
+            output.status.success() && String::from_utf8_lossy(&output.stdout).trim() == "false"
+        })
+}
+
+#[cfg(target_os = "macos")]
+fn os_prefers_reduced_motion() -> bool {
+    use std::process::Command;
+
+    Command::new("defaults")
+        .args(["read", "com.apple.universalaccess", "reduceMotion"])
+        .output()
+        .is_ok_and(|output| {
+            output.status.success() && String::from_utf8_lossy(&output.stdout).trim() == "1"
+        })
+}
+
+#[cfg(target_os = "windows")]
+fn os_prefers_reduced_motion() -> bool {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        SystemParametersInfoW, SPI_GETCLIENTAREAANIMATION,
+    };
+
+    let mut animations_enabled = 1i32;
+    let ok = unsafe {
+        SystemParametersInfoW(
+            SPI_GETCLIENTAREAANIMATION,
+            0,
+            &mut animations_enabled as *mut i32 as *mut _,
+            0,
+        )
+    };
+    ok != 0 && animations_enabled == 0
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn os_prefers_reduced_motion() -> bool {
+    false
+}
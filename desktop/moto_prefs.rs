@@ -0,0 +1,289 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Preferences owned by Moto rather than Servo.
+//!
+//! Servo's preference map rejects keys it doesn't know about, so settings for the browser
+//! chrome live here instead. Every pref has a default which also fixes its type, and values
+//! changed by the user are persisted to `prefs.sqlite` in the config directory.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{LazyLock, Mutex};
+
+use log::warn;
+use servo::config::prefs::PrefValue;
+
+use super::db::{create_prefs_db, get_prefs, set_pref};
+
+struct MotoPrefs {
+    values: HashMap<String, PrefValue>,
+    /// Where changed values are persisted, or None if persistence is unavailable.
+    config_dir: Option<String>,
+}
+
+static PREFS: LazyLock<Mutex<MotoPrefs>> = LazyLock::new(|| {
+    Mutex::new(MotoPrefs {
+        values: default_prefs(),
+        config_dir: None,
+    })
+});
+
+fn default_prefs() -> HashMap<String, PrefValue> {
+    HashMap::from([
+        (
+            "accessibility.typeaheadfind".to_owned(),
+            PrefValue::Bool(false),
+        ),
+        (
+            "bookmarks.expanded-folders".to_owned(),
+            PrefValue::Array(vec![]),
+        ),
+        (
+            "browser.default-background".to_owned(),
+            PrefValue::Str("auto".to_owned()),
+        ),
+        (
+            "data-url.max-length".to_owned(),
+            PrefValue::Int(2 * 1024 * 1024),
+        ),
+        ("devtools.enabled".to_owned(), PrefValue::Bool(false)),
+        ("devtools.port".to_owned(), PrefValue::Int(6000)),
+        ("downloads.always-ask".to_owned(), PrefValue::Bool(false)),
+        (
+            "downloads.directory".to_owned(),
+            PrefValue::Str(String::new()),
+        ),
+        ("general.autoscroll".to_owned(), PrefValue::Bool(true)),
+        ("general.smoothscroll".to_owned(), PrefValue::Bool(false)),
+        ("history.max-entries".to_owned(), PrefValue::Int(50_000)),
+        ("history.retention-days".to_owned(), PrefValue::Int(0)),
+        ("navigation.log".to_owned(), PrefValue::Bool(false)),
+        (
+            "newtab.custom-path".to_owned(),
+            PrefValue::Str(String::new()),
+        ),
+        ("privacy.clear-on-exit".to_owned(), PrefValue::Bool(false)),
+        (
+            "privacy.clear-on-exit.cache".to_owned(),
+            PrefValue::Bool(true),
+        ),
+        (
+            "privacy.clear-on-exit.cookies".to_owned(),
+            PrefValue::Bool(true),
+        ),
+        (
+            "privacy.clear-on-exit.history".to_owned(),
+            PrefValue::Bool(true),
+        ),
+        (
+            "search.image-endpoint".to_owned(),
+            PrefValue::Str(String::new()),
+        ),
+        (
+            "search.engines".to_owned(),
+            PrefValue::Array(
+                [
+                    "DuckDuckGo|https://duckduckgo.com/html/?q=%s",
+                    "Google|https://www.google.com/search?q=%s",
+                    "Wikipedia|https://en.wikipedia.org/w/index.php?search=%s",
+                ]
+                .into_iter()
+                .map(|engine| PrefValue::Str(engine.to_owned()))
+                .collect(),
+            ),
+        ),
+        ("shortcuts.disabled".to_owned(), PrefValue::Array(vec![])),
+        (
+            "spatial-navigation.enabled".to_owned(),
+            PrefValue::Bool(false),
+        ),
+        ("tabs.background-throttle".to_owned(), PrefValue::Bool(true)),
+        (
+            "tabs.open-position".to_owned(),
+            PrefValue::Str("end".to_owned()),
+        ),
+        ("tabs.vertical".to_owned(), PrefValue::Bool(false)),
+        (
+            "toolbar.buttons".to_owned(),
+            PrefValue::Array(
+                [
+                    "back",
+                    "forward",
+                    "reload",
+                    "bookmark",
+                    "translate",
+                    "install",
+                ]
+                .into_iter()
+                .map(|name| PrefValue::Str(name.to_owned()))
+                .collect(),
+            ),
+        ),
+        (
+            "translate.endpoint".to_owned(),
+            PrefValue::Str("https://translate.google.com/translate?sl=auto&tl=%t&u=%s".to_owned()),
+        ),
+        (
+            "translate.target-lang".to_owned(),
+            PrefValue::Str("en".to_owned()),
+        ),
+        (
+            "ui.animations".to_owned(),
+            PrefValue::Str("auto".to_owned()),
+        ),
+        ("ui.menu-max-entries".to_owned(), PrefValue::Int(20)),
+        ("ui.use-system-accent".to_owned(), PrefValue::Bool(false)),
+        ("warn-on-quit".to_owned(), PrefValue::Bool(true)),
+    ])
+}
+
+/// Load any persisted values from the given config directory, and persist future changes there.
+pub fn init(config_dir: &str) {
+    let path = Path::new(&config_dir).join("prefs.sqlite");
+    if !path.exists() {
+        if let Err(e) = create_prefs_db(config_dir) {
+            warn!("Failed to create prefs DB: {}", e);
+            return;
+        }
+    }
+
+    let mut prefs = PREFS.lock().unwrap();
+    for (key, text) in get_prefs(config_dir) {
+        let Some(default) = prefs.values.get(&key) else {
+            warn!("Ignoring unknown pref {}", key);
+            continue;
+        };
+        match parse_pref_value(default, &text) {
+            Some(value) => {
+                prefs.values.insert(key, value);
+            },
+            None => warn!("Ignoring invalid value {:?} for pref {}", text, key),
+        }
+    }
+    prefs.config_dir = Some(config_dir.to_owned());
+}
+
+/// Returns the value of the given pref, or [PrefValue::Missing] if there is no such pref.
+pub fn get(key: &str) -> PrefValue {
+    PREFS
+        .lock()
+        .unwrap()
+        .values
+        .get(key)
+        .cloned()
+        .unwrap_or(PrefValue::Missing)
+}
+
+pub fn get_bool(key: &str) -> bool {
+    matches!(get(key), PrefValue::Bool(true))
+}
+
+pub fn get_int(key: &str) -> i64 {
+    match get(key) {
+        PrefValue::Int(i) => i,
+        _ => 0,
+    }
+}
+
+pub fn get_str(key: &str) -> String {
+    match get(key) {
+        PrefValue::Str(s) => s,
+        _ => String::new(),
+    }
+}
+
+/// Returns the string elements of the given array pref, skipping any of other types.
+pub fn get_str_array(key: &str) -> Vec<String> {
+    match get(key) {
+        PrefValue::Array(values) => values
+            .into_iter()
+            .filter_map(|value| match value {
+                PrefValue::Str(s) => Some(s),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Returns true iff the given key is a Moto pref, as opposed to a Servo one.
+pub fn is_moto_pref(key: &str) -> bool {
+    PREFS.lock().unwrap().values.contains_key(key)
+}
+
+/// Returns all prefs and their current values.
+pub fn pref_map() -> Vec<(String, PrefValue)> {
+    PREFS
+        .lock()
+        .unwrap()
+        .values
+        .iter()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect()
+}
+
+/// Set the given pref, persisting it if possible. The value must have the same type as the
+/// pref's default.
+pub fn set(key: &str, value: PrefValue) -> Result<(), String> {
+    let mut prefs = PREFS.lock().unwrap();
+    let Some(current) = prefs.values.get(key) else {
+        return Err(format!("Unknown pref {}", key));
+    };
+    if std::mem::discriminant(current) != std::mem::discriminant(&value) {
+        return Err(format!("Wrong type for pref {}: {:?}", key, value));
+    }
+    let Some(text) = pref_value_to_string(&value) else {
+        return Err(format!("Unsupported value for pref {}: {:?}", key, value));
+    };
+
+    if let Some(config_dir) = &prefs.config_dir {
+        set_pref(config_dir, key, &text).map_err(|e| e.to_string())?;
+    }
+    prefs.values.insert(key.to_owned(), value);
+    Ok(())
+}
+
+/// Arrays are persisted one element per line, and can't be nested.
+fn pref_value_to_string(value: &PrefValue) -> Option<String> {
+    match value {
+        PrefValue::Float(f) => Some(f.to_string()),
+        PrefValue::Int(i) => Some(i.to_string()),
+        PrefValue::Str(s) if !s.contains('\n') => Some(s.clone()),
+        PrefValue::Str(_) => None,
+        PrefValue::Bool(b) => Some(b.to_string()),
+        PrefValue::Array(values) => values
+            .iter()
+            .map(|value| match value {
+                PrefValue::Array(_) => None,
+                value => pref_value_to_string(value),
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(|lines| lines.join("\n")),
+        PrefValue::Missing => None,
+    }
+}
+
+/// Parse a persisted value, using the pref's default to determine the expected type. The elements
+/// of an array have the type of the default's first element, or are strings if it is empty.
+fn parse_pref_value(default: &PrefValue, text: &str) -> Option<PrefValue> {
+    match default {
+        PrefValue::Float(_) => text.parse().ok().map(PrefValue::Float),
+        PrefValue::Int(_) => text.parse().ok().map(PrefValue::Int),
+        PrefValue::Str(_) => Some(PrefValue::Str(text.to_owned())),
+        PrefValue::Bool(_) => text.parse().ok().map(PrefValue::Bool),
+        PrefValue::Array(values) => {
+            let element = match values.first() {
+                Some(PrefValue::Array(_)) => return None,
+                Some(element) => element.clone(),
+                None => PrefValue::Str(String::new()),
+            };
+            text.lines()
+                .map(|line| parse_pref_value(&element, line))
+                .collect::<Option<Vec<_>>>()
+                .map(PrefValue::Array)
+        },
+        PrefValue::Missing => None,
+    }
+}
@@ -0,0 +1,140 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! An opt-in log of navigations, load times and errors, for diagnosing slow or failing sites.
+//!
+//! Entries are only written while the `navigation.log` pref is set, and only ever to
+//! `navigation.log` in the config directory. Each line has a UTC timestamp, the kind of entry, the
+//! URL and any details, separated by tabs. The log can be read and cleared on `moto:log`.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::warn;
+use servo::servo_url::ServoUrl;
+
+use super::db::config_dir;
+use super::moto_prefs;
+
+const LOG_FILE: &str = "navigation.log";
+
+/// How big the log can grow before its older half is dropped. That still leaves far more entries
+/// than moto:log shows.
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+
+/// Append an entry to the log, if logging is enabled.
+pub fn record(kind: &str, url: &ServoUrl, details: &str) {
+    if !moto_prefs::get_bool("navigation.log") {
+        return;
+    }
+    let config_dir = config_dir();
+    if config_dir.is_empty() {
+        return;
+    }
+    let line = format!(
+        "{}\t{}\t{}\t{}\n",
+        format_timestamp(SystemTime::now()),
+        kind,
+        url,
+        details.replace(['\t', '\n'], " ")
+    );
+    let path = Path::new(&config_dir).join(LOG_FILE);
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| {
+            file.write_all(line.as_bytes())?;
+            file.metadata()
+        });
+    match result {
+        Ok(metadata) if metadata.len() > MAX_LOG_SIZE => {
+            let result = fs::read(&path).and_then(|log| fs::write(&path, newest_half(&log)));
+            if let Err(e) = result {
+                warn!("Failed to trim navigation log ({})", e);
+            }
+        },
+        Ok(_) => {},
+        Err(e) => warn!("Failed to write navigation log ({})", e),
+    }
+}
+
+/// Returns the newer half of the log's lines, roughly, by size.
+fn newest_half(log: &[u8]) -> &[u8] {
+    let middle = log.len() / 2;
+    match log[middle..].iter().position(|&byte| byte == b'\n') {
+        Some(end_of_line) => &log[middle + end_of_line + 1..],
+        None => &[],
+    }
+}
+
+/// Returns the contents of the log, which is empty if nothing was logged yet.
+pub fn read_log(config_dir: &str) -> io::Result<String> {
+    match fs::read_to_string(Path::new(config_dir).join(LOG_FILE)) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        result => result,
+    }
+}
+
+pub fn clear_log(config_dir: &str) -> io::Result<()> {
+    match fs::remove_file(Path::new(config_dir).join(LOG_FILE)) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// Formats the time as a UTC date and time, like `2024-09-30 17:05:09`.
+fn format_timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let (days, seconds) = (seconds / 86400, seconds % 86400);
+
+    // Convert days since the epoch to a civil date, after Howard Hinnant's `civil_from_days`.
+    let days = days as i64 + 719468;
+    let era = days / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[test]
+fn test_newest_half() {
+    assert_eq!(newest_half(b"one\ntwo\nthree\nfour\n"), b"four\n");
+    assert_eq!(newest_half(b"a single line\n"), b"");
+    assert_eq!(newest_half(b""), b"");
+}
+
+#[test]
+fn test_format_timestamp() {
+    use std::time::Duration;
+
+    assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01 00:00:00");
+    let time = UNIX_EPOCH + Duration::from_secs(1727715909);
+    assert_eq!(format_timestamp(time), "2024-09-30 17:05:09");
+    // A leap day.
+    let time = UNIX_EPOCH + Duration::from_secs(951782400);
+    assert_eq!(format_timestamp(time), "2000-02-29 00:00:00");
+}
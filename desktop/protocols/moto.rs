@@ -2,19 +2,133 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-//! Loads resources using a mapping from well-known shortcuts to resource: urls.
-//! Recognized shorcuts:
+//! Loads moto: pages, which are looked up by name in a registry.
+//! Built-in pages:
+//! - moto:about
+//! - moto:bookmarks
+//! - moto:config
+//! - moto:error?message=<message>
+//! - moto:history
+//! - moto:log
 //! - moto:newtab
+//! - moto:readinglist
+//! - moto:sitesettings?origin=<origin>
+//!
+//! Forks can add pages of their own with [register_page], before Servo starts.
 
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
 use std::future::Future;
+use std::hash::BuildHasher;
+use std::path::Path;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, RwLock};
 
+use headers::{ContentType, HeaderMapExt};
+use http::StatusCode;
+use log::warn;
 use servo::net::fetch::methods::{DoneChannel, FetchContext};
 use servo::net::protocols::ProtocolHandler;
 use servo::net_traits::request::Request;
-use servo::net_traits::response::Response;
+use servo::net_traits::response::{Response, ResponseBody};
+use servo::net_traits::ResourceFetchTiming;
+use servo::servo_url::ServoUrl;
 
+use crate::desktop::backups::{list_backups, restore_backup, Backup};
+use crate::desktop::db::{
+    config_dir, get_bookmark_folders, get_bookmarks, get_history, get_reading_list,
+    get_site_settings, migrate_bookmarks_db, move_bookmark, remove_bookmark,
+    remove_from_reading_list, remove_site_setting, set_bookmark_order, set_reading_list_item_read,
+    update_bookmark,
+};
+use crate::desktop::minibrowser::{hex_color, Bookmark, BookmarkFolder};
+use crate::desktop::moto_prefs;
+use crate::desktop::navigation_log::{clear_log, read_log};
 use crate::desktop::protocols::resource::ResourceProtocolHandler;
+use crate::parser::location_bar_input_to_url;
+
+type ResponseFuture = Pin<Box<dyn Future<Output = Response> + Send>>;
+
+/// How a moto: page is loaded.
+#[derive(Clone, Copy)]
+pub enum MotoPage {
+    /// A file served by the resource: protocol, such as `/newtab.html`.
+    Resource(&'static str),
+    /// An HTML page generated from the page's URL whenever it is loaded.
+    Html(fn(&ServoUrl) -> String),
+    /// An HTML page like [MotoPage::Html] whose query can ask it to change something, such as
+    /// removing a bookmark. It is passed whether the request is allowed to, which only requests
+    /// from the page's own links are: see [action_query].
+    Actions(fn(&ServoUrl, bool) -> String),
+    /// A page that needs more control over its response than [MotoPage::Html] gives.
+    Loader(fn(&mut Request, &mut DoneChannel, &FetchContext) -> ResponseFuture),
+    /// A page drawn by the minibrowser in place of the webview, like moto:config, with the given
+    /// resource loaded underneath as a placeholder. The minibrowser has to know how to draw it.
+    Drawn(&'static str),
+}
+
+struct Registration {
+    page: MotoPage,
+    /// Whether moto:about links to the page.
+    listed: bool,
+}
+
+static PAGES: LazyLock<RwLock<BTreeMap<String, Registration>>> = LazyLock::new(|| {
+    let pages = [
+        ("about", MotoPage::Html(|_| about_page()), false),
+        ("bookmarks", MotoPage::Actions(bookmarks_page), true),
+        ("config", MotoPage::Drawn("/config.html"), true),
+        ("error", MotoPage::Html(error_page), false),
+        ("history", MotoPage::Html(|_| history_page()), true),
+        ("log", MotoPage::Actions(log_page), true),
+        ("newtab", MotoPage::Loader(load_newtab), true),
+        ("readinglist", MotoPage::Actions(reading_list_page), true),
+        ("sitesettings", MotoPage::Actions(site_settings_page), false),
+    ];
+    let pages = pages
+        .into_iter()
+        .map(|(name, page, listed)| (name.to_owned(), Registration { page, listed }));
+    RwLock::new(pages.collect())
+});
+
+/// Make `moto:<name>` load the given page, replacing any page already registered with that name,
+/// including the built-in ones. Pass `listed` to link to the page from moto:about, which only makes
+/// sense for pages that don't need a query.
+///
+/// Call this before Servo starts, at the top of `cli::main`, so the page is there for the first
+/// load. For example:
+///
+/// ```ignore
+/// register_page("hello", MotoPage::Html(|_| "<h1>Hello</h1>".to_owned()), true);
+/// ```
+pub fn register_page(name: &str, page: MotoPage, listed: bool) {
+    PAGES
+        .write()
+        .unwrap()
+        .insert(name.to_owned(), Registration { page, listed });
+}
+
+fn registered_page(name: &str) -> Option<MotoPage> {
+    PAGES
+        .read()
+        .unwrap()
+        .get(name)
+        .map(|registration| registration.page)
+}
+
+/// Returns the name of the page if the given URL is a moto: page that the minibrowser draws
+/// itself, rather than letting Servo render it.
+pub fn drawn_page_name(url: &ServoUrl) -> Option<String> {
+    if url.scheme() != "moto" {
+        return None;
+    }
+    match registered_page(url.path())? {
+        MotoPage::Drawn(_) => Some(url.path().to_owned()),
+        _ => None,
+    }
+}
 
 #[derive(Default)]
 pub struct MotoProtocolHandler {}
@@ -25,25 +139,711 @@ impl ProtocolHandler for MotoProtocolHandler {
         request: &mut Request,
         done_chan: &mut DoneChannel,
         context: &FetchContext,
-    ) -> Pin<Box<dyn Future<Output = Response> + Send>> {
+    ) -> ResponseFuture {
         let url = request.current_url();
 
-        match url.path() {
-            "newtab" => ResourceProtocolHandler::response_for_path(
-                request,
-                done_chan,
-                context,
-                "/newtab.html",
-            ),
-            "config" => ResourceProtocolHandler::response_for_path(
-                request,
-                done_chan,
-                context,
-                "/newtab.html",
-            ),
-            _ => Box::pin(std::future::ready(Response::network_internal_error(
+        match registered_page(url.path()) {
+            // The minibrowser draws over the placeholder of a drawn page.
+            Some(MotoPage::Resource(path) | MotoPage::Drawn(path)) => {
+                ResourceProtocolHandler::response_for_path(request, done_chan, context, path)
+            },
+            Some(MotoPage::Html(build)) => html_response(request, build(&url)),
+            Some(MotoPage::Actions(build)) => {
+                html_response(request, build(&url, is_action(request)))
+            },
+            Some(MotoPage::Loader(load)) => load(request, done_chan, context),
+            None => Box::pin(std::future::ready(Response::network_internal_error(
                 "Invalid shortcut",
             ))),
         }
     }
 }
+
+fn load_newtab(
+    request: &mut Request,
+    done_chan: &mut DoneChannel,
+    context: &FetchContext,
+) -> ResponseFuture {
+    // Users can replace the new tab page with a file of their own, but fall back to ours if it
+    // can't be read.
+    let custom_path = moto_prefs::get_str("newtab.custom-path");
+    if !custom_path.is_empty() {
+        let file_path = Path::new(&custom_path);
+        match File::open(file_path) {
+            Ok(file) if file_path.is_file() => {
+                return ResourceProtocolHandler::response_for_file(
+                    request, done_chan, context, file, file_path,
+                );
+            },
+            _ => warn!("Failed to open new tab page {}", custom_path),
+        }
+    }
+    ResourceProtocolHandler::response_for_path(request, done_chan, context, "/newtab.html")
+}
+
+fn error_page(url: &ServoUrl) -> String {
+    let message = url
+        .as_url()
+        .query_pairs()
+        .find(|(key, _)| key == "message")
+        .map(|(_, value)| value.into_owned())
+        .unwrap_or_default();
+    let body = format!(
+        "<h1>Can't show this page</h1><p>{}</p>",
+        escape_html(&message)
+    );
+    page("Error", &body)
+}
+
+/// Returns the URL of an error page showing the given message.
+pub fn error_url(message: &str) -> ServoUrl {
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .append_pair("message", message)
+        .finish();
+    ServoUrl::parse(&format!("moto:error?{}", query)).expect("Error page URL should be valid")
+}
+
+/// A secret that moto: pages put in the links that change something. Other pages can link to
+/// moto: pages, but they can't read them, so they can't make those changes. It is new each session.
+static ACTION_TOKEN: LazyLock<String> = LazyLock::new(|| {
+    let state = RandomState::new();
+    format!("{:016x}{:016x}", state.hash_one(1), state.hash_one(2))
+});
+
+/// Returns the query for a link that changes something: the given pairs and [ACTION_TOKEN].
+fn action_query(pairs: &[(&str, &str)]) -> String {
+    url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(pairs)
+        .append_pair("token", &ACTION_TOKEN)
+        .finish()
+}
+
+/// Returns true iff the request has the [ACTION_TOKEN] and doesn't come from a page other than a
+/// moto: page, so it may change something.
+fn is_action(request: &Request) -> bool {
+    let url = request.current_url();
+    let has_token = url
+        .as_url()
+        .query_pairs()
+        .any(|(key, value)| key == "token" && value == ACTION_TOKEN.as_str());
+    let referrer = request.referrer.to_url();
+    has_token && referrer.map_or(true, |referrer| referrer.scheme() == "moto")
+}
+
+/// Build a response for a page generated on the fly, rather than loaded from a resource.
+fn html_response(request: &Request, content: String) -> ResponseFuture {
+    let url = request.current_url();
+    let mut response = Response::new(url, ResourceFetchTiming::new(request.timing_type()));
+    *response.body.lock().unwrap() = ResponseBody::Done(content.into_bytes());
+    response.headers.typed_insert(ContentType::html());
+    response.status = Some((StatusCode::OK, "OK".to_string()));
+    response.raw_status = Some((StatusCode::OK.as_u16(), b"OK".to_vec()));
+
+    Box::pin(std::future::ready(response))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn about_page() -> String {
+    let mut links = String::new();
+    for (name, registration) in PAGES.read().unwrap().iter() {
+        if registration.listed {
+            let name = escape_html(name);
+            links.push_str(&format!(
+                "  <li><a href=\"moto:{}\">moto:{}</a></li>\n",
+                name, name
+            ));
+        }
+    }
+    let body = format!(
+        r#"<div style="text-align: center">
+  <img src="resource:///moto_1024.png" alt="" width="256" height="256">
+  <p style="font-size: 1.25em">{}</p>
+</div>
+<h2>Moto pages</h2>
+<ul>
+{}</ul>"#,
+        escape_html(&crate::moto_version()),
+        links
+    );
+    page("About Moto", &body)
+}
+
+/// How many entries of the navigation log to show, newest first.
+const LOG_PAGE_ENTRIES: usize = 500;
+
+/// Builds the navigation log page, after clearing the log if asked to with `?clear`.
+fn log_page(url: &ServoUrl, allow_actions: bool) -> String {
+    let clear = allow_actions && url.as_url().query_pairs().any(|(key, _)| key == "clear");
+    let config_dir = config_dir();
+    if clear {
+        if let Err(e) = clear_log(&config_dir) {
+            warn!("Failed to clear navigation log ({})", e);
+        }
+    }
+
+    let mut body = String::from("<h1>Navigation Log</h1>");
+    if moto_prefs::get_bool("navigation.log") {
+        body.push_str(
+            "<p>Moto is logging navigations, load times and errors to navigation.log in its \
+             config directory. The log never leaves this computer. Turn it off with the \
+             navigation.log pref on <a href=\"moto:config\">moto:config</a>.</p>",
+        );
+    } else {
+        body.push_str(
+            "<p>Logging is off. Turn it on with the navigation.log pref on \
+             <a href=\"moto:config\">moto:config</a>. The log never leaves this computer.</p>",
+        );
+    }
+
+    let log = read_log(&config_dir).unwrap_or_else(|e| {
+        warn!("Failed to read navigation log ({})", e);
+        String::new()
+    });
+    if log.is_empty() {
+        body.push_str("<p>The log is empty.</p>");
+        return page("Navigation Log", &body);
+    }
+    body.push_str(&format!(
+        r#"<p><a href="moto:log?{}">Clear log</a></p><table>"#,
+        escape_html(&action_query(&[("clear", "")]))
+    ));
+    for line in log.lines().rev().take(LOG_PAGE_ENTRIES) {
+        body.push_str("<tr>");
+        for field in line.split('\t') {
+            body.push_str(&format!("<td>{}</td>", escape_html(field)));
+        }
+        body.push_str("</tr>");
+    }
+    body.push_str("</table>");
+    page("Navigation Log", &body)
+}
+
+/// Set when moto:bookmarks changes a bookmark, so the minibrowser knows to read them again.
+static BOOKMARKS_CHANGED: AtomicBool = AtomicBool::new(false);
+
+/// Returns true iff moto:bookmarks has changed the bookmarks since this was last called.
+pub fn take_bookmarks_changed() -> bool {
+    BOOKMARKS_CHANGED.swap(false, Ordering::Relaxed)
+}
+
+/// Builds the bookmarks page, after applying the action in the URL's query, if any and if
+/// `allow_actions`: `remove` or `edit` the bookmark with the given id, `save` the `title`, `url`
+/// and `notes` given for it, `move` it to just `before` another one, or `restore` the backup with
+/// the given file name. Removing and restoring ask first, unless the query says to `confirm`.
+fn bookmarks_page(url: &ServoUrl, allow_actions: bool) -> String {
+    let query: HashMap<String, String> = url.as_url().query_pairs().into_owned().collect();
+    let config_dir = config_dir();
+    if !allow_actions {
+        return bookmark_list_page(&config_dir);
+    }
+    let bookmarks = get_bookmarks(&config_dir).unwrap_or_else(|e| {
+        warn!("Failed to read bookmarks ({})", e);
+        vec![]
+    });
+    let find_bookmark = |action: &str| {
+        let id = query.get(action)?.parse::<i64>().ok()?;
+        bookmarks.iter().find(|bookmark| bookmark.id == id)
+    };
+    let missing = || page("Bookmarks", "<p>That bookmark no longer exists.</p>");
+
+    if let Some(file_name) = query.get("restore") {
+        let backup = list_backups(&config_dir, "bookmarks.sqlite")
+            .into_iter()
+            .find(|backup| backup_file_name(backup) == *file_name);
+        let Some(backup) = backup else {
+            return page("Bookmarks", "<p>That backup no longer exists.</p>");
+        };
+        if !query.contains_key("confirm") {
+            return restore_bookmarks_page(&backup);
+        }
+        // The backup may be from before the last migration.
+        let result = restore_backup(&config_dir, "bookmarks.sqlite", &backup)
+            .map_err(|e| e.to_string())
+            .and_then(|()| migrate_bookmarks_db(&config_dir).map_err(|e| e.to_string()));
+        match result {
+            Ok(()) => BOOKMARKS_CHANGED.store(true, Ordering::Relaxed),
+            Err(e) => warn!("Failed to restore bookmarks from {} ({})", file_name, e),
+        }
+        return bookmark_list_page(&config_dir);
+    }
+
+    if query.contains_key("remove") {
+        let Some(bookmark) = find_bookmark("remove") else {
+            return missing();
+        };
+        if !query.contains_key("confirm") {
+            return remove_bookmark_page(bookmark);
+        }
+        match remove_bookmark(&config_dir, bookmark.id) {
+            Ok(()) => BOOKMARKS_CHANGED.store(true, Ordering::Relaxed),
+            Err(e) => warn!("Failed to remove bookmark {} ({})", bookmark.url, e),
+        }
+        return bookmark_list_page(&config_dir);
+    }
+    if query.contains_key("move") {
+        let (Some(bookmark), Some(before)) = (find_bookmark("move"), find_bookmark("before"))
+        else {
+            return missing();
+        };
+        if bookmark.id == before.id {
+            return bookmark_list_page(&config_dir);
+        }
+        // Moving it before a bookmark in another folder moves it into that folder too.
+        let folder = before.folder;
+        let result = if bookmark.folder == folder {
+            Ok(())
+        } else {
+            move_bookmark(&config_dir, bookmark.id, folder)
+        };
+        let mut ids: Vec<i64> = bookmarks
+            .iter()
+            .filter(|other| other.folder == folder && other.id != bookmark.id)
+            .map(|other| other.id)
+            .collect();
+        let index = ids
+            .iter()
+            .position(|&id| id == before.id)
+            .unwrap_or(ids.len());
+        ids.insert(index, bookmark.id);
+        match result.and_then(|()| set_bookmark_order(&config_dir, folder, &ids)) {
+            Ok(()) => BOOKMARKS_CHANGED.store(true, Ordering::Relaxed),
+            Err(e) => warn!("Failed to move bookmark {} ({})", bookmark.url, e),
+        }
+        return bookmark_list_page(&config_dir);
+    }
+    if query.contains_key("save") {
+        let Some(bookmark) = find_bookmark("save") else {
+            return missing();
+        };
+        let field = |name: &str| query.get(name).map_or("", |value| value.trim());
+        let (title, new_url, notes) = (field("title"), field("url"), field("notes"));
+        // Bookmarks are told apart by URL, so two can't share one.
+        let error = if location_bar_input_to_url(new_url).is_none() {
+            Some("That URL isn't valid.")
+        } else if bookmarks
+            .iter()
+            .any(|other| other.id != bookmark.id && other.url == new_url)
+        {
+            Some("There is already a bookmark with this URL.")
+        } else {
+            None
+        };
+        if error.is_some() {
+            return edit_bookmark_page(bookmark.id, title, new_url, notes, error);
+        }
+        match update_bookmark(&config_dir, bookmark.id, new_url, title, notes) {
+            Ok(()) => BOOKMARKS_CHANGED.store(true, Ordering::Relaxed),
+            Err(e) => warn!("Failed to update bookmark {} ({})", bookmark.url, e),
+        }
+        return bookmark_list_page(&config_dir);
+    }
+    if query.contains_key("edit") {
+        return match find_bookmark("edit") {
+            Some(bookmark) => edit_bookmark_page(
+                bookmark.id,
+                &bookmark.title,
+                &bookmark.url,
+                &bookmark.notes,
+                None,
+            ),
+            None => missing(),
+        };
+    }
+    bookmark_list_page(&config_dir)
+}
+
+fn bookmark_list_page(config_dir: &str) -> String {
+    let bookmarks = get_bookmarks(config_dir).unwrap_or_else(|e| {
+        warn!("Failed to read bookmarks ({})", e);
+        vec![]
+    });
+    let folders = get_bookmark_folders(config_dir).unwrap_or_else(|e| {
+        warn!("Failed to read bookmark folders ({})", e);
+        vec![]
+    });
+    let mut body = String::from("<h1>Bookmarks</h1>");
+    if bookmarks.is_empty() {
+        body.push_str("<p>Pages you bookmark will show up here.</p>");
+    } else {
+        body.push_str(&bookmark_list(&folders, &bookmarks, None));
+    }
+    // Bookmarks in folders that no longer exist would otherwise be missing.
+    let orphans: Vec<&Bookmark> = bookmarks
+        .iter()
+        .filter(|bookmark| !folders.iter().any(|folder| folder.id == bookmark.folder))
+        .collect();
+    if !orphans.is_empty() {
+        body.push_str("<ul>");
+        for bookmark in orphans {
+            body.push_str(&bookmark_item(bookmark));
+        }
+        body.push_str("</ul>");
+    }
+    if !bookmarks.is_empty() {
+        body.push_str(BOOKMARK_DRAG_SCRIPT);
+    }
+
+    // Moto backs up the bookmarks each time it starts, so they can be had back after a mistake.
+    let backups = list_backups(config_dir, "bookmarks.sqlite");
+    if !backups.is_empty() {
+        body.push_str("<h2>Restore from Backup</h2><ul>");
+        for backup in backups {
+            body.push_str(&format!(
+                r#"<li>Backup from {} (<a href="moto:bookmarks?{}">Restore</a>)</li>"#,
+                escape_html(&backup.age()),
+                escape_html(&action_query(&[("restore", &backup_file_name(&backup))])),
+            ));
+        }
+        body.push_str("</ul>");
+    }
+    page("Bookmarks", &body)
+}
+
+/// Lets bookmarks be dragged onto each other to reorder them, including onto those in other
+/// folders to move them there, like in the Bookmarks menu.
+const BOOKMARK_DRAG_SCRIPT: &str = r#"<script>
+  let dragged = null;
+  for (const item of document.querySelectorAll("li[data-move]")) {
+    item.addEventListener("dragstart", (event) => {
+      dragged = item;
+      event.dataTransfer.setData("text/plain", item.dataset.id);
+    });
+    item.addEventListener("dragover", (event) => {
+      if (dragged && dragged !== item) {
+        event.preventDefault();
+      }
+    });
+    item.addEventListener("drop", (event) => {
+      event.preventDefault();
+      location.href = "moto:bookmarks?" + dragged.dataset.move + "&before=" + item.dataset.id;
+    });
+  }
+</script>"#;
+
+fn backup_file_name(backup: &Backup) -> String {
+    backup
+        .path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Asks whether to replace the bookmarks with the given backup, since that loses any changes made
+/// since it was taken.
+fn restore_bookmarks_page(backup: &Backup) -> String {
+    let body = format!(
+        r#"<h1>Restore Bookmarks</h1>
+<p>Replace your bookmarks with the backup from {}? Changes made since then will be lost.</p>
+<p><a href="moto:bookmarks?{}">Restore</a> <a href="moto:bookmarks">Cancel</a></p>"#,
+        escape_html(&backup.age()),
+        escape_html(&action_query(&[
+            ("restore", &backup_file_name(backup)),
+            ("confirm", ""),
+        ])),
+    );
+    page("Restore Bookmarks", &body)
+}
+
+/// Returns the folders in the given one, or at the top level, and the bookmarks in it, as a list
+/// with a nested list for each folder.
+fn bookmark_list(
+    folders: &[BookmarkFolder],
+    bookmarks: &[Bookmark],
+    folder: Option<i64>,
+) -> String {
+    let mut list = String::from("<ul>");
+    for child in folders.iter().filter(|child| child.parent == folder) {
+        let style = child
+            .color32()
+            .map(|color| format!(r#" style="color: {}""#, hex_color(color)))
+            .unwrap_or_default();
+        list.push_str(&format!(
+            "<li><strong{}>{} {}</strong>{}</li>",
+            style,
+            escape_html(child.glyph()),
+            escape_html(&child.name),
+            bookmark_list(folders, bookmarks, Some(child.id))
+        ));
+    }
+    for bookmark in bookmarks
+        .iter()
+        .filter(|bookmark| Some(bookmark.folder) == folder)
+    {
+        list.push_str(&bookmark_item(bookmark));
+    }
+    list.push_str("</ul>");
+    list
+}
+
+fn bookmark_item(bookmark: &Bookmark) -> String {
+    let title = if bookmark.title.is_empty() {
+        &bookmark.url
+    } else {
+        &bookmark.title
+    };
+    let notes = if bookmark.notes.is_empty() {
+        String::new()
+    } else {
+        format!("<br><small>{}</small>", escape_html(&bookmark.notes))
+    };
+    let id = bookmark.id.to_string();
+    format!(
+        r#"<li draggable="true" data-id="{}" data-move="{}"><a href="{}">{}</a> (<a href="moto:bookmarks?{}">Edit</a>, <a href="moto:bookmarks?{}">Delete</a>){}</li>"#,
+        id,
+        escape_html(&action_query(&[("move", &id)])),
+        escape_html(&bookmark.url),
+        escape_html(title),
+        escape_html(&action_query(&[("edit", &id)])),
+        escape_html(&action_query(&[("remove", &id)])),
+        notes,
+    )
+}
+
+/// Asks whether to delete the bookmark, since that can't be undone.
+fn remove_bookmark_page(bookmark: &Bookmark) -> String {
+    let title = if bookmark.title.is_empty() {
+        &bookmark.url
+    } else {
+        &bookmark.title
+    };
+    let id = bookmark.id.to_string();
+    let body = format!(
+        r#"<h1>Delete Bookmark</h1>
+<p>Delete the bookmark for <a href="{}">{}</a>? This can't be undone.</p>
+<p><a href="moto:bookmarks?{}">Delete</a> <a href="moto:bookmarks">Cancel</a></p>"#,
+        escape_html(&bookmark.url),
+        escape_html(title),
+        escape_html(&action_query(&[("remove", &id), ("confirm", "")])),
+    );
+    page("Delete Bookmark", &body)
+}
+
+/// Builds the form for changing a bookmark, with the given values and the error that stopped
+/// them from being saved, if any.
+fn edit_bookmark_page(id: i64, title: &str, url: &str, notes: &str, error: Option<&str>) -> String {
+    let error = error
+        .map(|error| format!("<p><strong>{}</strong></p>", escape_html(error)))
+        .unwrap_or_default();
+    let token = escape_html(&ACTION_TOKEN);
+    let body = format!(
+        r#"<h1>Edit Bookmark</h1>
+{error}<form>
+  <input type="hidden" name="save" value="{id}">
+  <input type="hidden" name="token" value="{token}">
+  <p><label>Title <input name="title" value="{}"></label></p>
+  <p><label>URL <input name="url" value="{}"></label></p>
+  <p><label>Notes<br><textarea name="notes" rows="4" cols="60">{}</textarea></label></p>
+  <p><button>Save</button> <a href="moto:bookmarks">Cancel</a></p>
+</form>
+<script>
+  // Servo only submits forms to a few schemes, which don't include moto:.
+  document.querySelector("form").addEventListener("submit", (event) => {{
+    event.preventDefault();
+    location.href = "moto:bookmarks?" + new URLSearchParams(new FormData(event.target));
+  }});
+</script>"#,
+        escape_html(title),
+        escape_html(url),
+        escape_html(notes),
+    );
+    page("Edit Bookmark", &body)
+}
+
+/// How many visited pages to show, most recent first.
+const HISTORY_PAGE_ENTRIES: usize = 1000;
+
+/// Builds the history page, which lists visited pages under the day they were last visited.
+fn history_page() -> String {
+    let history = get_history(&config_dir(), HISTORY_PAGE_ENTRIES).unwrap_or_else(|e| {
+        warn!("Failed to read history ({})", e);
+        vec![]
+    });
+    let mut body = String::from("<h1>History</h1>");
+    if history.is_empty() {
+        body.push_str("<p>Pages you visit will show up here.</p>");
+        return page("History", &body);
+    }
+    let mut day = None;
+    for entry in &history {
+        // Visits are SQLite datetimes, like `2024-09-30 17:05:09.123`.
+        let (date, time) = entry
+            .visited
+            .split_once(' ')
+            .unwrap_or((&entry.visited, ""));
+        if day != Some(date) {
+            if day.is_some() {
+                body.push_str("</table>");
+            }
+            body.push_str(&format!("<h2>{}</h2><table>", escape_html(date)));
+            day = Some(date);
+        }
+        let title = if entry.title.is_empty() {
+            &entry.url
+        } else {
+            &entry.title
+        };
+        body.push_str(&format!(
+            r#"<tr><td>{}</td><td><a href="{}">{}</a></td></tr>"#,
+            escape_html(time.get(..5).unwrap_or(time)),
+            escape_html(&entry.url),
+            escape_html(title),
+        ));
+    }
+    body.push_str("</table>");
+    page("History", &body)
+}
+
+/// Builds the settings page for the origin in the URL's query, after resetting the setting given
+/// as `reset`, if any and if `allow_actions`.
+fn site_settings_page(url: &ServoUrl, allow_actions: bool) -> String {
+    let mut origin = None;
+    let mut reset = None;
+    for (key, value) in url.as_url().query_pairs() {
+        match &*key {
+            "origin" => origin = Some(value.into_owned()),
+            "reset" => reset = Some(value.into_owned()),
+            _ => {},
+        }
+    }
+    let Some(origin) = origin else {
+        return page("Site Settings", "<p>No origin given.</p>");
+    };
+    let config_dir = config_dir();
+
+    if let Some(setting) = reset.filter(|_| allow_actions) {
+        if let Err(e) = remove_site_setting(&config_dir, &origin, &setting) {
+            warn!("Failed to reset {} for {} ({})", setting, origin, e);
+        }
+    }
+
+    let settings = get_site_settings(&config_dir, &origin).unwrap_or_default();
+    let mut body = format!("<h1>{}</h1>", escape_html(&origin));
+    if settings.is_empty() {
+        body.push_str("<p>Moto has not stored any settings for this site.</p>");
+    } else {
+        body.push_str("<table>");
+        for (setting, value) in settings {
+            let reset_url = action_query(&[("origin", &origin), ("reset", &setting)]);
+            // Permissions are stored as `permission.<name>`, and zoom as a factor, like `1.1`.
+            let (name, value) = match (setting.strip_prefix("permission."), &*setting) {
+                (Some(permission), _) => (format!("Permission: {}", permission), value),
+                (None, "zoom") => match value.parse::<f32>() {
+                    Ok(zoom) => ("Zoom".to_owned(), format!("{:.0}%", zoom * 100.0)),
+                    Err(_) => ("Zoom".to_owned(), value),
+                },
+                (None, _) => (setting.clone(), value),
+            };
+            body.push_str(&format!(
+                r#"<tr><td>{}</td><td>{}</td><td><a href="moto:sitesettings?{}">Reset</a></td></tr>"#,
+                escape_html(&name),
+                escape_html(&value),
+                escape_html(&reset_url),
+            ));
+        }
+        body.push_str("</table>");
+    }
+    page("Site Settings", &body)
+}
+
+/// Builds the reading list page, after applying the action in the URL's query, if any and if
+/// `allow_actions`.
+fn reading_list_page(url: &ServoUrl, allow_actions: bool) -> String {
+    let mut action = None;
+    for (key, value) in url.as_url().query_pairs() {
+        if let "open" | "remove" | "unread" = &*key {
+            action = Some((key.into_owned(), value.into_owned()));
+        }
+    }
+    reading_list_action_page(action.filter(|_| allow_actions))
+}
+
+/// Builds the reading list page, after applying the given action (`open`, `remove` or `unread`)
+/// to the item with the given URL. Opening an item marks it as read and redirects to it.
+fn reading_list_action_page(action: Option<(String, String)>) -> String {
+    let config_dir = config_dir();
+    let items = get_reading_list(&config_dir).unwrap_or_default();
+
+    if let Some((action, item_url)) = action {
+        // Only act on URLs that are actually in the list, so other pages can't use this to
+        // redirect anywhere they like.
+        if !items.iter().any(|item| item.url == item_url) {
+            return page(
+                "Reading List",
+                "<p>That page is not in your reading list.</p>",
+            );
+        }
+        let result = match &*action {
+            "open" => set_reading_list_item_read(&config_dir, &item_url, true),
+            "unread" => set_reading_list_item_read(&config_dir, &item_url, false),
+            _ => remove_from_reading_list(&config_dir, &item_url),
+        };
+        if let Err(e) = result {
+            warn!(
+                "Failed to {} reading list item {} ({})",
+                action, item_url, e
+            );
+        }
+        if action == "open" {
+            return format!(
+                r#"<!DOCTYPE html><meta http-equiv="refresh" content="0; url={}">"#,
+                escape_html(&item_url)
+            );
+        }
+        return reading_list_action_page(None);
+    }
+
+    let mut body = String::from("<h1>Reading List</h1>");
+    if items.is_empty() {
+        body.push_str("<p>Pages you save to read later will show up here.</p>");
+    } else {
+        body.push_str("<table>");
+        for item in items {
+            let query = |action| action_query(&[(action, &item.url)]);
+            let title = if item.title.is_empty() {
+                &item.url
+            } else {
+                &item.title
+            };
+            let state = if item.read {
+                format!(
+                    r#"Read (<a href="moto:readinglist?{}">mark unread</a>)"#,
+                    escape_html(&query("unread"))
+                )
+            } else {
+                "<strong>Unread</strong>".to_owned()
+            };
+            body.push_str(&format!(
+                r#"<tr><td><a href="moto:readinglist?{}">{}</a></td><td>{}</td><td>{}</td><td><a href="moto:readinglist?{}">Remove</a></td></tr>"#,
+                escape_html(&query("open")),
+                escape_html(title),
+                escape_html(&item.added),
+                state,
+                escape_html(&query("remove")),
+            ));
+        }
+        body.push_str("</table>");
+    }
+    page("Reading List", &body)
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="UTF-8">
+  <meta name="viewport" content="width=device-width, initial-scale=1.0">
+  <title>{title}</title>
+</head>
+<body>
+{body}
+</body>
+</html>"#
+    )
+}
@@ -10,6 +10,7 @@
 use std::fs::File;
 use std::future::Future;
 use std::io::BufReader;
+use std::path::Path;
 use std::pin::Pin;
 
 use headers::{ContentType, HeaderMapExt};
@@ -56,36 +57,47 @@ impl ResourceProtocolHandler {
             )));
         }
 
-        let response = if let Ok(file) = File::open(file_path.clone()) {
-            let mut response = Response::new(
-                request.current_url(),
-                ResourceFetchTiming::new(request.timing_type()),
-            );
-            let reader = BufReader::with_capacity(FILE_CHUNK_SIZE, file);
-
-            // Set Content-Type header.
-            let mime = mime_guess::from_path(file_path).first_or_octet_stream();
-            response.headers.typed_insert(ContentType::from(mime));
-
-            // Setup channel to receive cross-thread messages about the file fetch
-            // operation.
-            let (mut done_sender, done_receiver) = unbounded_channel();
-            *done_chan = Some((done_sender.clone(), done_receiver));
-
-            *response.body.lock().unwrap() = ResponseBody::Receiving(vec![]);
-
-            context.filemanager.lock().unwrap().fetch_file_in_chunks(
-                &mut done_sender,
-                reader,
-                response.body.clone(),
-                context.cancellation_listener.clone(),
-                RelativePos::full_range(),
-            );
-
-            response
-        } else {
-            Response::network_internal_error("Opening file failed")
-        };
+        match File::open(file_path.clone()) {
+            Ok(file) => Self::response_for_file(request, done_chan, context, file, &file_path),
+            Err(_) => Box::pin(std::future::ready(Response::network_internal_error(
+                "Opening file failed",
+            ))),
+        }
+    }
+
+    /// Build a response that streams the given file, which was opened from `file_path`.
+    /// Callers are responsible for checking that the file may be loaded.
+    pub fn response_for_file(
+        request: &mut Request,
+        done_chan: &mut DoneChannel,
+        context: &FetchContext,
+        file: File,
+        file_path: &Path,
+    ) -> Pin<Box<dyn Future<Output = Response> + Send>> {
+        let mut response = Response::new(
+            request.current_url(),
+            ResourceFetchTiming::new(request.timing_type()),
+        );
+        let reader = BufReader::with_capacity(FILE_CHUNK_SIZE, file);
+
+        // Set Content-Type header.
+        let mime = mime_guess::from_path(file_path).first_or_octet_stream();
+        response.headers.typed_insert(ContentType::from(mime));
+
+        // Setup channel to receive cross-thread messages about the file fetch
+        // operation.
+        let (mut done_sender, done_receiver) = unbounded_channel();
+        *done_chan = Some((done_sender.clone(), done_receiver));
+
+        *response.body.lock().unwrap() = ResponseBody::Receiving(vec![]);
+
+        context.filemanager.lock().unwrap().fetch_file_in_chunks(
+            &mut done_sender,
+            reader,
+            response.body.clone(),
+            context.cancellation_listener.clone(),
+            RelativePos::full_range(),
+        );
 
         Box::pin(std::future::ready(response))
     }
@@ -0,0 +1,80 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! The search engines offered by the location bar.
+//!
+//! Engines are listed in the `search.engines` pref as `Name|template` entries, where `%s` in the
+//! template is replaced by the search terms. The first engine is the default one.
+
+use log::warn;
+use servo::config::prefs::PrefValue;
+use servo::servo_config::pref;
+
+use super::moto_prefs;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchEngine {
+    pub name: String,
+    pub template: String,
+}
+
+impl SearchEngine {
+    /// The character shown for the engine in the location bar.
+    pub fn icon(&self) -> String {
+        self.name
+            .chars()
+            .next()
+            .map(|initial| initial.to_uppercase().to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// Returns the configured search engines, skipping entries without a template.
+pub fn search_engines() -> Vec<SearchEngine> {
+    moto_prefs::get_str_array("search.engines")
+        .into_iter()
+        .filter_map(|entry| {
+            let (name, template) = entry.split_once('|')?;
+            let (name, template) = (name.trim(), template.trim());
+            if name.is_empty() || !template.contains("%s") {
+                warn!("Ignoring invalid search engine {:?}", entry);
+                return None;
+            }
+            Some(SearchEngine {
+                name: name.to_owned(),
+                template: template.to_owned(),
+            })
+        })
+        .collect()
+}
+
+/// Returns the engine used when no engine was picked, which is the first configured one.
+pub fn default_engine() -> Option<SearchEngine> {
+    search_engines().into_iter().next()
+}
+
+/// Returns the search page used when no engine was picked, which is that of the default engine,
+/// or Servo's `shell.searchpage` if there are none.
+pub fn default_search_page() -> String {
+    default_engine()
+        .map(|engine| engine.template)
+        .unwrap_or_else(|| pref!(shell.searchpage))
+}
+
+/// Make the given engine the default, by moving it to the front of the list.
+pub fn make_default(engine: &SearchEngine) -> Result<(), String> {
+    let mut entries = moto_prefs::get_str_array("search.engines");
+    let Some(index) = entries
+        .iter()
+        .position(|entry| entry.split_once('|').map(|(name, _)| name.trim()) == Some(&engine.name))
+    else {
+        return Err(format!("Unknown search engine {}", engine.name));
+    };
+    let entry = entries.remove(index);
+    entries.insert(0, entry);
+    moto_prefs::set(
+        "search.engines",
+        PrefValue::Array(entries.into_iter().map(PrefValue::Str).collect()),
+    )
+}
@@ -0,0 +1,77 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Snapshots of the open tabs, so they can be restored if Moto doesn't shut down cleanly.
+//!
+//! The snapshot is the index of the focused tab followed by the tabs' URLs, one per line, and is
+//! written periodically while Moto runs. A marker file is touched on clean shutdown, so a snapshot
+//! newer than the marker means Moto crashed.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+use servo::servo_url::ServoUrl;
+
+const SESSION_FILE: &str = "session.txt";
+const CLEAN_SHUTDOWN_FILE: &str = "clean-shutdown";
+
+/// The open tabs, and which of them is focused.
+pub struct Session {
+    pub urls: Vec<ServoUrl>,
+    pub focused: usize,
+}
+
+/// Write a snapshot of the given session. The snapshot is written to a temporary file first and
+/// renamed into place, so a crash while saving can't leave a truncated snapshot behind.
+pub fn save_session(config_dir: &str, session: &Session) -> io::Result<()> {
+    let path = Path::new(config_dir).join(SESSION_FILE);
+    let temp_path = path.with_extension("txt.tmp");
+    let mut file = File::create(&temp_path)?;
+    writeln!(file, "{}", session.focused)?;
+    for url in &session.urls {
+        writeln!(file, "{}", url)?;
+    }
+    file.sync_all()?;
+    fs::rename(temp_path, path)
+}
+
+/// Record that Moto is shutting down cleanly, so the current snapshot isn't offered for restore.
+pub fn mark_clean_shutdown(config_dir: &str) -> io::Result<()> {
+    File::create(Path::new(config_dir).join(CLEAN_SHUTDOWN_FILE))?.sync_all()
+}
+
+/// Delete the snapshot, if any.
+pub fn clear_session(config_dir: &str) -> io::Result<()> {
+    match fs::remove_file(Path::new(config_dir).join(SESSION_FILE)) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// Returns the last snapshot if Moto didn't shut down cleanly after taking it.
+pub fn unclean_session(config_dir: &str) -> Option<Session> {
+    let path = Path::new(config_dir).join(SESSION_FILE);
+    let saved = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+    let clean_shutdown = fs::metadata(Path::new(config_dir).join(CLEAN_SHUTDOWN_FILE))
+        .and_then(|m| m.modified())
+        .ok();
+    if clean_shutdown.is_some_and(|clean_shutdown| clean_shutdown >= saved) {
+        return None;
+    }
+
+    let text = fs::read_to_string(path).ok()?;
+    let mut lines = text.lines();
+    let focused: usize = lines.next()?.parse().ok()?;
+    let urls: Vec<Option<ServoUrl>> = lines.map(|line| ServoUrl::parse(line).ok()).collect();
+
+    // If the focused tab can't be restored, focus the nearest one that can instead.
+    let restored_before_focused = urls.iter().take(focused).flatten().count();
+    let urls: Vec<ServoUrl> = urls.into_iter().flatten().collect();
+    if urls.is_empty() {
+        return None;
+    }
+    let focused = restored_before_focused.min(urls.len() - 1);
+    Some(Session { urls, focused })
+}
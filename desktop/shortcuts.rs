@@ -0,0 +1,48 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Keyboard shortcuts that users can turn off, for when they get in the way of web apps.
+//!
+//! Shortcuts are known by name rather than by their keys, since some are handled by the
+//! minibrowser, others before key events reach Servo, and a few in both places. Listing a name in
+//! the `shortcuts.disabled` pref leaves its keys to the page instead.
+
+use super::moto_prefs;
+
+/// The names of the shortcuts that can be disabled, and the keys they are bound to.
+pub const SHORTCUTS: &[(&str, &str)] = &[
+    ("back", "Cmd/Alt+Left, Cmd/Ctrl+["),
+    ("close-tab", "Cmd/Ctrl+W"),
+    ("console", "Cmd/Ctrl+Shift+J"),
+    ("devtools", "F12, Cmd/Ctrl+Shift+I"),
+    ("find", "Cmd/Ctrl+F"),
+    ("focus-location", "Cmd/Ctrl+L"),
+    ("forward", "Cmd/Alt+Right, Cmd/Ctrl+]"),
+    ("new-tab", "Cmd/Ctrl+T"),
+    ("next-tab", "Ctrl+PageDown"),
+    ("preferences", "Cmd/Ctrl+Comma"),
+    ("previous-tab", "Ctrl+PageUp"),
+    ("quit", "Cmd/Ctrl+Q"),
+    ("recent-tabs", "Ctrl+Tab, Ctrl+Shift+Tab"),
+    ("reload", "Cmd/Ctrl+R"),
+    ("sampling-profiler", "Cmd/Ctrl+P"),
+    ("select-tab", "Cmd/Ctrl+1 to Cmd/Ctrl+9"),
+    ("toolbar-focus", "F6, Cmd/Ctrl+Shift+E"),
+    ("webrender-debug", "Ctrl+F9 to Ctrl+F12"),
+    ("zoom", "Cmd/Ctrl+Plus, Cmd/Ctrl+Minus, Cmd/Ctrl+0"),
+];
+
+/// Returns a function telling whether the named shortcut is enabled. The pref is read once, so
+/// get a new function for each key event or frame rather than keeping one around.
+pub fn enabled_shortcuts() -> impl Fn(&str) -> bool {
+    let disabled = moto_prefs::get_str_array("shortcuts.disabled");
+    move |name| {
+        debug_assert!(
+            SHORTCUTS.iter().any(|(shortcut, _)| *shortcut == name),
+            "Unknown shortcut {}",
+            name
+        );
+        !disabled.iter().any(|shortcut| shortcut == name)
+    }
+}
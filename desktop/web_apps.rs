@@ -0,0 +1,162 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Sites installed as apps from their web app manifests.
+//!
+//! Installed apps are recorded in `web_apps.sqlite` in the config directory, along with a copy of
+//! their icon. On Linux they also get a desktop entry, so they can be launched like any other app.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use curl::easy::Easy;
+use log::warn;
+use servo::servo_url::ServoUrl;
+use tinyfiledialogs::MessageBoxIcon;
+
+use super::db::{add_web_app, create_web_apps_db};
+
+/// An app described by a site's web app manifest.
+pub struct WebApp {
+    pub name: String,
+    pub start_url: ServoUrl,
+    pub icon_url: Option<ServoUrl>,
+}
+
+/// Install the given app in the background, and tell the user once it's done.
+pub fn install_web_app(config_dir: String, app: WebApp) {
+    let spawned = thread::Builder::new()
+        .name("WebAppInstall".to_owned())
+        .spawn(move || {
+            // The app name comes from the site, so it isn't passed to the dialogs.
+            match install(&config_dir, &app) {
+                Ok(()) => tinyfiledialogs::message_box_ok(
+                    "Install Site",
+                    "The site has been installed as an app.",
+                    MessageBoxIcon::Info,
+                ),
+                Err(e) => {
+                    warn!("Failed to install {} ({})", app.start_url, e);
+                    tinyfiledialogs::message_box_ok(
+                        "Install Site",
+                        "The site could not be installed.",
+                        MessageBoxIcon::Error,
+                    );
+                },
+            }
+        });
+    if let Err(e) = spawned {
+        warn!("Failed to spawn web app install thread ({})", e);
+    }
+}
+
+fn install(config_dir: &str, app: &WebApp) -> Result<(), String> {
+    if !Path::new(config_dir).join("web_apps.sqlite").exists() {
+        create_web_apps_db(config_dir).map_err(|e| e.to_string())?;
+    }
+    let id = app_id(&app.name);
+
+    // An app without its icon still works, so failing to get it isn't fatal.
+    let icon_path = app.icon_url.as_ref().and_then(|icon_url| {
+        download_icon(config_dir, &id, icon_url)
+            .map_err(|e| warn!("Failed to download icon {} ({})", icon_url, e))
+            .ok()
+    });
+    let icon = icon_path.as_ref().map(|path| path.to_string_lossy());
+
+    add_web_app(
+        config_dir,
+        &app.name,
+        app.start_url.as_str(),
+        icon.as_deref().unwrap_or_default(),
+    )
+    .map_err(|e| e.to_string())?;
+    create_launcher(&id, app, icon_path.as_deref())
+}
+
+/// Returns an identifier for the app with the given name that is safe to use in file names.
+fn app_id(name: &str) -> String {
+    let mut id = String::new();
+    for c in name.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() {
+            id.push(c);
+        } else if !id.is_empty() && !id.ends_with('-') {
+            id.push('-');
+        }
+    }
+    match id.trim_end_matches('-') {
+        "" => "app".to_owned(),
+        id => id.to_owned(),
+    }
+}
+
+fn download_icon(config_dir: &str, id: &str, icon_url: &ServoUrl) -> Result<PathBuf, String> {
+    // The manifest comes from the page, so it mustn't get curl to read local files and the like.
+    if !matches!(icon_url.scheme(), "http" | "https") {
+        return Err(format!("unsupported icon URL scheme {}", icon_url.scheme()));
+    }
+    let mut data = vec![];
+    let mut easy = Easy::new();
+    easy.follow_location(true).map_err(|e| e.to_string())?;
+    easy.fail_on_error(true).map_err(|e| e.to_string())?;
+    easy.url(icon_url.as_str()).map_err(|e| e.to_string())?;
+    {
+        let mut transfer = easy.transfer();
+        transfer
+            .write_function(|chunk| {
+                data.extend_from_slice(chunk);
+                Ok(chunk.len())
+            })
+            .map_err(|e| e.to_string())?;
+        transfer.perform().map_err(|e| e.to_string())?;
+    }
+
+    let dir = Path::new(config_dir).join("web-apps");
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(format!("{}.png", id));
+    fs::write(&path, data).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// Add a desktop entry that opens the app in a Moto app window.
+#[cfg(target_os = "linux")]
+fn create_launcher(id: &str, app: &WebApp, icon_path: Option<&Path>) -> Result<(), String> {
+    let dirs = directories::BaseDirs::new().ok_or("no home directory")?;
+    let dir = dirs.data_dir().join("applications");
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let mut entry = format!(
+        "[Desktop Entry]\nType=Application\nName={}\nExec={} --app {}\nCategories=Network;\n",
+        app.name.replace(['\n', '\r'], " "),
+        desktop_entry_argument(&exe.to_string_lossy()),
+        desktop_entry_argument(app.start_url.as_str()),
+    );
+    if let Some(icon_path) = icon_path {
+        entry.push_str(&format!("Icon={}\n", icon_path.display()));
+    }
+    fs::write(dir.join(format!("moto-{}.desktop", id)), entry).map_err(|e| e.to_string())
+}
+
+/// Other platforms only record the app for now.
+#[cfg(not(target_os = "linux"))]
+fn create_launcher(_id: &str, _app: &WebApp, _icon_path: Option<&Path>) -> Result<(), String> {
+    Ok(())
+}
+
+/// Quote an argument for the Exec key of a desktop entry.
+#[cfg(target_os = "linux")]
+fn desktop_entry_argument(argument: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in argument.chars() {
+        match c {
+            '"' | '`' | '$' | '\\' => quoted.push_str(&format!("\\\\{}", c)),
+            '%' => quoted.push_str("%%"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
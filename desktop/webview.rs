@@ -3,11 +3,12 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::Write;
 use std::rc::Rc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 use std::vec::Drain;
 use std::{env, thread};
 
@@ -17,28 +18,93 @@ use gilrs::ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Repeat, Repla
 use gilrs::{EventType, Gilrs};
 use keyboard_types::{Key, KeyboardEvent, Modifiers, ShortcutMatcher};
 use log::{debug, error, info, trace, warn};
-use servo::base::id::TopLevelBrowsingContextId as WebViewId;
+use servo::base::id::{BrowsingContextId, TopLevelBrowsingContextId as WebViewId};
 use servo::compositing::windowing::{EmbedderEvent, WebRenderDebugOption};
 use servo::embedder_traits::{
     CompositorEventVariant, ContextMenuResult, DualRumbleEffectParams, EmbedderMsg, FilterPattern,
     GamepadHapticEffectType, PermissionPrompt, PermissionRequest, PromptDefinition, PromptOrigin,
     PromptResult,
 };
-use servo::ipc_channel::ipc::IpcSender;
+use servo::ipc_channel::ipc::{self, IpcReceiver, IpcSender, TryRecvError};
+use servo::script_traits::webdriver_msg::{
+    WebDriverJSResult, WebDriverJSValue, WebDriverScriptCommand,
+};
 use servo::script_traits::{
     GamepadEvent, GamepadIndex, GamepadInputBounds, GamepadSupportedHapticEffects,
-    GamepadUpdateType, TouchEventType, TraversalDirection,
+    GamepadUpdateType, TouchEventType, TraversalDirection, WebDriverCommandMsg,
 };
 use servo::servo_config::opts;
 use servo::servo_url::ServoUrl;
-use servo::webrender_api::units::DeviceRect;
+use servo::webrender_api::units::{DeviceIntPoint, DeviceRect, LayoutVector2D};
 use servo::webrender_api::ScrollLocation;
 use tinyfiledialogs::{self, MessageBoxIcon, OkCancel, YesNo};
 
+use super::db::{
+    add_history_entry, config_dir, get_site_setting, prune_history, remove_site_setting,
+    set_history_title, set_site_setting,
+};
 use super::keyutils::{CMD_OR_ALT, CMD_OR_CONTROL};
+use super::motion::animations_allowed;
+use super::moto_prefs;
+use super::navigation_log;
+use super::session::{save_session, Session};
+use super::shortcuts::enabled_shortcuts;
+use super::web_apps::{install_web_app, WebApp};
 use super::window_trait::{WindowPortsMethods, LINE_HEIGHT};
+use crate::desktop::protocols::moto::error_url;
 use crate::desktop::tracing::{trace_embedder_event, trace_embedder_msg};
-use crate::parser::location_bar_input_to_url;
+use crate::parser::{location_bar_input_to_url, parse_data_url};
+
+/// Moves focus between links and controls with the arrow keys, injected into each page when
+/// spatial navigation is enabled.
+const SPATIAL_NAVIGATION_SCRIPT: &str = include_str!("../resources/spatial-navigation.js");
+
+/// Searches the page as the user types after pressing `/` or `'`, injected into each page when
+/// quick find is enabled.
+const QUICK_FIND_SCRIPT: &str = include_str!("../resources/quick-find.js");
+
+/// Keeps what the page prints to the console for the JavaScript console panel, injected into each
+/// page.
+const CONSOLE_SCRIPT: &str = include_str!("../resources/console.js");
+
+/// Opens links in a new tab on middle-click and Ctrl+click, injected into each page.
+const LINK_CLICKS_SCRIPT: &str = include_str!("../resources/link-clicks.js");
+
+/// Returns the readable text of the page, for the Copy Page Text and Save Page Text actions.
+const PAGE_TEXT_SCRIPT: &str = include_str!("../resources/page-text.js");
+
+/// Returns the text selected in the page, for copying it when the user presses Ctrl+C or Ctrl+X.
+const COPY_SELECTION_SCRIPT: &str = include_str!("../resources/copy-selection.js");
+
+/// Highlights the find bar's search, given as an argument with the direction, and moves to the
+/// next or previous match.
+const FIND_IN_PAGE_SCRIPT: &str = include_str!("../resources/find-in-page.js");
+
+/// Reads the app details from the page's web app manifest, for installing the site.
+const WEB_APP_MANIFEST_SCRIPT: &str = include_str!("../resources/web-app-manifest.js");
+
+/// Returns the URL to load when the user navigates to the given one. This is an error page for
+/// `data:` URLs that are malformed or longer than the `data-url.max-length` pref allows, since
+/// those can hang the browser.
+pub fn checked_navigation_url(url: ServoUrl) -> ServoUrl {
+    if url.scheme() != "data" {
+        return url;
+    }
+    let max_length = moto_prefs::get_int("data-url.max-length");
+    let length = url.as_str().len();
+    if max_length > 0 && length as i64 > max_length {
+        warn!("Not loading data: URL of {} bytes", length);
+        return error_url(&format!(
+            "This data: URL is {} bytes long, more than the limit of {} bytes set by the \
+             data-url.max-length pref.",
+            length, max_length
+        ));
+    }
+    if parse_data_url(url.as_str()).is_none() {
+        return error_url("This data: URL is malformed.");
+    }
+    url
+}
 
 pub struct WebViewManager<Window: WindowPortsMethods + ?Sized> {
     status_text: Option<String>,
@@ -48,12 +114,31 @@ pub struct WebViewManager<Window: WindowPortsMethods + ?Sized> {
     /// and we exit if it ever becomes empty.
     webviews: HashMap<WebViewId, WebView>,
 
-    /// The order in which the webviews were created.
+    /// The order in which the webviews are shown in the tab strip.
     creation_order: Vec<WebViewId>,
 
+    /// Webviews requested by the browser chrome (e.g. the new tab button) that have not been
+    /// opened yet. Any other new webview was opened by a page.
+    chrome_opened_webviews: HashSet<WebViewId>,
+
+    /// Webviews being reopened from a previous session that have not been opened yet, and the
+    /// one to focus once it has.
+    restoring_webviews: HashSet<WebViewId>,
+    restore_focus: Option<WebViewId>,
+    /// Webviews that are opening without taking focus from the focused one.
+    background_webviews: HashSet<WebViewId>,
+
+    /// Named groups of tabs, and which group each grouped webview belongs to.
+    /// The members of a group are always next to each other in `creation_order`.
+    tab_groups: HashMap<TabGroupId, TabGroup>,
+    tab_group_membership: HashMap<WebViewId, TabGroupId>,
+    next_tab_group_id: usize,
+
     /// The webview that is currently focused.
     /// Modified by EmbedderMsg::WebViewFocused and EmbedderMsg::WebViewBlurred.
     focused_webview_id: Option<WebViewId>,
+    /// The open webviews that have been focused, most recently focused first.
+    recently_focused: Vec<WebViewId>,
 
     /// Pre-creation state for WebViews.
     /// This is needed because in some situations the WebViewOpened event is sent
@@ -67,6 +152,120 @@ pub struct WebViewManager<Window: WindowPortsMethods + ?Sized> {
     haptic_effects: HashMap<usize, HapticEffect>,
     shutdown_requested: bool,
     history: Vec<ServoUrl>,
+
+    /// The port Servo's devtools server is listening on, if it has been started.
+    devtools_port: Option<u16>,
+
+    /// Scripts run in a webview's page that Servo hasn't returned the result of yet.
+    pending_scripts: Vec<(WebViewId, ScriptPurpose, IpcReceiver<WebDriverJSResult>)>,
+
+    /// Where to send the user's answer when a page's beforeunload handler asks them to confirm
+    /// leaving it, until they answer.
+    unload_prompt: Option<IpcSender<bool>>,
+
+    /// Whether the open tabs have changed since the session snapshot was last saved, and when
+    /// that was.
+    session_changed: bool,
+    last_session_save: Instant,
+
+    /// Whether this is a single-purpose window opened with `--app`, which has no tabs to save.
+    app_mode: bool,
+
+    /// The number of loads that have completed in any webview.
+    completed_loads: usize,
+
+    /// The scroll being animated, if the general.smoothscroll pref is set.
+    smooth_scroll: Option<SmoothScroll>,
+
+    /// Where to send the result of the context menu the page asked for, while it's open.
+    context_menu: Option<IpcSender<ContextMenuResult>>,
+
+    /// Webviews that Servo was asked to create but hasn't opened yet, and when that was.
+    opening_webviews: HashMap<WebViewId, Instant>,
+    /// Webviews that were given up on for taking too long to open, until Servo closes them.
+    abandoned_webviews: HashSet<WebViewId>,
+
+    /// A problem to tell the user about, such as a tab that failed to open.
+    notice: Option<String>,
+}
+
+/// How often the session snapshot is saved, at most.
+const SESSION_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long to wait for a new webview to open before giving up on it.
+const WEBVIEW_OPEN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Smooth scrolling eases out, covering about two thirds of the remaining distance in this time.
+const SMOOTH_SCROLL_TIME_CONSTANT: Duration = Duration::from_millis(60);
+
+/// How much each zoom in or out changes the page zoom by.
+const ZOOM_STEP: f32 = 1.1;
+
+/// The page zoom is kept within the range that Servo's compositor allows.
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 8.0;
+
+/// How many visits are added to the history between prunes, which forget the pages beyond the
+/// history.max-entries and history.retention-days prefs. The first visit of a session prunes too.
+const HISTORY_PRUNE_INTERVAL: usize = 100;
+
+/// The number of visits added to the history this session.
+static HISTORY_VISITS: AtomicUsize = AtomicUsize::new(0);
+
+/// Set when the history DB couldn't be created, so there is nowhere to record visits.
+static HISTORY_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Stop recording visits for the rest of the session.
+pub fn disable_history() {
+    HISTORY_DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// A scroll delta being sent to Servo a bit at a time over several frames.
+struct SmoothScroll {
+    /// The part of the delta that hasn't been sent yet.
+    remaining: LayoutVector2D,
+    cursor: DeviceIntPoint,
+    last_step: Instant,
+}
+
+/// Why Moto ran a script in a page, which decides what to do with its result.
+#[derive(Clone, Copy, Debug)]
+enum ScriptPurpose {
+    /// Evaluating what was typed into the JavaScript console panel.
+    Console,
+    /// Installing [CONSOLE_SCRIPT].
+    ConsoleCapture,
+    /// Taking the lines the page has printed since they were last taken.
+    ConsoleOutput,
+    CopyAsCurl,
+    CopySelection,
+    Find,
+    HasManifest,
+    InstallWebApp,
+    LinkClicks,
+    PageLanguage,
+    PageText(PageTextAction),
+    QuickFind,
+    SpatialNavigation,
+}
+
+/// What to do with the text of a page.
+#[derive(Clone, Copy, Debug)]
+pub enum PageTextAction {
+    Copy,
+    Save,
+}
+
+/// The find bar of a webview, while it is open.
+#[derive(Debug, Default)]
+pub struct FindBar {
+    /// The text being searched for.
+    pub query: String,
+    /// The number of the current match, counting from one, and the number of matches, once the
+    /// page has been searched.
+    pub matches: Option<(u32, u32)>,
+    /// When the text was last edited, if the page hasn't been searched for it since.
+    pub edited: Option<Instant>,
 }
 
 #[derive(Clone, Default)]
@@ -82,6 +281,16 @@ pub enum LoadStatus {
     LoadComplete,
 }
 
+impl LoadStatus {
+    /// Describes the status in words, for anyone who can't see the spinner.
+    pub fn description(self) -> &'static str {
+        match self {
+            LoadStatus::HeadParsed | LoadStatus::LoadStart => "Loading",
+            LoadStatus::LoadComplete => "Loaded",
+        }
+    }
+}
+
 // The state of each Tab/WebView
 #[derive(Debug)]
 pub struct WebView {
@@ -90,6 +299,23 @@ pub struct WebView {
     pub url: Option<ServoUrl>,
     pub focused: bool,
     pub load_status: LoadStatus,
+    /// The language of the loaded page, from its `<html lang>` attribute.
+    pub language: Option<String>,
+    /// Whether the loaded page links to a web app manifest, so it can be installed.
+    pub has_manifest: bool,
+    /// How often to reload the page, if the user has asked for that, and when to do so next.
+    auto_reload: Option<(Duration, Instant)>,
+    /// The session history, and the index of the current entry in it.
+    session_history: Vec<ServoUrl>,
+    session_history_index: usize,
+    /// When the current load started, for the navigation log.
+    load_started: Option<Instant>,
+    /// The find bar, if it is open in this webview.
+    pub find_bar: Option<FindBar>,
+    /// The lines shown in the JavaScript console panel for the loaded page.
+    pub console: Vec<String>,
+    /// The page zoom, which is applied whenever the webview is focused.
+    zoom: f32,
 }
 
 impl WebView {
@@ -100,8 +326,52 @@ impl WebView {
             url: preload_data.url,
             focused: false,
             load_status: LoadStatus::LoadComplete,
+            language: None,
+            has_manifest: false,
+            auto_reload: None,
+            session_history: vec![],
+            session_history_index: 0,
+            load_started: None,
+            find_bar: None,
+            console: vec![],
+            zoom: 1.0,
         }
     }
+
+    /// Returns the page zoom, where 1 is 100%.
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Returns the URL of the session history entry before the current one, if there is one.
+    pub fn back_url(&self) -> Option<&ServoUrl> {
+        self.session_history_index
+            .checked_sub(1)
+            .and_then(|index| self.session_history.get(index))
+    }
+
+    /// Returns the URL of the session history entry after the current one, if there is one.
+    pub fn forward_url(&self) -> Option<&ServoUrl> {
+        self.session_history.get(self.session_history_index + 1)
+    }
+
+    /// Returns how often the page is reloaded automatically, if it is.
+    pub fn auto_reload_interval(&self) -> Option<Duration> {
+        self.auto_reload.map(|(interval, _)| interval)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct TabGroupId(usize);
+
+/// A named set of tabs that are shown together in the tab strip.
+#[derive(Debug)]
+pub struct TabGroup {
+    pub name: String,
+    /// Index into the palette used to draw groups, which the tab strip wraps around.
+    pub color: usize,
+    /// Whether the group's tabs are hidden, leaving only the group itself in the tab strip.
+    pub collapsed: bool,
 }
 
 pub struct ServoEventResponse {
@@ -123,7 +393,15 @@ where
             status_text: None,
             webviews: HashMap::default(),
             creation_order: vec![],
+            chrome_opened_webviews: HashSet::default(),
+            restoring_webviews: HashSet::default(),
+            restore_focus: None,
+            background_webviews: HashSet::default(),
+            tab_groups: HashMap::default(),
+            tab_group_membership: HashMap::default(),
+            next_tab_group_id: 0,
             focused_webview_id: None,
+            recently_focused: vec![],
             webview_preload_data: HashMap::default(),
             window,
             clipboard: match Clipboard::new() {
@@ -145,9 +423,29 @@ where
             event_queue: Vec::new(),
             shutdown_requested: false,
             history: vec![],
+            devtools_port: None,
+            pending_scripts: vec![],
+            unload_prompt: None,
+            session_changed: false,
+            last_session_save: Instant::now(),
+            app_mode: false,
+            completed_loads: 0,
+            smooth_scroll: None,
+            context_menu: None,
+            opening_webviews: HashMap::default(),
+            abandoned_webviews: HashSet::default(),
+            notice: None,
         }
     }
 
+    pub fn app_mode(&self) -> bool {
+        self.app_mode
+    }
+
+    pub fn set_app_mode(&mut self, app_mode: bool) {
+        self.app_mode = app_mode;
+    }
+
     pub fn get_mut(&mut self, webview_id: WebViewId) -> Option<&mut WebView> {
         self.webviews.get_mut(&webview_id)
     }
@@ -185,15 +483,223 @@ where
         }
     }
 
+    pub fn completed_loads(&self) -> usize {
+        self.completed_loads
+    }
+
+    /// Returns true iff the page has asked for a context menu which hasn't been closed yet.
+    pub fn has_context_menu(&self) -> bool {
+        self.context_menu.is_some()
+    }
+
+    /// Tell the page its context menu has closed. Any item chosen from it is handled by Moto.
+    pub fn close_context_menu(&mut self) {
+        if let Some(sender) = self.context_menu.take() {
+            if let Err(e) = sender.send(ContextMenuResult::Dismissed) {
+                warn!("Failed to close context menu ({})", e);
+            }
+        }
+    }
+
+    /// Get the text of the focused webview's page, and copy or save it.
+    pub fn request_page_text(&mut self, action: PageTextAction) {
+        let Some(webview_id) = self.focused_webview_id else {
+            return;
+        };
+        let purpose = ScriptPurpose::PageText(action);
+        self.run_script(webview_id, purpose, PAGE_TEXT_SCRIPT.to_owned());
+    }
+
+    /// Open the find bar in the focused webview, keeping the search from the last time it was open.
+    pub fn open_find_bar(&mut self) {
+        let webview_id = self.focused_webview_id;
+        if let Some(webview) = webview_id.and_then(|id| self.get_mut(id)) {
+            webview.find_bar.get_or_insert_with(FindBar::default);
+        }
+    }
+
+    /// Highlight the given text in the focused webview's page and move to the next or previous
+    /// match, or remove the highlights if the text is empty.
+    pub fn find_in_page(&mut self, query: String, forward: bool) {
+        let Some(webview_id) = self.focused_webview_id else {
+            return;
+        };
+        let script = format!(
+            "{}({}, {})",
+            FIND_IN_PAGE_SCRIPT,
+            js_string_literal(&query),
+            forward
+        );
+        self.run_script(webview_id, ScriptPurpose::Find, script);
+    }
+
+    /// Close the find bar in the focused webview, removing the highlights.
+    pub fn close_find_bar(&mut self) {
+        let webview_id = self.focused_webview_id;
+        let Some(webview) = webview_id.and_then(|id| self.get_mut(id)) else {
+            return;
+        };
+        if webview.find_bar.take().is_some() {
+            self.find_in_page(String::new(), true);
+        }
+    }
+
+    pub fn zoom_in(&mut self) {
+        self.zoom_focused_webview(|zoom| zoom * ZOOM_STEP);
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.zoom_focused_webview(|zoom| zoom / ZOOM_STEP);
+    }
+
+    pub fn reset_zoom(&mut self) {
+        self.zoom_focused_webview(|_| 1.0);
+    }
+
+    fn zoom_focused_webview(&mut self, zoom: impl FnOnce(f32) -> f32) {
+        let webview_id = self.focused_webview_id;
+        let Some(webview) = webview_id.and_then(|id| self.get_mut(id)) else {
+            return;
+        };
+        webview.zoom = zoom(webview.zoom).clamp(MIN_ZOOM, MAX_ZOOM);
+        let zoom = webview.zoom;
+        // Remember the zoom for the site, for when it is visited again.
+        if let Some(origin) = webview.url.as_ref().and_then(site_origin) {
+            let config_dir = config_dir();
+            let result = if zoom == 1.0 {
+                remove_site_setting(&config_dir, &origin, "zoom")
+            } else {
+                set_site_setting(&config_dir, &origin, "zoom", &zoom.to_string())
+            };
+            if let Err(e) = result {
+                warn!("Failed to store zoom for {} ({})", origin, e);
+            }
+        }
+        self.apply_zoom(zoom);
+    }
+
+    /// Servo's page zoom applies to every webview, so set it to that of the focused webview each
+    /// time it changes or another webview is focused.
+    fn apply_zoom(&mut self, zoom: f32) {
+        // Servo only multiplies the zoom, so start again from 100%.
+        self.event_queue.push(EmbedderEvent::ResetZoom);
+        if zoom != 1.0 {
+            self.event_queue.push(EmbedderEvent::Zoom(zoom));
+        }
+    }
+
+    /// Install the focused webview's site as an app, using its web app manifest.
+    pub fn install_web_app(&mut self) {
+        let Some(webview_id) = self.focused_webview_id else {
+            return;
+        };
+        let script = WEB_APP_MANIFEST_SCRIPT.to_owned();
+        self.run_script(webview_id, ScriptPurpose::InstallWebApp, script);
+    }
+
+    /// Copy a cURL command that requests the focused webview's page like Servo did, as far as the
+    /// page can tell. Only cookies visible to scripts are included.
+    pub fn copy_as_curl(&mut self) {
+        let Some(webview_id) = self.focused_webview_id else {
+            return;
+        };
+        let script = "[location.href, navigator.userAgent, navigator.language, document.referrer, \
+                      document.cookie]";
+        self.run_script(webview_id, ScriptPurpose::CopyAsCurl, script.to_owned());
+    }
+
+    /// Evaluate the given script in the focused webview's page, showing it and its result in the
+    /// JavaScript console panel.
+    pub fn evaluate_in_console(&mut self, script: String) {
+        let Some(webview_id) = self.focused_webview_id else {
+            return;
+        };
+        // Show what the page printed before the script first.
+        self.read_console_output();
+        if let Some(webview) = self.get_mut(webview_id) {
+            webview.console.push(format!("> {}", script));
+        }
+        self.run_script(webview_id, ScriptPurpose::Console, script);
+        self.read_console_output();
+    }
+
+    /// Add what the focused webview's page has printed since the last time to the JavaScript
+    /// console panel.
+    pub fn read_console_output(&mut self) {
+        let Some(webview_id) = self.focused_webview_id else {
+            return;
+        };
+        let script = "(window.__motoConsole || []).splice(0)".to_owned();
+        self.run_script(webview_id, ScriptPurpose::ConsoleOutput, script);
+    }
+
+    pub fn devtools_port(&self) -> Option<u16> {
+        self.devtools_port
+    }
+
     pub fn status_text(&self) -> Option<String> {
         self.status_text.clone()
     }
 
     pub fn get_events(&mut self) -> Vec<EmbedderEvent> {
+        // Every new webview is requested here, wherever the request came from, so this is where
+        // they start waiting to open.
+        let now = Instant::now();
+        for event in &self.event_queue {
+            if let EmbedderEvent::NewWebView(_, webview_id) = event {
+                self.opening_webviews.insert(*webview_id, now);
+            }
+        }
         std::mem::take(&mut self.event_queue)
     }
 
-    // Returns the webviews in the creation order.
+    /// Returns when the webview that has been opening the longest should be given up on, if any.
+    pub fn opening_webviews_deadline(&self) -> Option<Instant> {
+        self.opening_webviews
+            .values()
+            .min()
+            .map(|requested| *requested + WEBVIEW_OPEN_TIMEOUT)
+    }
+
+    /// Give up on any webviews that Servo hasn't opened in time, leaving focus where it is, and
+    /// returning true iff there were any.
+    pub fn check_opening_webviews(&mut self) -> bool {
+        let now = Instant::now();
+        let failed: Vec<WebViewId> = self
+            .opening_webviews
+            .iter()
+            .filter(|(_, requested)| **requested + WEBVIEW_OPEN_TIMEOUT <= now)
+            .map(|(webview_id, _)| *webview_id)
+            .collect();
+        for webview_id in &failed {
+            warn!("{:?} failed to open", webview_id);
+            self.opening_webviews.remove(webview_id);
+            self.abandoned_webviews.insert(*webview_id);
+            self.chrome_opened_webviews.remove(webview_id);
+            self.restoring_webviews.remove(webview_id);
+            self.background_webviews.remove(webview_id);
+            self.webview_preload_data.remove(webview_id);
+        }
+        if self.restoring_webviews.is_empty() {
+            self.restore_focus = None;
+        }
+        if failed.is_empty() {
+            return false;
+        }
+        self.notice = Some(if failed.len() == 1 {
+            "Couldn't open a new tab".to_owned()
+        } else {
+            format!("Couldn't open {} new tabs", failed.len())
+        });
+        true
+    }
+
+    /// Returns any problem the user should be told about, once.
+    pub fn take_notice(&mut self) -> Option<String> {
+        self.notice.take()
+    }
+
+    // Returns the webviews in the tab strip order.
     pub fn webviews(&self) -> Vec<(WebViewId, &WebView)> {
         let mut res = vec![];
         for id in &self.creation_order {
@@ -202,16 +708,105 @@ where
         res
     }
 
-    /// List the most recent history entries
+    /// Returns the webviews that have been focused, most recently focused first.
+    pub fn recently_focused_webviews(&self) -> Vec<(WebViewId, &WebView)> {
+        self.recently_focused
+            .iter()
+            .filter_map(|id| Some((*id, self.webviews.get(id)?)))
+            .collect()
+    }
+
+    /// Returns the group the given webview belongs to, if any.
+    pub fn tab_group(&self, webview_id: WebViewId) -> Option<(TabGroupId, &TabGroup)> {
+        let group_id = *self.tab_group_membership.get(&webview_id)?;
+        self.tab_groups
+            .get(&group_id)
+            .map(|group| (group_id, group))
+    }
+
+    pub fn tab_group_mut(&mut self, group_id: TabGroupId) -> Option<&mut TabGroup> {
+        self.tab_groups.get_mut(&group_id)
+    }
+
+    /// Returns all tab groups, oldest first.
+    pub fn tab_groups(&self) -> Vec<(TabGroupId, &TabGroup)> {
+        let mut groups = self
+            .tab_groups
+            .iter()
+            .map(|(id, group)| (*id, group))
+            .collect::<Vec<_>>();
+        groups.sort_by_key(|(id, _)| *id);
+        groups
+    }
+
+    /// Create a new tab group containing just the given webview.
+    pub fn new_tab_group(&mut self, webview_id: WebViewId) {
+        let group_id = TabGroupId(self.next_tab_group_id);
+        self.next_tab_group_id += 1;
+        self.tab_groups.insert(
+            group_id,
+            TabGroup {
+                name: format!("Group {}", group_id.0 + 1),
+                color: group_id.0,
+                collapsed: false,
+            },
+        );
+        self.set_tab_group(webview_id, Some(group_id));
+    }
+
+    /// Move the given webview into the given tab group, or out of any group if None.
+    pub fn set_tab_group(&mut self, webview_id: WebViewId, group_id: Option<TabGroupId>) {
+        let Some(index) = self.creation_order.iter().position(|id| *id == webview_id) else {
+            return;
+        };
+        self.creation_order.remove(index);
+        let old_group_id = match group_id {
+            Some(group_id) => self.tab_group_membership.insert(webview_id, group_id),
+            None => self.tab_group_membership.remove(&webview_id),
+        };
+
+        // Keep groups together by placing the webview after the other members of the group it
+        // joined, or of the group it left.
+        let anchor = group_id.or(old_group_id);
+        let index = self
+            .creation_order
+            .iter()
+            .rposition(|id| {
+                anchor.is_some() && self.tab_group_membership.get(id) == anchor.as_ref()
+            })
+            .map_or(index, |last_member| last_member + 1);
+        self.creation_order.insert(index, webview_id);
+        self.remove_empty_tab_groups();
+    }
+
+    /// Remove the given tab group, leaving its webviews where they are.
+    pub fn ungroup_tabs(&mut self, group_id: TabGroupId) {
+        self.tab_group_membership.retain(|_, id| *id != group_id);
+        self.tab_groups.remove(&group_id);
+    }
+
+    fn remove_empty_tab_groups(&mut self) {
+        self.tab_groups.retain(|id, _| {
+            self.tab_group_membership
+                .values()
+                .any(|group_id| group_id == id)
+        });
+    }
+
+    /// Returns the visited URLs, most recent first.
     pub fn history(&self) -> Vec<ServoUrl> {
         self.history
             .iter()
             .rev()
-            .take(20) // TODO: Make this number configurable
             .cloned()
             .collect::<Vec<ServoUrl>>()
     }
 
+    /// Forget the visited URLs listed by [Self::history].
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
     pub fn handle_window_events(&mut self, events: Vec<EmbedderEvent>) {
         for event in events {
             trace_embedder_event!(event, "{event:?}");
@@ -219,11 +814,78 @@ where
                 EmbedderEvent::Keyboard(key_event) => {
                     self.handle_key_from_window(key_event);
                 },
+                EmbedderEvent::NewWebView(url, webview_id) => {
+                    self.chrome_opened_webviews.insert(webview_id);
+                    self.event_queue
+                        .push(EmbedderEvent::NewWebView(url, webview_id));
+                },
+                EmbedderEvent::Scroll(
+                    ScrollLocation::Delta(delta),
+                    cursor,
+                    TouchEventType::Move,
+                ) if Self::smooth_scrolling_enabled() => {
+                    self.start_smooth_scroll(delta, cursor);
+                },
                 event => {
                     self.event_queue.push(event);
                 },
             }
         }
+        self.step_smooth_scroll();
+    }
+
+    fn smooth_scrolling_enabled() -> bool {
+        moto_prefs::get_bool("general.smoothscroll") && animations_allowed()
+    }
+
+    /// Add the given delta to the scroll being animated, or start animating it.
+    fn start_smooth_scroll(&mut self, delta: LayoutVector2D, cursor: DeviceIntPoint) {
+        match &mut self.smooth_scroll {
+            Some(scroll) => {
+                scroll.remaining += delta;
+                scroll.cursor = cursor;
+            },
+            None => {
+                self.smooth_scroll = Some(SmoothScroll {
+                    remaining: delta,
+                    cursor,
+                    last_step: Instant::now(),
+                })
+            },
+        }
+    }
+
+    /// Send Servo the part of the animated scroll due since the last step.
+    fn step_smooth_scroll(&mut self) {
+        let Some(scroll) = &mut self.smooth_scroll else {
+            return;
+        };
+        let now = Instant::now();
+        let elapsed = now - scroll.last_step;
+        scroll.last_step = now;
+        let fraction =
+            1.0 - (-elapsed.as_secs_f32() / SMOOTH_SCROLL_TIME_CONSTANT.as_secs_f32()).exp();
+        let mut step = scroll.remaining * fraction;
+        if (scroll.remaining - step).length() < 0.5 {
+            step = scroll.remaining;
+        }
+        scroll.remaining -= step;
+        let cursor = scroll.cursor;
+        if scroll.remaining == LayoutVector2D::zero() {
+            self.smooth_scroll = None;
+        }
+        if step != LayoutVector2D::zero() {
+            self.event_queue.push(EmbedderEvent::Scroll(
+                ScrollLocation::Delta(step),
+                cursor,
+                TouchEventType::Move,
+            ));
+        }
+    }
+
+    /// Returns true iff a smooth scroll is still being animated.
+    pub fn is_smooth_scrolling(&self) -> bool {
+        self.smooth_scroll.is_some()
     }
 
     /// Handle updates to connected gamepads from GilRs
@@ -422,6 +1084,113 @@ where
         stopped_successfully
     }
 
+    /// Returns when the session snapshot should next be saved, or None if it is up to date or this
+    /// is an app window.
+    pub fn session_save_deadline(&self) -> Option<Instant> {
+        (self.session_changed && !self.app_mode)
+            .then_some(self.last_session_save + SESSION_SAVE_INTERVAL)
+    }
+
+    /// Reload the given webview at the given interval from now on, or stop if None.
+    pub fn set_auto_reload(&mut self, webview_id: WebViewId, interval: Option<Duration>) {
+        if let Some(webview) = self.webviews.get_mut(&webview_id) {
+            webview.auto_reload = interval.map(|interval| (interval, Instant::now() + interval));
+        }
+    }
+
+    /// Returns true iff a page is waiting for the user to confirm leaving it.
+    pub fn unload_prompt_pending(&self) -> bool {
+        self.unload_prompt.is_some()
+    }
+
+    /// Answer the page waiting for the user to confirm leaving it, which unloads iff `leave`.
+    pub fn answer_unload_prompt(&mut self, leave: bool) {
+        if let Some(sender) = self.unload_prompt.take() {
+            if let Err(e) = sender.send(leave) {
+                warn!("Failed to send AllowUnload response: {}", e);
+            }
+        }
+    }
+
+    /// Returns when the next automatic reload is due, if any webview has one.
+    pub fn auto_reload_deadline(&self) -> Option<Instant> {
+        self.webviews
+            .values()
+            .filter_map(|webview| webview.auto_reload)
+            .map(|(_, next)| next)
+            .min()
+    }
+
+    /// Reload any webviews whose automatic reload is due.
+    pub fn auto_reload(&mut self) {
+        let now = Instant::now();
+        for (webview_id, webview) in &mut self.webviews {
+            let Some((interval, next)) = &mut webview.auto_reload else {
+                continue;
+            };
+            if *next <= now {
+                *next = now + *interval;
+                self.event_queue.push(EmbedderEvent::Reload(*webview_id));
+            }
+        }
+    }
+
+    /// Save a snapshot of the open tabs if they have changed and it is time to do so.
+    pub fn autosave_session(&mut self) {
+        if self
+            .session_save_deadline()
+            .map_or(true, |deadline| deadline > Instant::now())
+        {
+            return;
+        }
+        self.session_changed = false;
+        self.last_session_save = Instant::now();
+
+        let config_dir = config_dir();
+        if config_dir.is_empty() {
+            return;
+        }
+        let mut session = Session {
+            urls: vec![],
+            focused: 0,
+        };
+        for id in &self.creation_order {
+            let Some(url) = self
+                .webviews
+                .get(id)
+                .and_then(|webview| webview.url.clone())
+            else {
+                continue;
+            };
+            if Some(*id) == self.focused_webview_id {
+                session.focused = session.urls.len();
+            }
+            session.urls.push(url);
+        }
+        if let Err(e) = save_session(&config_dir, &session) {
+            warn!("Failed to save session ({})", e);
+        }
+    }
+
+    /// Reopen the tabs of the given session, in order, and focus the one that was focused.
+    pub fn restore_session(&mut self, session: Session) {
+        let webview_ids: Vec<WebViewId> = session.urls.iter().map(|_| WebViewId::new()).collect();
+        self.restore_focus = webview_ids.get(session.focused).copied();
+        for (url, &webview_id) in session.urls.into_iter().zip(&webview_ids) {
+            self.event_queue
+                .push(EmbedderEvent::NewWebView(url, webview_id));
+        }
+        self.restoring_webviews = webview_ids.into_iter().collect();
+    }
+
+    /// Open the given URL in a new webview, without focusing it.
+    pub fn open_in_background(&mut self, url: ServoUrl) {
+        let webview_id = WebViewId::new();
+        self.background_webviews.insert(webview_id);
+        self.event_queue
+            .push(EmbedderEvent::NewWebView(url, webview_id));
+    }
+
     pub fn shutdown_requested(&self) -> bool {
         self.shutdown_requested
     }
@@ -437,13 +1206,263 @@ where
             .position(|webview| webview.0 == focused_id)
     }
 
+    /// Returns where a newly opened webview should be placed in the tab strip. Webviews opened
+    /// by a page always go after the focused one, while those opened from the browser chrome
+    /// follow the `tabs.open-position` pref.
+    fn new_webview_index(&mut self, webview_id: WebViewId) -> usize {
+        // Restored tabs keep the order they had in the previous session.
+        if self.restoring_webviews.contains(&webview_id) {
+            return self.creation_order.len();
+        }
+        let after_current = !self.chrome_opened_webviews.remove(&webview_id)
+            || moto_prefs::get_str("tabs.open-position") == "after-current";
+        match self.get_focused_webview_index() {
+            Some(index) if after_current => index + 1,
+            _ => self.creation_order.len(),
+        }
+    }
+
+    /// Run the given script in the page of the given webview. Its result is handled according to
+    /// `purpose` once Servo returns it.
+    fn run_script(&mut self, webview_id: WebViewId, purpose: ScriptPurpose, script: String) {
+        let (sender, receiver) = match ipc::channel() {
+            Ok(channel) => channel,
+            Err(e) => {
+                warn!("Failed to create IPC channel ({})", e);
+                return;
+            },
+        };
+        self.event_queue.push(EmbedderEvent::WebDriverCommand(
+            WebDriverCommandMsg::ScriptCommand(
+                BrowsingContextId::from(webview_id),
+                WebDriverScriptCommand::ExecuteScript(script, sender),
+            ),
+        ));
+        self.pending_scripts.push((webview_id, purpose, receiver));
+    }
+
+    /// Handle the results of any scripts Servo has finished running, returning true iff the
+    /// minibrowser needs updating.
+    fn receive_script_results(&mut self) -> bool {
+        let mut changed = false;
+        let mut pending_scripts = std::mem::take(&mut self.pending_scripts);
+        pending_scripts.retain(|(webview_id, purpose, receiver)| {
+            let result = match receiver.try_recv() {
+                Err(TryRecvError::Empty) => return true,
+                Ok(result) => result,
+                Err(e) => {
+                    warn!("Failed to receive {:?} script result ({:?})", purpose, e);
+                    return false;
+                },
+            };
+            match purpose {
+                ScriptPurpose::Console => {
+                    let line = match result {
+                        Ok(value) => format!("{:?}", value),
+                        Err(error) => format!("Error: {:?}", error),
+                    };
+                    if let Some(webview) = self.webviews.get_mut(webview_id) {
+                        webview.console.push(line);
+                        changed = true;
+                    }
+                },
+                ScriptPurpose::ConsoleCapture => {
+                    if let Err(e) = result {
+                        warn!("Failed to set up console capture ({:?})", e);
+                    }
+                },
+                ScriptPurpose::ConsoleOutput => {
+                    let lines = match result {
+                        Ok(WebDriverJSValue::ArrayLike(values)) => values,
+                        result => {
+                            warn!("Failed to read console output ({:?})", result);
+                            return false;
+                        },
+                    };
+                    if let Some(webview) = self.webviews.get_mut(webview_id) {
+                        changed |= !lines.is_empty();
+                        webview.console.extend(lines.into_iter().map(|line| match line {
+                            WebDriverJSValue::String(line) => line,
+                            value => format!("{:?}", value),
+                        }));
+                    }
+                },
+                ScriptPurpose::CopyAsCurl => match curl_command_from_script_result(result) {
+                    Ok(command) => {
+                        if let Some(clipboard) = &mut self.clipboard {
+                            if let Err(e) = clipboard.set_text(command) {
+                                warn!("Error setting clipboard contents ({})", e);
+                            }
+                        }
+                    },
+                    Err(e) => warn!("Failed to get request details ({})", e),
+                },
+                ScriptPurpose::CopySelection => match result {
+                    Ok(WebDriverJSValue::String(text)) if !text.is_empty() => {
+                        if let Some(clipboard) = &mut self.clipboard {
+                            if let Err(e) = clipboard.set_text(text) {
+                                warn!("Error setting clipboard contents ({})", e);
+                            }
+                        }
+                    },
+                    Ok(_) => {},
+                    Err(e) => warn!("Failed to get selection ({:?})", e),
+                },
+                ScriptPurpose::Find => {
+                    let matches = match result {
+                        Ok(WebDriverJSValue::ArrayLike(values)) => match values.as_slice() {
+                            [WebDriverJSValue::Number(current), WebDriverJSValue::Number(count)] => {
+                                Some((*current as u32, *count as u32))
+                            },
+                            _ => None,
+                        },
+                        result => {
+                            warn!("Failed to find in page ({:?})", result);
+                            None
+                        },
+                    };
+                    let find_bar = self
+                        .webviews
+                        .get_mut(webview_id)
+                        .and_then(|webview| webview.find_bar.as_mut());
+                    if let Some(find_bar) = find_bar {
+                        find_bar.matches = matches;
+                        changed = true;
+                    }
+                },
+                ScriptPurpose::HasManifest => {
+                    let has_manifest = matches!(result, Ok(WebDriverJSValue::Boolean(true)));
+                    if let Some(webview) = self.webviews.get_mut(webview_id) {
+                        changed |= webview.has_manifest != has_manifest;
+                        webview.has_manifest = has_manifest;
+                    }
+                },
+                ScriptPurpose::InstallWebApp => match web_app_from_script_result(result) {
+                    Ok(app) => install_web_app(config_dir(), app),
+                    Err(e) => warn!("Failed to read web app manifest ({})", e),
+                },
+                ScriptPurpose::PageLanguage => {
+                    let language = match result {
+                        Ok(WebDriverJSValue::String(language)) if !language.is_empty() => {
+                            Some(language)
+                        },
+                        _ => None,
+                    };
+                    if let Some(webview) = self.webviews.get_mut(webview_id) {
+                        changed |= webview.language != language;
+                        webview.language = language;
+                    }
+                },
+                ScriptPurpose::PageText(action) => {
+                    let text = match result {
+                        Ok(WebDriverJSValue::String(text)) => text,
+                        result => {
+                            warn!("Failed to get page text ({:?})", result);
+                            return false;
+                        },
+                    };
+                    match action {
+                        PageTextAction::Copy => {
+                            if let Some(clipboard) = &mut self.clipboard {
+                                if let Err(e) = clipboard.set_text(text) {
+                                    warn!("Error setting clipboard contents ({})", e);
+                                }
+                            }
+                        },
+                        PageTextAction::Save => {
+                            let title = self
+                                .webviews
+                                .get(webview_id)
+                                .and_then(|webview| webview.title.clone())
+                                .unwrap_or_default();
+                            save_page_text(&title, &text);
+                        },
+                    }
+                },
+                ScriptPurpose::LinkClicks => {
+                    if let Err(e) = result {
+                        warn!("Failed to set up link clicks ({:?})", e);
+                    }
+                },
+                ScriptPurpose::QuickFind => {
+                    if let Err(e) = result {
+                        warn!("Failed to set up quick find ({:?})", e);
+                    }
+                },
+                ScriptPurpose::SpatialNavigation => {
+                    if let Err(e) = result {
+                        warn!("Failed to set up spatial navigation ({:?})", e);
+                    }
+                },
+            }
+            false
+        });
+        self.pending_scripts = pending_scripts;
+        changed
+    }
+
+    /// Returns the remembered decision for the given permission prompt on the webview's origin,
+    /// or asks the user and remembers their answer for next time.
+    fn permission_for_prompt(
+        &self,
+        webview_id: Option<WebViewId>,
+        prompt: PermissionPrompt,
+    ) -> PermissionRequest {
+        let origin = webview_id
+            .and_then(|id| self.webviews.get(&id))
+            .and_then(|webview| webview.url.as_ref())
+            .map(|url| url.origin().ascii_serialization());
+        let (PermissionPrompt::Request(name) | PermissionPrompt::Insecure(name)) = &prompt;
+        let setting = format!("permission.{:?}", name);
+        let config_dir = config_dir();
+
+        if let Some(origin) = &origin {
+            match get_site_setting(&config_dir, origin, &setting).as_deref() {
+                Some("granted") => return PermissionRequest::Granted,
+                Some("denied") => return PermissionRequest::Denied,
+                _ => {},
+            }
+        }
+
+        let permission_state = prompt_user(prompt);
+
+        // Only remember answers that actually came from the user.
+        if let Some(origin) = origin {
+            if cfg!(target_os = "linux") && !opts::get().headless {
+                let value = match permission_state {
+                    PermissionRequest::Granted => "granted",
+                    PermissionRequest::Denied => "denied",
+                };
+                if let Err(e) = set_site_setting(&config_dir, &origin, &setting, value) {
+                    warn!("Failed to store permission for {} ({})", origin, e);
+                }
+            }
+        }
+        permission_state
+    }
+
     /// Handle key events before sending them to Servo.
     fn handle_key_from_window(&mut self, key_event: KeyboardEvent) {
+        let enabled = enabled_shortcuts();
+        let select_tab = enabled("select-tab");
+        // Servo copies from form fields itself, but not the rest of the page, so the selection is
+        // copied here too. The key still goes to the page, which may handle it.
+        let copy = ShortcutMatcher::from_event(key_event.clone())
+            .shortcut(CMD_OR_CONTROL, 'C', || true)
+            .shortcut(CMD_OR_CONTROL, 'X', || true)
+            .otherwise(|| false)
+            .unwrap_or(false);
+        if copy {
+            if let Some(webview_id) = self.focused_webview_id {
+                let script = COPY_SELECTION_SCRIPT.to_owned();
+                self.run_script(webview_id, ScriptPurpose::CopySelection, script);
+            }
+        }
         let embedder_event = ShortcutMatcher::from_event(key_event.clone())
-            .shortcut(CMD_OR_CONTROL, 'R', || {
+            .optional_shortcut(enabled("reload"), CMD_OR_CONTROL, 'R', || {
                 self.focused_webview_id.map(EmbedderEvent::Reload)
             })
-            .shortcut(CMD_OR_CONTROL, 'L', || {
+            .optional_shortcut(enabled("focus-location"), CMD_OR_CONTROL, 'L', || {
                 if !opts::get().minibrowser {
                     let url = match self.focused_webview() {
                         Some(webview) => webview
@@ -459,7 +1478,7 @@ where
                     input.and_then(|input| {
                         location_bar_input_to_url(&input).and_then(|url| {
                             self.focused_webview_id
-                                .map(|id| EmbedderEvent::LoadUrl(id, url))
+                                .map(|id| EmbedderEvent::LoadUrl(id, checked_navigation_url(url)))
                         })
                     })
                 } else {
@@ -467,16 +1486,32 @@ where
                 }
             })
             // Select the first 8 tabs via shortcuts
-            .shortcut(CMD_OR_CONTROL, '1', || self.focus_webview_by_index(0))
-            .shortcut(CMD_OR_CONTROL, '2', || self.focus_webview_by_index(1))
-            .shortcut(CMD_OR_CONTROL, '3', || self.focus_webview_by_index(2))
-            .shortcut(CMD_OR_CONTROL, '4', || self.focus_webview_by_index(3))
-            .shortcut(CMD_OR_CONTROL, '5', || self.focus_webview_by_index(4))
-            .shortcut(CMD_OR_CONTROL, '6', || self.focus_webview_by_index(5))
-            .shortcut(CMD_OR_CONTROL, '7', || self.focus_webview_by_index(6))
-            .shortcut(CMD_OR_CONTROL, '8', || self.focus_webview_by_index(7))
+            .optional_shortcut(select_tab, CMD_OR_CONTROL, '1', || {
+                self.focus_webview_by_index(0)
+            })
+            .optional_shortcut(select_tab, CMD_OR_CONTROL, '2', || {
+                self.focus_webview_by_index(1)
+            })
+            .optional_shortcut(select_tab, CMD_OR_CONTROL, '3', || {
+                self.focus_webview_by_index(2)
+            })
+            .optional_shortcut(select_tab, CMD_OR_CONTROL, '4', || {
+                self.focus_webview_by_index(3)
+            })
+            .optional_shortcut(select_tab, CMD_OR_CONTROL, '5', || {
+                self.focus_webview_by_index(4)
+            })
+            .optional_shortcut(select_tab, CMD_OR_CONTROL, '6', || {
+                self.focus_webview_by_index(5)
+            })
+            .optional_shortcut(select_tab, CMD_OR_CONTROL, '7', || {
+                self.focus_webview_by_index(6)
+            })
+            .optional_shortcut(select_tab, CMD_OR_CONTROL, '8', || {
+                self.focus_webview_by_index(7)
+            })
             // Cmd/Ctrl 9 is a bit different in that it focuses the last tab instead of the 9th
-            .shortcut(CMD_OR_CONTROL, '9', || {
+            .optional_shortcut(select_tab, CMD_OR_CONTROL, '9', || {
                 let len = self.webviews().len();
                 if len > 0 {
                     self.focus_webview_by_index(len - 1)
@@ -484,28 +1519,45 @@ where
                     None
                 }
             })
-            .shortcut(Modifiers::CONTROL, Key::PageDown, || {
-                let i = self.get_focused_webview_index()?;
-                self.focus_webview_by_index((i + 1) % self.webviews().len())
-            })
-            .shortcut(Modifiers::CONTROL, Key::PageUp, || {
-                let index = self.get_focused_webview_index()?;
-                let new_index = if index == 0 {
-                    self.webviews().len() - 1
-                } else {
-                    index - 1
-                };
-                self.focus_webview_by_index(new_index)
-            })
-            .shortcut(CMD_OR_CONTROL, 'W', || {
+            .optional_shortcut(
+                enabled("next-tab"),
+                Modifiers::CONTROL,
+                Key::PageDown,
+                || {
+                    let i = self.get_focused_webview_index()?;
+                    self.focus_webview_by_index((i + 1) % self.webviews().len())
+                },
+            )
+            .optional_shortcut(
+                enabled("previous-tab"),
+                Modifiers::CONTROL,
+                Key::PageUp,
+                || {
+                    let index = self.get_focused_webview_index()?;
+                    let new_index = if index == 0 {
+                        self.webviews().len() - 1
+                    } else {
+                        index - 1
+                    };
+                    self.focus_webview_by_index(new_index)
+                },
+            )
+            .optional_shortcut(enabled("close-tab"), CMD_OR_CONTROL, 'W', || {
                 self.focused_webview_id.map(EmbedderEvent::CloseWebView)
             })
-            .shortcut(CMD_OR_CONTROL, 'T', || {
-                let url = ServoUrl::parse("moto:newtab").unwrap();
-                Some(EmbedderEvent::NewWebView(url, WebViewId::new()))
+            .optional_shortcut(
+                enabled("new-tab") && !self.app_mode,
+                CMD_OR_CONTROL,
+                'T',
+                || {
+                    let url = ServoUrl::parse("moto:newtab").unwrap();
+                    Some(EmbedderEvent::NewWebView(url, WebViewId::new()))
+                },
+            )
+            .optional_shortcut(enabled("quit"), CMD_OR_CONTROL, 'Q', || {
+                Some(EmbedderEvent::Quit)
             })
-            .shortcut(CMD_OR_CONTROL, 'Q', || Some(EmbedderEvent::Quit))
-            .shortcut(CMD_OR_CONTROL, 'P', || {
+            .optional_shortcut(enabled("sampling-profiler"), CMD_OR_CONTROL, 'P', || {
                 let rate = env::var("SAMPLING_RATE")
                     .ok()
                     .and_then(|s| s.parse().ok())
@@ -519,30 +1571,48 @@ where
                     Duration::from_secs(duration),
                 ))
             })
-            .shortcut(Modifiers::CONTROL, Key::F9, || {
-                Some(EmbedderEvent::CaptureWebRender)
-            })
-            .shortcut(Modifiers::CONTROL, Key::F10, || {
-                Some(EmbedderEvent::ToggleWebRenderDebug(
-                    WebRenderDebugOption::RenderTargetDebug,
-                ))
-            })
-            .shortcut(Modifiers::CONTROL, Key::F11, || {
-                Some(EmbedderEvent::ToggleWebRenderDebug(
-                    WebRenderDebugOption::TextureCacheDebug,
-                ))
-            })
-            .shortcut(Modifiers::CONTROL, Key::F12, || {
-                Some(EmbedderEvent::ToggleWebRenderDebug(
-                    WebRenderDebugOption::Profiler,
-                ))
-            })
-            .shortcut(CMD_OR_ALT, Key::ArrowRight, || {
+            .optional_shortcut(
+                enabled("webrender-debug"),
+                Modifiers::CONTROL,
+                Key::F9,
+                || Some(EmbedderEvent::CaptureWebRender),
+            )
+            .optional_shortcut(
+                enabled("webrender-debug"),
+                Modifiers::CONTROL,
+                Key::F10,
+                || {
+                    Some(EmbedderEvent::ToggleWebRenderDebug(
+                        WebRenderDebugOption::RenderTargetDebug,
+                    ))
+                },
+            )
+            .optional_shortcut(
+                enabled("webrender-debug"),
+                Modifiers::CONTROL,
+                Key::F11,
+                || {
+                    Some(EmbedderEvent::ToggleWebRenderDebug(
+                        WebRenderDebugOption::TextureCacheDebug,
+                    ))
+                },
+            )
+            .optional_shortcut(
+                enabled("webrender-debug"),
+                Modifiers::CONTROL,
+                Key::F12,
+                || {
+                    Some(EmbedderEvent::ToggleWebRenderDebug(
+                        WebRenderDebugOption::Profiler,
+                    ))
+                },
+            )
+            .optional_shortcut(enabled("forward"), CMD_OR_ALT, Key::ArrowRight, || {
                 self.focused_webview_id
                     .map(|id| EmbedderEvent::Navigation(id, TraversalDirection::Forward(1)))
             })
             .optional_shortcut(
-                cfg!(not(target_os = "windows")),
+                cfg!(not(target_os = "windows")) && enabled("forward"),
                 CMD_OR_CONTROL,
                 ']',
                 || {
@@ -550,12 +1620,12 @@ where
                         .map(|id| EmbedderEvent::Navigation(id, TraversalDirection::Forward(1)))
                 },
             )
-            .shortcut(CMD_OR_ALT, Key::ArrowLeft, || {
+            .optional_shortcut(enabled("back"), CMD_OR_ALT, Key::ArrowLeft, || {
                 self.focused_webview_id
                     .map(|id| EmbedderEvent::Navigation(id, TraversalDirection::Back(1)))
             })
             .optional_shortcut(
-                cfg!(not(target_os = "windows")),
+                cfg!(not(target_os = "windows")) && enabled("back"),
                 CMD_OR_CONTROL,
                 '[',
                 || {
@@ -575,6 +1645,9 @@ where
             })
             .flatten();
         if let Some(event) = embedder_event {
+            if let EmbedderEvent::NewWebView(_, webview_id) = event {
+                self.chrome_opened_webviews.insert(webview_id);
+            }
             self.event_queue.push(event);
         }
     }
@@ -582,18 +1655,6 @@ where
     /// Handle key events after they have been handled by Servo.
     fn handle_key_from_servo(&mut self, webview_id: Option<WebViewId>, event: KeyboardEvent) {
         ShortcutMatcher::from_event(event)
-            .shortcut(CMD_OR_CONTROL, '=', || {
-                self.event_queue.push(EmbedderEvent::Zoom(1.1))
-            })
-            .shortcut(CMD_OR_CONTROL, '+', || {
-                self.event_queue.push(EmbedderEvent::Zoom(1.1))
-            })
-            .shortcut(CMD_OR_CONTROL, '-', || {
-                self.event_queue.push(EmbedderEvent::Zoom(1.0 / 1.1))
-            })
-            .shortcut(CMD_OR_CONTROL, '0', || {
-                self.event_queue.push(EmbedderEvent::ResetZoom)
-            })
             .shortcut(Modifiers::empty(), Key::PageDown, || {
                 let scroll_location = ScrollLocation::Delta(Vector2D::new(
                     0.0,
@@ -659,8 +1720,17 @@ where
             .and_then(|id| self.webviews.get(&id))
             .map(|webview| webview.rect.min.ceil().to_i32())
             .unwrap_or(Point2D::zero());
-        let event = EmbedderEvent::Scroll(scroll_location, origin, phase);
-        self.event_queue.push(event);
+        match scroll_location {
+            ScrollLocation::Delta(delta)
+                if phase == TouchEventType::Move && Self::smooth_scrolling_enabled() =>
+            {
+                self.start_smooth_scroll(delta, origin);
+            },
+            _ => {
+                let event = EmbedderEvent::Scroll(scroll_location, origin, phase);
+                self.event_queue.push(event);
+            },
+        }
     }
 
     /// Returns true if the caller needs to manually present a new frame.
@@ -669,7 +1739,7 @@ where
         events: Drain<'_, (Option<WebViewId>, EmbedderMsg)>,
     ) -> ServoEventResponse {
         let mut need_present = self.load_status() != LoadStatus::LoadComplete;
-        let mut need_update = false;
+        let mut need_update = self.receive_script_results();
         for (webview_id, msg) in events {
             if let Some(webview_id) = webview_id {
                 trace_embedder_msg!(msg, "{webview_id} {msg:?}");
@@ -687,6 +1757,9 @@ where
                     if let Some(webview_id) = webview_id {
                         if let Some(webview) = self.get_mut(webview_id) {
                             webview.title = title.clone();
+                            if let (Some(url), Some(title)) = (&webview.url, &title) {
+                                record_history_title(url, title);
+                            }
                             if webview.focused {
                                 self.window.set_title(&format!(
                                     "{} - Moto",
@@ -799,11 +1872,19 @@ where
                     }
                 },
                 EmbedderMsg::AllowUnload(sender) => {
-                    // Always allow unload for now.
-                    if let Err(e) = sender.send(true) {
-                        let reason = format!("Failed to send AllowUnload response: {}", e);
-                        self.event_queue
-                            .push(EmbedderEvent::SendError(webview_id, reason));
+                    // The minibrowser asks the user, so without it there's nobody to ask.
+                    if !opts::get().minibrowser {
+                        if let Err(e) = sender.send(true) {
+                            let reason = format!("Failed to send AllowUnload response: {}", e);
+                            self.event_queue
+                                .push(EmbedderEvent::SendError(webview_id, reason));
+                        }
+                    } else {
+                        // Only one prompt is shown at a time, so an earlier page stays.
+                        if let Some(previous) = self.unload_prompt.replace(sender) {
+                            let _ = previous.send(false);
+                        }
+                        need_update = true;
                     }
                 },
                 EmbedderMsg::AllowNavigationRequest(pipeline_id, _url) => {
@@ -819,7 +1900,16 @@ where
                         warn!("Failed to send AllowOpeningWebView response: {}", e);
                     };
                 },
+                EmbedderMsg::WebViewOpened(new_webview_id)
+                    if self.abandoned_webviews.contains(&new_webview_id) =>
+                {
+                    // The user was told this tab couldn't open, so close it rather than have it
+                    // turn up later.
+                    self.event_queue
+                        .push(EmbedderEvent::CloseWebView(new_webview_id));
+                },
                 EmbedderMsg::WebViewOpened(new_webview_id) => {
+                    self.opening_webviews.remove(&new_webview_id);
                     let scale = self.window.hidpi_factor().get();
                     let toolbar = self.window.toolbar_height().get();
 
@@ -831,20 +1921,67 @@ where
                     // Make sure to not add duplicates into the creation_order vector.
                     // This can happen as explained in https://github.com/servo/servo/issues/33075
                     let preload_data = self.ensure_preload_data_mut(&new_webview_id).clone();
-                    if let Entry::Vacant(entry) = self.webviews.entry(new_webview_id) {
-                        entry.insert(WebView::new(rect, preload_data));
-                        self.creation_order.push(new_webview_id);
-                        self.event_queue
-                            .push(EmbedderEvent::FocusWebView(new_webview_id));
+                    if !self.webviews.contains_key(&new_webview_id) {
+                        let index = self.new_webview_index(new_webview_id);
+                        self.webviews
+                            .insert(new_webview_id, WebView::new(rect, preload_data));
+                        self.creation_order.insert(index, new_webview_id);
+                        self.session_changed = true;
+
+                        // A webview opened right after the focused one joins its group, if any.
+                        let group_id = self
+                            .focused_webview_id
+                            .filter(|id| index > 0 && self.creation_order[index - 1] == *id)
+                            .and_then(|id| self.tab_group_membership.get(&id).copied());
+                        if let Some(group_id) = group_id {
+                            self.tab_group_membership.insert(new_webview_id, group_id);
+                        }
+
+                        // While a session is being restored, its focused tab stays focused once
+                        // it has opened.
+                        let focus_id = match self.restore_focus {
+                            Some(id)
+                                if self.restoring_webviews.remove(&new_webview_id)
+                                    && self.webviews.contains_key(&id) =>
+                            {
+                                id
+                            },
+                            _ => new_webview_id,
+                        };
+                        if self.restoring_webviews.is_empty() {
+                            self.restore_focus = None;
+                        }
                         self.event_queue
                             .push(EmbedderEvent::MoveResizeWebView(new_webview_id, rect));
-                        self.event_queue
-                            .push(EmbedderEvent::RaiseWebViewToTop(new_webview_id, true));
+                        let background = self.background_webviews.remove(&new_webview_id)
+                            && self.focused_webview_id.is_some();
+                        if background {
+                            let throttled = moto_prefs::get_bool("tabs.background-throttle");
+                            self.event_queue.push(EmbedderEvent::SetWebViewThrottled(
+                                new_webview_id,
+                                throttled,
+                            ));
+                        } else {
+                            self.event_queue.push(EmbedderEvent::FocusWebView(focus_id));
+                            self.event_queue
+                                .push(EmbedderEvent::RaiseWebViewToTop(new_webview_id, true));
+                        }
                     }
                 },
+                EmbedderMsg::WebViewClosed(webview_id)
+                    if self.abandoned_webviews.contains(&webview_id) =>
+                {
+                    self.abandoned_webviews.remove(&webview_id);
+                    self.webview_preload_data.remove(&webview_id);
+                },
                 EmbedderMsg::WebViewClosed(webview_id) => {
+                    self.opening_webviews.remove(&webview_id);
+                    self.recently_focused.retain(|&id| id != webview_id);
                     self.webviews.retain(|&id, _| id != webview_id);
                     self.creation_order.retain(|&id| id != webview_id);
+                    self.tab_group_membership.remove(&webview_id);
+                    self.remove_empty_tab_groups();
+                    self.session_changed = true;
                     self.focused_webview_id = None;
                     if let Some(&newest_webview_id) = self.creation_order.last() {
                         self.event_queue
@@ -853,16 +1990,36 @@ where
                         self.event_queue.push(EmbedderEvent::Quit);
                     }
                 },
+                // A webview may be closed before the message saying it was focused arrives, and
+                // a focused webview that isn't in the tab strip could never be unfocused.
+                EmbedderMsg::WebViewFocused(webview_id)
+                    if !self.webviews.contains_key(&webview_id) =>
+                {
+                    warn!("Ignoring focus of unknown {:?}", webview_id);
+                },
                 EmbedderMsg::WebViewFocused(webview_id) => {
                     for (id, webview) in &mut self.webviews {
                         webview.focused = *id == webview_id;
                     }
                     self.focused_webview_id = Some(webview_id);
+                    self.recently_focused.retain(|&id| id != webview_id);
+                    self.recently_focused.insert(0, webview_id);
+                    self.session_changed = true;
                     need_update = true;
                     // Show the most recently created webview and hide all others.
                     // TODO: Stop doing this once we have full multiple webviews support
                     self.event_queue
                         .push(EmbedderEvent::ShowWebView(webview_id, true));
+                    let zoom = self.webviews[&webview_id].zoom;
+                    self.apply_zoom(zoom);
+
+                    // Slow down timers and animations in background tabs, which are hidden anyway.
+                    let throttle_background = moto_prefs::get_bool("tabs.background-throttle");
+                    for &id in self.webviews.keys() {
+                        let throttled = throttle_background && id != webview_id;
+                        self.event_queue
+                            .push(EmbedderEvent::SetWebViewThrottled(id, throttled));
+                    }
                 },
                 EmbedderMsg::WebViewBlurred => {
                     for webview in self.webviews.values_mut() {
@@ -912,13 +2069,38 @@ where
                         let mut new_history: Option<ServoUrl> = None;
                         if let Some(webview) = self.get_mut(webview_id) {
                             new_history = Some(urls[current].clone());
+                            // The new page hasn't been searched yet.
+                            if let Some(find_bar) = &mut webview.find_bar {
+                                find_bar.matches = None;
+                            }
+                            // Going to another site uses that site's zoom.
+                            let origin = site_origin(&urls[current]);
+                            let zoom_changed = origin != webview.url.as_ref().and_then(site_origin);
                             webview.url = Some(urls[current].clone());
+                            webview.session_history = urls;
+                            webview.session_history_index = current;
+                            if zoom_changed {
+                                webview.zoom = origin
+                                    .and_then(|origin| {
+                                        get_site_setting(&config_dir(), &origin, "zoom")
+                                    })
+                                    .and_then(|zoom| zoom.parse::<f32>().ok())
+                                    .map_or(1.0, |zoom| zoom.clamp(MIN_ZOOM, MAX_ZOOM));
+                                if webview.focused {
+                                    let zoom = webview.zoom;
+                                    self.apply_zoom(zoom);
+                                }
+                            }
+                            self.session_changed = true;
                             need_update = true;
                         } else {
                             let data = self.ensure_preload_data_mut(&webview_id);
                             data.url = Some(urls[current].clone());
                         }
                         if let Some(history) = new_history {
+                            navigation_log::record("navigate", &history, "");
+                            // The title follows in ChangePageTitle.
+                            record_history_entry(&history, "");
                             self.history.push(history);
                         }
                     }
@@ -930,6 +2112,9 @@ where
                     if let Some(webview_id) = webview_id {
                         if let Some(webview) = self.get_mut(webview_id) {
                             webview.load_status = LoadStatus::LoadStart;
+                            webview.load_started = Some(Instant::now());
+                            webview.language = None;
+                            webview.console.clear();
                             need_update = true;
                         }
                     }
@@ -938,14 +2123,44 @@ where
                     if let Some(webview_id) = webview_id {
                         if let Some(webview) = self.get_mut(webview_id) {
                             webview.load_status = LoadStatus::LoadComplete;
+                            if let (Some(started), Some(url)) =
+                                (webview.load_started.take(), &webview.url)
+                            {
+                                let details = format!("{} ms", started.elapsed().as_millis());
+                                navigation_log::record("loaded", url, &details);
+                            }
                             need_update = true;
                         }
+                        self.completed_loads += 1;
+                        let script = "document.documentElement.lang".to_owned();
+                        self.run_script(webview_id, ScriptPurpose::PageLanguage, script);
+                        let script = r#"document.querySelector('link[rel~="manifest"]') !== null"#;
+                        self.run_script(webview_id, ScriptPurpose::HasManifest, script.to_owned());
+                        let script = LINK_CLICKS_SCRIPT.to_owned();
+                        self.run_script(webview_id, ScriptPurpose::LinkClicks, script);
+                        let script = CONSOLE_SCRIPT.to_owned();
+                        self.run_script(webview_id, ScriptPurpose::ConsoleCapture, script);
+                        if moto_prefs::get_bool("spatial-navigation.enabled") {
+                            let script = SPATIAL_NAVIGATION_SCRIPT.to_owned();
+                            self.run_script(webview_id, ScriptPurpose::SpatialNavigation, script);
+                        }
+                        if moto_prefs::get_bool("accessibility.typeaheadfind") {
+                            let script = QUICK_FIND_SCRIPT.to_owned();
+                            self.run_script(webview_id, ScriptPurpose::QuickFind, script);
+                        }
                     }
                 },
                 EmbedderMsg::Shutdown => {
                     self.shutdown_requested = true;
                 },
-                EmbedderMsg::Panic(_reason, _backtrace) => {},
+                EmbedderMsg::Panic(reason, _backtrace) => {
+                    let url = webview_id
+                        .and_then(|webview_id| self.webviews.get(&webview_id))
+                        .and_then(|webview| webview.url.as_ref());
+                    if let Some(url) = url {
+                        navigation_log::record("error", url, &reason);
+                    }
+                },
                 EmbedderMsg::GetSelectedBluetoothDevice(devices, sender) => {
                     let selected = platform_get_selected_devices(devices);
                     if let Err(e) = sender.send(selected) {
@@ -970,7 +2185,7 @@ where
                     };
                 },
                 EmbedderMsg::PromptPermission(prompt, sender) => {
-                    let permission_state = prompt_user(prompt);
+                    let permission_state = self.permission_for_prompt(webview_id, prompt);
                     let _ = sender.send(permission_state);
                 },
                 EmbedderMsg::ShowIME(_kind, _text, _multiline, _rect) => {
@@ -991,11 +2206,22 @@ where
                     // TODO(ferjm): MediaSession support for winit based browsers.
                 },
                 EmbedderMsg::OnDevtoolsStarted(port, _token) => match port {
-                    Ok(p) => info!("Devtools Server running on port {}", p),
+                    Ok(p) => {
+                        info!("Devtools Server running on port {}", p);
+                        self.devtools_port = Some(p);
+                        need_update = true;
+                    },
                     Err(()) => error!("Error running devtools server"),
                 },
                 EmbedderMsg::ShowContextMenu(sender, ..) => {
-                    let _ = sender.send(ContextMenuResult::Ignored);
+                    if opts::get().minibrowser {
+                        // The minibrowser shows the menu, and closes it once the user is done.
+                        self.close_context_menu();
+                        self.context_menu = Some(sender);
+                        need_update = true;
+                    } else {
+                        let _ = sender.send(ContextMenuResult::Ignored);
+                    }
                 },
                 EmbedderMsg::ReadyToPresent(_webview_ids) => {
                     need_present = true;
@@ -1134,6 +2360,150 @@ fn get_selected_files(patterns: Vec<FilterPattern>, multiple_files: bool) -> Opt
         .expect("Thread spawning failed")
 }
 
+/// Returns the app described by the result of [WEB_APP_MANIFEST_SCRIPT].
+fn web_app_from_script_result(result: WebDriverJSResult) -> Result<WebApp, String> {
+    let values = match result {
+        Ok(WebDriverJSValue::ArrayLike(values)) => values,
+        Ok(WebDriverJSValue::Null) => return Err("the page has no usable manifest".to_owned()),
+        result => return Err(format!("{:?}", result)),
+    };
+    let details: Option<Vec<String>> = values
+        .into_iter()
+        .map(|value| match value {
+            WebDriverJSValue::String(s) => Some(s),
+            _ => None,
+        })
+        .collect();
+    let Some([name, start_url, icon_url]) = details.as_deref() else {
+        return Err(format!("unexpected details {:?}", details));
+    };
+    Ok(WebApp {
+        name: name.clone(),
+        start_url: ServoUrl::parse(start_url).map_err(|e| e.to_string())?,
+        icon_url: ServoUrl::parse(icon_url).ok(),
+    })
+}
+
+/// Returns a cURL command for a GET request like the one for the page described by the given
+/// script result, which is its URL, user agent, language, referrer and cookies.
+fn curl_command_from_script_result(result: WebDriverJSResult) -> Result<String, String> {
+    let values = match result {
+        Ok(WebDriverJSValue::ArrayLike(values)) => values,
+        result => return Err(format!("{:?}", result)),
+    };
+    let details: Option<Vec<String>> = values
+        .into_iter()
+        .map(|value| match value {
+            WebDriverJSValue::String(s) => Some(s),
+            _ => None,
+        })
+        .collect();
+    let Some([url, user_agent, language, referrer, cookies]) = details.as_deref() else {
+        return Err(format!("unexpected details {:?}", details));
+    };
+
+    let mut command = format!("curl {}", shell_quote(url));
+    let headers = [
+        ("User-Agent", user_agent),
+        ("Accept-Language", language),
+        ("Referer", referrer),
+        ("Cookie", cookies),
+    ];
+    for (name, value) in headers {
+        if !value.is_empty() {
+            command += &format!(" -H {}", shell_quote(&format!("{}: {}", name, value)));
+        }
+    }
+    Ok(command)
+}
+
+/// Returns the origin that site settings are stored under for the given URL, if it has one.
+fn site_origin(url: &ServoUrl) -> Option<String> {
+    let origin = url.origin();
+    origin.is_tuple().then(|| origin.ascii_serialization())
+}
+
+/// Quotes the given text as a JavaScript string literal.
+fn js_string_literal(text: &str) -> String {
+    let mut literal = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\u{2028}' | '\u{2029}' => literal.push_str(&format!("\\u{:04x}", c as u32)),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// Quotes the given text as a single argument for a POSIX shell.
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+/// Ask where to save the given page text, suggesting a file name based on the page's title.
+fn save_page_text(title: &str, text: &str) {
+    // Only suggest names that are safe to pass to the dialog, which may go through a shell.
+    let name: String = title
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let name = match name.trim_matches('_') {
+        "" => "page",
+        name => name,
+    };
+    let Some(path) = tinyfiledialogs::save_file_dialog_with_filter(
+        "Save Page Text",
+        &format!("{}.txt", name),
+        &["*.txt"],
+        "Text files",
+    ) else {
+        return;
+    };
+    if let Err(e) = std::fs::write(&path, text) {
+        warn!("Failed to save page text to {} ({})", path, e);
+    }
+}
+
+/// Add a visit to the given page to the history DB, if there is one.
+fn record_history_entry(url: &ServoUrl, title: &str) {
+    let config_dir = config_dir();
+    if config_dir.is_empty() || HISTORY_DISABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Err(e) = add_history_entry(&config_dir, url.as_str(), title) {
+        warn!("Failed to add {} to history ({})", url, e);
+    }
+    if HISTORY_VISITS.fetch_add(1, Ordering::Relaxed) % HISTORY_PRUNE_INTERVAL == 0 {
+        let max_entries = moto_prefs::get_int("history.max-entries");
+        let retention_days = moto_prefs::get_int("history.retention-days");
+        if let Err(e) = prune_history(&config_dir, max_entries, retention_days) {
+            warn!("Failed to prune history ({})", e);
+        }
+    }
+}
+
+/// Give the page's entry in the history DB its title, once the page has one.
+fn record_history_title(url: &ServoUrl, title: &str) {
+    let config_dir = config_dir();
+    if config_dir.is_empty() || HISTORY_DISABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Err(e) = set_history_title(&config_dir, url.as_str(), title) {
+        warn!("Failed to set the title of {} in history ({})", url, e);
+    }
+}
+
 // This is a mitigation for #25498, not a verified solution.
 // There may be codepaths in tinyfiledialog.c that this is
 // inadquate against, as it passes the string via shell to
@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use std::net::{Ipv4Addr, Ipv6Addr};
 use std::path::{Path, PathBuf};
 
 use log::warn;
@@ -20,6 +21,24 @@ pub fn parse_url_or_filename(cwd: &Path, input: &str) -> Result<ServoUrl, ()> {
     }
 }
 
+/// Convert an absolute path, such as one picked in a file dialog, to a file URL.
+pub fn file_path_to_url(path: impl AsRef<Path>) -> Option<ServoUrl> {
+    Url::from_file_path(path).ok().map(ServoUrl::from_url)
+}
+
+/// Returns true iff the given URL looks like a file to download rather than a page to show, going
+/// by whether the end of its path has a file extension. Local files, like those picked with Open
+/// File, are never downloads.
+pub fn is_download_url(url: &ServoUrl) -> bool {
+    if !matches!(url.scheme(), "http" | "https") {
+        return false;
+    }
+    url.as_url()
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .is_some_and(|name| name.contains('.'))
+}
+
 pub fn get_default_url(
     url_opt: Option<&str>,
     cwd: impl AsRef<Path>,
@@ -65,20 +84,90 @@ pub fn get_default_url(
 /// If this is not a valid URL, try to "fix" it by adding a scheme or if all else fails,
 /// interpret the string as a search term.
 pub fn location_bar_input_to_url(request: &str) -> Option<ServoUrl> {
+    location_bar_input_to_url_with_search(request, &pref!(shell.searchpage))
+}
+
+/// Like [location_bar_input_to_url], but searching with the given search page, in which `%s` is
+/// replaced by the URL-encoded search terms.
+pub fn location_bar_input_to_url_with_search(request: &str, search_page: &str) -> Option<ServoUrl> {
+    location_bar_input_to_url_without_search(request).or_else(|| {
+        let terms: String =
+            url::form_urlencoded::byte_serialize(request.trim().as_bytes()).collect();
+        ServoUrl::parse(&search_page.replace("%s", &terms)).ok()
+    })
+}
+
+/// Like [location_bar_input_to_url], but returning None for input that would be searched for.
+///
+/// A single word is only taken as a host if it is `localhost`, an IP address or has a port, so
+/// other intranet hosts need a scheme or a trailing slash, like `intranet/`.
+pub fn location_bar_input_to_url_without_search(request: &str) -> Option<ServoUrl> {
     let request = request.trim();
-    ServoUrl::parse(request)
-        .ok()
-        .or_else(|| {
-            if request.starts_with('/') {
-                ServoUrl::parse(&format!("file://{}", request)).ok()
-            } else if request.contains('/') || is_reg_domain(request) {
-                ServoUrl::parse(&format!("https://{}", request)).ok()
-            } else {
-                None
-            }
-        })
-        .or_else(|| {
-            let url = pref!(shell.searchpage).replace("%s", request);
-            ServoUrl::parse(&url).ok()
-        })
+    // Text with spaces is searched for, unless it is clearly a URL or a path.
+    if request.contains(char::is_whitespace) {
+        if request.starts_with('/') {
+            return ServoUrl::parse(&format!("file://{}", request)).ok();
+        }
+        return ServoUrl::parse(request)
+            .ok()
+            .filter(|url| request.contains("://") || url.scheme() == "data");
+    }
+    // Local servers are usually plain HTTP, and `localhost:8080` would otherwise be taken as a URL
+    // with the scheme `localhost`.
+    if is_local_server(request) {
+        return ServoUrl::parse(&format!("http://{}", request)).ok();
+    }
+    ServoUrl::parse(request).ok().or_else(|| {
+        if request.starts_with('/') {
+            ServoUrl::parse(&format!("file://{}", request)).ok()
+        } else if request.contains('/') || is_reg_domain(request) {
+            ServoUrl::parse(&format!("https://{}", request)).ok()
+        } else {
+            None
+        }
+    })
+}
+
+/// Returns whether the input starts with a host and a port, like `intranet:8080`, or with
+/// `localhost` or an IP address.
+fn is_local_server(request: &str) -> bool {
+    let authority = request.split(['/', '?', '#']).next().unwrap_or_default();
+    let (host, has_port) = match authority.rsplit_once(':') {
+        Some((host, port))
+            if !port.is_empty() && port.bytes().all(|byte| byte.is_ascii_digit()) =>
+        {
+            (host, true)
+        },
+        _ => (authority, false),
+    };
+    let is_ipv6 = host
+        .strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .is_some_and(|host| host.parse::<Ipv6Addr>().is_ok());
+    let is_hostname = !host.is_empty()
+        && host
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'.');
+    (has_port && (is_hostname || is_ipv6))
+        || host.eq_ignore_ascii_case("localhost")
+        || host.parse::<Ipv4Addr>().is_ok()
+        || is_ipv6
+}
+
+/// The media type and decoded contents of a `data:` URL.
+#[derive(Debug, PartialEq)]
+pub struct DataUrl {
+    pub media_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Decode a `data:` URL, or return None if it isn't a well-formed one.
+pub fn parse_data_url(input: &str) -> Option<DataUrl> {
+    let url = data_url::DataUrl::process(input).ok()?;
+    // Fragments aren't part of the data.
+    let (data, _) = url.decode_to_vec().ok()?;
+    Some(DataUrl {
+        media_type: url.mime_type().to_string(),
+        data,
+    })
 }
@@ -0,0 +1,38 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+// Keeps what the page prints with console.log, console.warn and console.error for the JavaScript
+// console panel, which takes the lines with `window.__motoConsole.splice(0)`. Injected into each
+// page by Moto, since Servo only prints them to stdout.
+(function () {
+  if (window.__motoConsole) {
+    return;
+  }
+  // Pages that log a lot while the panel is closed would otherwise keep growing.
+  const MAX_LINES = 1000;
+  const lines = [];
+  window.__motoConsole = lines;
+
+  function format(arg) {
+    if (typeof arg === "object" && arg !== null && !(arg instanceof Error)) {
+      try {
+        return JSON.stringify(arg);
+      } catch (e) {
+        // Cyclic objects can't be stringified.
+      }
+    }
+    return String(arg);
+  }
+
+  for (const [level, prefix] of [["log", ""], ["warn", "Warning: "], ["error", "Error: "]]) {
+    const original = console[level];
+    console[level] = function (...args) {
+      lines.push(prefix + args.map(format).join(" "));
+      if (lines.length > MAX_LINES) {
+        lines.shift();
+      }
+      return original.apply(console, args);
+    };
+  }
+})();
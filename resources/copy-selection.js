@@ -0,0 +1,18 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+// Returns the text selected in the page, or an empty string if there is none or focus is in a
+// form field, which Servo copies from itself. Run by Moto when the user presses Ctrl+C or Ctrl+X
+// (Cmd on macOS) over web content.
+(function () {
+  const active = document.activeElement;
+  if (
+    active &&
+    (active.tagName === "INPUT" || active.tagName === "TEXTAREA" || active.isContentEditable)
+  ) {
+    return "";
+  }
+  const selection = document.getSelection();
+  return selection ? selection.toString() : "";
+})()
@@ -0,0 +1,90 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+// Finds text in the page for Moto's find bar, highlighting every match and moving to the next or
+// previous one, which is highlighted differently and scrolled into view. Moto appends the search
+// and the direction as arguments when it runs this. An empty search removes the highlights.
+// Returns the number of the current match, counting from one, and the number of matches.
+(function (query, forward) {
+  const MATCH_STYLE = "background: #ffeb3b; color: black;";
+  const CURRENT_MATCH_STYLE = "background: #ff9632; color: black;";
+
+  if (!window.__motoFind) {
+    window.__motoFind = { query: "", marks: [], index: -1 };
+  }
+  const state = window.__motoFind;
+
+  // Puts the highlighted text back as it was.
+  function removeMarks() {
+    for (const mark of state.marks) {
+      const parent = mark.parentNode;
+      if (!parent) {
+        continue;
+      }
+      parent.replaceChild(document.createTextNode(mark.textContent), mark);
+      parent.normalize();
+    }
+    state.marks = [];
+  }
+
+  if (query === "" || !document.body) {
+    removeMarks();
+    state.query = "";
+    state.index = -1;
+    return [0, 0];
+  }
+
+  // Search again when the search changes, or when the page has replaced any of the matches.
+  const stale = state.marks.some((mark) => !mark.isConnected);
+  if (query !== state.query || stale) {
+    removeMarks();
+    const needle = query.toLowerCase();
+    const walker = document.createTreeWalker(document.body, NodeFilter.SHOW_TEXT);
+    const found = [];
+    for (let node = walker.nextNode(); node; node = walker.nextNode()) {
+      const element = node.parentElement;
+      if (!element || ["SCRIPT", "STYLE", "NOSCRIPT", "TEXTAREA"].includes(element.tagName)) {
+        continue;
+      }
+      const rect = element.getBoundingClientRect();
+      if (rect.width === 0 && rect.height === 0) {
+        continue;
+      }
+      const haystack = node.data.toLowerCase();
+      let index = haystack.indexOf(needle);
+      while (index !== -1) {
+        found.push([node, index]);
+        index = haystack.indexOf(needle, index + needle.length);
+      }
+    }
+    // Wrap the matches from the last one back, so that splitting a text node leaves the offsets
+    // of the earlier matches in it alone.
+    for (let i = found.length - 1; i >= 0; i--) {
+      const [node, start] = found[i];
+      const text = node.splitText(start);
+      text.splitText(query.length);
+      const mark = document.createElement("mark");
+      mark.setAttribute("style", MATCH_STYLE);
+      text.parentNode.replaceChild(mark, text);
+      mark.appendChild(text);
+      state.marks.unshift(mark);
+    }
+    state.query = query;
+    // A new search starts at the first match, or the last when going backwards.
+    state.index = forward ? -1 : state.marks.length;
+  }
+
+  const count = state.marks.length;
+  if (count === 0) {
+    return [0, 0];
+  }
+  if (state.marks[state.index]) {
+    state.marks[state.index].setAttribute("style", MATCH_STYLE);
+  }
+  state.index = (state.index + (forward ? 1 : -1) + count) % count;
+  const mark = state.marks[state.index];
+  mark.setAttribute("style", CURRENT_MATCH_STYLE);
+  mark.scrollIntoView({ block: "center", inline: "nearest" });
+  return [state.index + 1, count];
+})
@@ -0,0 +1,38 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+// Opens links in a new tab when they are middle-clicked or Ctrl-clicked (Cmd-clicked on macOS),
+// including links to moto: pages. Injected into each page by Moto, since Servo follows those
+// clicks in the same tab or not at all.
+(function () {
+  if (window.__motoLinkClicks) {
+    return;
+  }
+  window.__motoLinkClicks = true;
+
+  const isMac = navigator.platform.startsWith("Mac");
+
+  function openInNewTab(event) {
+    if (event.defaultPrevented) {
+      return;
+    }
+    const link = event.target.closest && event.target.closest("a[href], area[href]");
+    if (!link || link.href.startsWith("javascript:")) {
+      return;
+    }
+    event.preventDefault();
+    window.open(link.href, "_blank", "noopener");
+  }
+
+  document.addEventListener("click", (event) => {
+    if (event.button === 0 && (isMac ? event.metaKey : event.ctrlKey)) {
+      openInNewTab(event);
+    }
+  });
+  document.addEventListener("auxclick", (event) => {
+    if (event.button === 1) {
+      openInNewTab(event);
+    }
+  });
+})();
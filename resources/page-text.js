@@ -0,0 +1,49 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+// Returns the readable text of the page, without scripts or styles and with whitespace collapsed.
+// Run by Moto for the Copy Page Text and Save Page Text actions.
+(function () {
+  const skipped = new Set(["SCRIPT", "STYLE", "NOSCRIPT", "TEMPLATE", "SVG", "IFRAME"]);
+  const blocks = new Set([
+    "ADDRESS", "ARTICLE", "ASIDE", "BLOCKQUOTE", "DD", "DIV", "DL", "DT", "FIGCAPTION", "FIGURE",
+    "FOOTER", "FORM", "H1", "H2", "H3", "H4", "H5", "H6", "HEADER", "HR", "LI", "MAIN", "NAV",
+    "OL", "P", "PRE", "SECTION", "TABLE", "TR", "UL",
+  ]);
+
+  let text = "";
+  function walk(node, pre) {
+    // Line breaks in the page's source aren't line breaks on the page, except in <pre>.
+    if (node.nodeType === Node.TEXT_NODE) {
+      text += pre ? node.data : node.data.replace(/\s+/g, " ");
+      return;
+    }
+    if (node.nodeType !== Node.ELEMENT_NODE || skipped.has(node.tagName.toUpperCase())) {
+      return;
+    }
+    const tag = node.tagName.toUpperCase();
+    if (tag === "BR") {
+      text += "\n";
+    }
+    if (blocks.has(tag)) {
+      text += "\n";
+    }
+    for (const child of node.childNodes) {
+      walk(child, pre || tag === "PRE");
+    }
+    if (blocks.has(tag)) {
+      text += "\n";
+    } else if (tag === "TD" || tag === "TH") {
+      text += " ";
+    }
+  }
+  walk(document.body || document.documentElement, false);
+
+  return text
+    .split("\n")
+    .map((line) => line.replace(/\s+/g, " ").trim())
+    .join("\n")
+    .replace(/\n{3,}/g, "\n\n")
+    .trim();
+})()
@@ -0,0 +1,118 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+// Quick find: typing "/" searches the page's text as you type, and "'" searches links only.
+// Injected into each page by Moto when the accessibility.typeaheadfind pref is set.
+(function () {
+  if (window.__motoQuickFind) {
+    return;
+  }
+  window.__motoQuickFind = true;
+
+  const indicator = document.createElement("div");
+  indicator.style.cssText =
+    "position: fixed; left: 0; bottom: 0; z-index: 2147483647; padding: 2px 8px;" +
+    "font: 13px sans-serif; color: #000; background: #f0f0f0; border: 1px solid #aaa;" +
+    "display: none;";
+
+  // The current search, or null if quick find isn't active.
+  let query = null;
+  let linksOnly = false;
+
+  function isEditable(element) {
+    return element.isContentEditable ||
+      element.tagName === "TEXTAREA" ||
+      element.tagName === "SELECT" ||
+      (element.tagName === "INPUT" && !["button", "checkbox", "radio", "submit", "reset"].includes(element.type));
+  }
+
+  function isVisible(node) {
+    const element = node.parentElement;
+    if (!element || indicator.contains(element)) {
+      return false;
+    }
+    const rect = element.getBoundingClientRect();
+    return rect.width > 0 && rect.height > 0;
+  }
+
+  // Selects the first match for the current query and scrolls it into view, returning false if
+  // there isn't one.
+  function findFirst() {
+    const needle = query.toLowerCase();
+    const walker = document.createTreeWalker(document.body, NodeFilter.SHOW_TEXT);
+    for (let node = walker.nextNode(); node; node = walker.nextNode()) {
+      if (linksOnly && !node.parentElement?.closest("a[href]")) {
+        continue;
+      }
+      const index = node.data.toLowerCase().indexOf(needle);
+      if (index === -1 || !isVisible(node)) {
+        continue;
+      }
+      const range = document.createRange();
+      range.setStart(node, index);
+      range.setEnd(node, index + needle.length);
+      const selection = window.getSelection();
+      selection.removeAllRanges();
+      selection.addRange(range);
+      node.parentElement.scrollIntoView({ block: "center", inline: "nearest" });
+      if (linksOnly) {
+        node.parentElement.closest("a[href]").focus();
+      }
+      return true;
+    }
+    return false;
+  }
+
+  function update() {
+    const found = query === "" || findFirst();
+    indicator.textContent = (linksOnly ? "Quick find (links only): " : "Quick find: ") + query +
+      (found ? "" : " (not found)");
+    indicator.style.display = "block";
+  }
+
+  function dismiss() {
+    query = null;
+    indicator.style.display = "none";
+  }
+
+  document.addEventListener("keydown", (event) => {
+    if (event.altKey || event.ctrlKey || event.metaKey) {
+      return;
+    }
+    if (query === null) {
+      const current = document.activeElement;
+      if ((event.key !== "/" && event.key !== "'") || (current && isEditable(current))) {
+        return;
+      }
+      if (!indicator.isConnected) {
+        document.documentElement.appendChild(indicator);
+      }
+      query = "";
+      linksOnly = event.key === "'";
+    } else if (event.key === "Escape") {
+      dismiss();
+    } else if (event.key === "Backspace") {
+      query = query.slice(0, -1);
+    } else if (event.key === "Enter") {
+      // Leave the match selected (or the link focused) for the page to act on.
+      dismiss();
+      return;
+    } else if (event.key.length === 1) {
+      query += event.key;
+    } else {
+      return;
+    }
+    event.preventDefault();
+    event.stopPropagation();
+    if (query !== null) {
+      update();
+    }
+  }, true);
+
+  document.addEventListener("mousedown", () => {
+    if (query !== null) {
+      dismiss();
+    }
+  }, true);
+})();
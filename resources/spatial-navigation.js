@@ -0,0 +1,82 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+// Moves focus between links and controls with the arrow keys, based on where they are on screen.
+// Injected into each page by Moto when the spatial-navigation.enabled pref is set.
+(function () {
+  if (window.__motoSpatialNavigation) {
+    return;
+  }
+  window.__motoSpatialNavigation = true;
+
+  const style = document.createElement("style");
+  style.textContent = ":focus { outline: 3px solid #1a73e8 !important; outline-offset: 2px; }";
+  document.documentElement.appendChild(style);
+
+  const directions = {
+    ArrowUp: [0, -1],
+    ArrowDown: [0, 1],
+    ArrowLeft: [-1, 0],
+    ArrowRight: [1, 0],
+  };
+  const focusable =
+    "a[href], area[href], button, input, select, textarea, [tabindex]:not([tabindex='-1'])";
+
+  function isEditable(element) {
+    return element.isContentEditable ||
+      element.tagName === "TEXTAREA" ||
+      element.tagName === "SELECT" ||
+      (element.tagName === "INPUT" && !["button", "checkbox", "radio", "submit", "reset"].includes(element.type));
+  }
+
+  function center(rect) {
+    return [rect.left + rect.width / 2, rect.top + rect.height / 2];
+  }
+
+  document.addEventListener("keydown", (event) => {
+    const direction = directions[event.key];
+    if (!direction || event.altKey || event.ctrlKey || event.metaKey || event.shiftKey) {
+      return;
+    }
+    const current = document.activeElement;
+    if (current && isEditable(current)) {
+      return;
+    }
+
+    const candidates = Array.from(document.querySelectorAll(focusable)).filter((element) => {
+      const rect = element.getBoundingClientRect();
+      return element !== current && !element.disabled && rect.width > 0 && rect.height > 0;
+    });
+    let best = null;
+    if (!current || current === document.body) {
+      // Nothing is focused yet, so start with the first control in view.
+      best = candidates.find((element) => {
+        const rect = element.getBoundingClientRect();
+        return rect.bottom > 0 && rect.top < window.innerHeight;
+      });
+    } else {
+      const [fromX, fromY] = center(current.getBoundingClientRect());
+      let bestScore = Infinity;
+      for (const element of candidates) {
+        const [x, y] = center(element.getBoundingClientRect());
+        const along = (x - fromX) * direction[0] + (y - fromY) * direction[1];
+        if (along <= 0) {
+          continue;
+        }
+        const across = Math.abs((x - fromX) * direction[1] + (y - fromY) * direction[0]);
+        const score = along + 2 * across;
+        if (score < bestScore) {
+          bestScore = score;
+          best = element;
+        }
+      }
+    }
+
+    if (best) {
+      event.preventDefault();
+      best.focus();
+      best.scrollIntoView({ block: "nearest", inline: "nearest" });
+    }
+  }, true);
+})();
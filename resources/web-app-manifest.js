@@ -0,0 +1,42 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+// Reads the page's web app manifest, returning the app's name, start URL and largest PNG icon
+// (or "" if it has none). Run by Moto when the user installs the site.
+(function () {
+  const link = document.querySelector('link[rel~="manifest"]');
+  if (!link) {
+    return null;
+  }
+  const request = new XMLHttpRequest();
+  request.open("GET", link.href, false);
+  request.send();
+  if (request.status !== 200) {
+    return null;
+  }
+  const manifest = JSON.parse(request.responseText);
+  const base = request.responseURL || link.href;
+
+  const name = manifest.name || manifest.short_name || document.title || location.host;
+  // The start URL has to be on the same origin as the page, or it's ignored.
+  let startUrl = new URL(manifest.start_url || location.href, base);
+  if (startUrl.origin !== location.origin) {
+    startUrl = new URL(location.href);
+  }
+
+  let icon = "";
+  let iconSize = -1;
+  for (const entry of manifest.icons || []) {
+    if (!entry.src || (entry.type && entry.type !== "image/png")) {
+      continue;
+    }
+    const sizes = String(entry.sizes || "").split(/\s+/).map((size) => parseInt(size, 10) || 0);
+    const size = Math.max(0, ...sizes);
+    if (size > iconSize) {
+      icon = new URL(entry.src, base).href;
+      iconSize = size;
+    }
+  }
+  return [String(name), startUrl.href, icon];
+})()
@@ -4,7 +4,13 @@
 
 use std::path::Path;
 
-use crate::parser::{get_default_url, location_bar_input_to_url, parse_url_or_filename};
+use servo::servo_url::ServoUrl;
+
+use crate::parser::{
+    file_path_to_url, get_default_url, is_download_url, location_bar_input_to_url,
+    location_bar_input_to_url_with_search, location_bar_input_to_url_without_search,
+    parse_data_url, parse_url_or_filename,
+};
 
 #[cfg(not(target_os = "windows"))]
 const FAKE_CWD: &str = "/fake/cwd";
@@ -54,6 +60,57 @@ fn test_file_path_parsing() {
     );
 }
 
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn test_file_path_to_url() {
+    assert!(file_path_to_url("relative/page.html").is_none());
+
+    let url = file_path_to_url("/home/user/My Pages/page one.html").unwrap();
+    assert_eq!(url.as_str(), "file:///home/user/My%20Pages/page%20one.html");
+
+    let url = file_path_to_url("/home/user/Документы/страница.html").unwrap();
+    assert_eq!(url.scheme(), "file");
+    assert_eq!(
+        url.to_file_path().unwrap(),
+        Path::new("/home/user/Документы/страница.html")
+    );
+    assert!(url.as_str().is_ascii());
+}
+
+#[test]
+#[cfg(target_os = "windows")]
+fn test_file_path_to_url() {
+    assert!(file_path_to_url("relative\\page.html").is_none());
+
+    let url = file_path_to_url("C:\\Users\\user\\My Pages\\page one.html").unwrap();
+    assert_eq!(
+        url.as_str(),
+        "file:///C:/Users/user/My%20Pages/page%20one.html"
+    );
+
+    let url = file_path_to_url("D:\\Документы\\страница.html").unwrap();
+    assert_eq!(url.path_segments().unwrap().next(), Some("D:"));
+    assert_eq!(
+        url.to_file_path().unwrap(),
+        Path::new("D:\\Документы\\страница.html")
+    );
+    assert!(url.as_str().is_ascii());
+}
+
+#[test]
+fn test_opened_files_are_not_downloads() {
+    let path = std::env::temp_dir().join("page.html");
+    let url = file_path_to_url(&path).unwrap();
+    assert!(!is_download_url(&url));
+    let url = file_path_to_url(path.with_extension("pdf")).unwrap();
+    assert!(!is_download_url(&url));
+
+    let download = |url: &str| is_download_url(&ServoUrl::parse(url).unwrap());
+    assert!(!download("https://servo.org/releases/"));
+    assert!(download("https://servo.org/servo-latest.tar.gz"));
+    assert!(download("https://servo.org/manual.pdf"));
+}
+
 #[test]
 #[cfg(not(target_os = "windows"))]
 // Windows file paths can't contain ?
@@ -208,6 +265,64 @@ fn test_cmdline_and_location_bar_url() {
     );
 }
 
+#[test]
+fn test_location_bar_url_with_search() {
+    let search_page = "https://en.wikipedia.org/w/index.php?search=%s";
+    assert_eq!(
+        location_bar_input_to_url_with_search("dragonfruit", search_page)
+            .unwrap()
+            .into_string(),
+        "https://en.wikipedia.org/w/index.php?search=dragonfruit",
+    );
+    // Only search terms use the search page.
+    assert_eq!(
+        location_bar_input_to_url_with_search("nic.md", search_page)
+            .unwrap()
+            .into_string(),
+        "https://nic.md/",
+    );
+    assert!(location_bar_input_to_url_without_search("dragonfruit").is_none());
+    // Search terms are encoded, so they can contain characters that mean something in a URL.
+    assert_eq!(
+        location_bar_input_to_url_with_search("c++ & rust?", search_page)
+            .unwrap()
+            .into_string(),
+        "https://en.wikipedia.org/w/index.php?search=c%2B%2B+%26+rust%3F",
+    );
+}
+
+#[test]
+fn test_location_bar_url_edge_cases() {
+    let url = |input| location_bar_input_to_url_without_search(input).map(|url| url.into_string());
+    assert_eq!(url("localhost"), Some("http://localhost/".to_owned()));
+    assert_eq!(
+        url("localhost:8080"),
+        Some("http://localhost:8080/".to_owned())
+    );
+    assert_eq!(
+        url("intranet:8080/wiki"),
+        Some("http://intranet:8080/wiki".to_owned())
+    );
+    assert_eq!(url("192.168.1.1"), Some("http://192.168.1.1/".to_owned()));
+    assert_eq!(url("[::1]:3000"), Some("http://[::1]:3000/".to_owned()));
+    // A single word is searched for, unless it ends with a slash.
+    assert_eq!(url("intranet"), None);
+    assert_eq!(url("intranet/"), Some("https://intranet/".to_owned()));
+    // Text with spaces is searched for, even if it looks like a domain or a path.
+    assert_eq!(url("servo.org rendering"), None);
+    assert_eq!(url("what is a/b testing"), None);
+    assert_eq!(url("define: browser"), None);
+    assert_eq!(
+        url("https://servo.org/search?q=a b"),
+        Some("https://servo.org/search?q=a%20b".to_owned())
+    );
+    assert_eq!(url("about:blank"), Some("about:blank".to_owned()));
+    assert_eq!(
+        url("mailto:a@servo.org"),
+        Some("mailto:a@servo.org".to_owned())
+    );
+}
+
 #[cfg(target_os = "linux")]
 #[test]
 fn test_cmd_and_location_bar_url() {
@@ -218,3 +333,30 @@ fn test_cmd_and_location_bar_url() {
         "file:///dev/null",
     );
 }
+
+#[test]
+fn test_data_url_parsing() {
+    let url = parse_data_url("data:text/html,%3Ch1%3EHello%2C%20world%3C%2Fh1%3E").unwrap();
+    assert_eq!(url.media_type, "text/html");
+    assert_eq!(url.data, b"<h1>Hello, world</h1>");
+
+    let url = parse_data_url("data:text/html;charset=utf-8;base64,PHA+SGk8L3A+").unwrap();
+    assert_eq!(url.media_type, "text/html;charset=utf-8");
+    assert_eq!(url.data, b"<p>Hi</p>");
+
+    let url = parse_data_url("data:image/png;base64,iVBORw0K GgoA").unwrap();
+    assert_eq!(url.media_type, "image/png");
+    assert_eq!(url.data, b"\x89PNG\r\n\x1a\n\0");
+
+    let url = parse_data_url("data:image/svg+xml,%3Csvg%2F%3E").unwrap();
+    assert_eq!(url.media_type, "image/svg+xml");
+    assert_eq!(url.data, b"<svg/>");
+
+    let url = parse_data_url("data:,plain").unwrap();
+    assert_eq!(url.media_type, "text/plain;charset=US-ASCII");
+    assert_eq!(url.data, b"plain");
+
+    assert!(parse_data_url("data:image/png;base64,!!!!").is_none());
+    assert!(parse_data_url("data:text/html").is_none());
+    assert!(parse_data_url("https://example.com/").is_none());
+}